@@ -1,154 +1,420 @@
+use super::DownloadedMod;
+use super::download::ExpectedHashes;
+use super::provider::{
+    Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved, download_version,
+    rewrite_cdn,
+};
+use super::{http, ratelimit};
+use crate::cache::store::Cache;
+use crate::config::{Config, NetworkConfig};
+use crate::error::Error;
+use crate::utils::hash::curseforge_fingerprint;
 use reqwest::blocking::Client;
 use reqwest::header;
 use sanitize_filename::sanitize;
-use serde_json::Value;
-use std::error::Error;
-use std::fs::File;
-use std::path::{Path, PathBuf};
-use std::time::Duration;
-
-pub fn download_curseforge_mod(
-    mod_id: &str, // CurseForge mod ID
-    mc_version: &str,
-    loader: &str,
-    output_dir: &Path,
-    api_key: &str, // CurseForge API Key
-) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // 1. 配置HTTP客户端
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::ACCEPT,
-        header::HeaderValue::from_static("application/json"),
-    );
-    headers.insert(
-        header::USER_AGENT,
-        header::HeaderValue::from_static("MiraMigrator/1.0"),
-    );
-    headers.insert(
-        header::AUTHORIZATION,
-        header::HeaderValue::from_str(&format!("Bearer {}", api_key))?,
-    );
-
-    let client = Client::builder()
-        .default_headers(headers)
-        .timeout(Duration::from_secs(30))
-        .build()?;
-
-    // 2. 验证项目存在
-    let project_url = format!("https://addons-ecs.forgesvc.net/api/v2/addon/{}", mod_id);
-    println!("Requesting mod data from: {}", project_url); // Debugging log
-    let project_res = client.get(&project_url).send()?;
-
-    println!("Response Status: {}", project_res.status()); // Debugging log
-
-    let project_status = project_res.status();
-    if !project_status.is_success() {
-        let error_text = project_res.text()?;
-        println!("Error Response: {}", error_text); // Debugging log
-        return Err(format!(
-            "The mod does not exist or error occurred: {} {}",
-            project_status,
-            error_text
-        )
-        .into());
-    }
-
-    // 3. 获取兼容版本
-    let versions_url = format!("https://addons-ecs.forgesvc.net/api/v2/addon/{}/files", mod_id);
-    println!("Requesting mod versions from: {}", versions_url); // Debugging log
-    let versions_res = client.get(&versions_url).send()?;
-
-    let versions_status = versions_res.status();
-    println!("Response Status: {}", versions_status); // Debugging log
-    if !versions_status.is_success() {
-        let error_text = versions_res.text()?;
-        println!("Error Response: {}", error_text); // Debugging log
-        return Err(format!(
-            "Failed to get mod versions: {} {}",
-            versions_status,
-            error_text
-        )
-        .into());
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// CurseForge 官方 API 地址
+const API_BASE: &str = "https://api.curseforge.com/v1";
+/// 官方 CDN 地址, 配置镜像时替换为 `cdn_url`
+const CDN_HOSTS: [&str; 2] = [
+    "https://edge.forgecdn.net",
+    "https://mediafilez.forgecdn.net",
+];
+/// Minecraft 在 CurseForge 上的游戏 ID
+const MINECRAFT_GAME_ID: &str = "432";
+/// CurseForge "Mods" 分类 ID
+const MODS_CLASS_ID: &str = "6";
+/// CurseForge 文件哈希算法: 1 = SHA-1
+const HASH_ALGO_SHA1: i64 = 1;
+/// CurseForge 在 gameVersions 中使用的加载器名称
+const LOADER_NAMES: [&str; 4] = ["forge", "fabric", "quilt", "neoforge"];
+
+/// CurseForge 下载来源
+pub struct CurseForgeProvider {
+    api_base: String,
+    cdn_url: Option<String>,
+    client: Client,
+    network: NetworkConfig,
+    cache: Cache,
+}
+
+impl CurseForgeProvider {
+    pub fn new(config: &Config, api_key: &str) -> Result<Self, Error> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/json"),
+        );
+        headers.insert(
+            "x-api-key",
+            header::HeaderValue::from_str(api_key).map_err(|e| e.to_string())?,
+        );
+        Ok(CurseForgeProvider {
+            api_base: config
+                .curseforge
+                .api_url
+                .as_deref()
+                .unwrap_or(API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+            cdn_url: config.curseforge.cdn_url.clone(),
+            client: http::build_client(&config.network, headers)?,
+            network: config.network.clone(),
+            cache: Cache::new(&config.cache),
+        })
     }
 
-    let versions_text = versions_res.text()?;
-    let versions: Vec<Value> = serde_json::from_str(&versions_text).map_err(|e| {
-        format!(
-            "JSON parsing failed: {}\nResponse content: {}",
-            e, versions_text
-        )
-    })?;
+    /// 发送 GET 请求并返回响应中的 `data` 字段, 资源不存在时返回 None
+    fn get_data(&self, url: &str, query: &[(&str, String)]) -> Result<Option<Value>, Error> {
+        let text = http::get_text(
+            &self.client,
+            url,
+            query,
+            &self.network,
+            Some(ratelimit::curseforge()),
+            &self.cache,
+        )?;
+        text.map(|text| parse_data(&text)).transpose()
+    }
+
+    /// 查询项目 (数字 ID 直接查询, 否则按 slug 搜索)
+    fn get_project(&self, mod_id: &str) -> Result<Option<Value>, Error> {
+        if mod_id.chars().all(|c| c.is_ascii_digit()) {
+            return self.get_data(&format!("{}/mods/{}", self.api_base, mod_id), &[]);
+        }
+        let results = self.get_data(
+            &format!("{}/mods/search", self.api_base),
+            &[
+                ("gameId", MINECRAFT_GAME_ID.to_string()),
+                ("classId", MODS_CLASS_ID.to_string()),
+                ("slug", mod_id.to_string()),
+            ],
+        )?;
+        Ok(results
+            .as_ref()
+            .and_then(|r| r.as_array())
+            .and_then(|arr| arr.first())
+            .cloned())
+    }
+
+    /// 按文件指纹查询项目 ID
+    fn match_fingerprint(&self, jar: &Path) -> Result<Option<i64>, Error> {
+        let Ok(fingerprint) = curseforge_fingerprint(jar) else {
+            return Ok(None);
+        };
+        let text = http::post_json_text(
+            &self.client,
+            &format!("{}/fingerprints/{}", self.api_base, MINECRAFT_GAME_ID),
+            &json!({ "fingerprints": [fingerprint] }),
+            &self.network,
+            Some(ratelimit::curseforge()),
+            &self.cache,
+        )?;
+        let Some(data) = text.map(|text| parse_data(&text)).transpose()? else {
+            return Ok(None);
+        };
+        Ok(data["exactMatches"]
+            .as_array()
+            .and_then(|matches| matches.first())
+            .and_then(|m| m["id"].as_i64()))
+    }
+}
+
+impl ModProvider for CurseForgeProvider {
+    fn id(&self) -> &'static str {
+        "curseforge"
+    }
+
+    fn name(&self) -> &'static str {
+        "CurseForge"
+    }
+
+    fn identify(&self, mod_id: &str, jar: &Path) -> Result<Option<RemoteProject>, Error> {
+        // 1. 按文件指纹查询, 可识别 Mod ID 与 slug 不一致的项目
+        if let Some(project_id) = self.match_fingerprint(jar)?
+            && let Some(project) = self.get_project(&project_id.to_string())?
+        {
+            return Ok(Some(parse_project(&project)));
+        }
+        // 2. 将 Mod ID 作为项目 ID 或 slug 查询
+        self.find_project(mod_id)
+    }
 
-    // 4. 选择与目标版本兼容的最新版本
-    let version = get_latest_compatible_version(&versions, mc_version, loader)?;
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        Ok(self.get_project(id)?.map(|p| parse_project(&p)))
+    }
 
-    // 5. 获取可下载文件
-    let file = version["file"]
-        .as_object()
-        .and_then(|file| file.get("downloadUrl"))
-        .and_then(|url| url.as_str())
-        .ok_or("Invalid download URL")?;
+    fn search(
+        &self,
+        query: &str,
+        mc_version: &str,
+        loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error> {
+        let mut params = vec![
+            ("gameId", MINECRAFT_GAME_ID.to_string()),
+            ("classId", MODS_CLASS_ID.to_string()),
+            ("searchFilter", query.to_string()),
+            ("gameVersion", mc_version.to_string()),
+        ];
+        if let Some(loader_type) = mod_loader_type(loader) {
+            params.push(("modLoaderType", loader_type.to_string()));
+        }
+        let results = self.get_data(&format!("{}/mods/search", self.api_base), &params)?;
+        Ok(results
+            .as_ref()
+            .and_then(|r| r.as_array())
+            .map(|arr| arr.iter().map(parse_project).collect())
+            .unwrap_or_default())
+    }
 
-    // 6. 安全下载文件
-    let file_name = sanitize(format!(
-        "{}-{}.jar",
-        mod_id,
-        version["name"].as_str().unwrap_or("unknown")
-    ));
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error> {
+        // CurseForge 每次只能按一个游戏版本筛选, 逐个查询后按文件 ID 去重
+        let mut versions: Vec<RemoteVersion> = Vec::new();
+        for mc_version in criteria.game_versions() {
+            let mut query = vec![("gameVersion", mc_version.to_string())];
+            if let Some(loader_type) = mod_loader_type(criteria.loader) {
+                query.push(("modLoaderType", loader_type.to_string()));
+            }
+            let files = self
+                .get_data(
+                    &format!("{}/mods/{}/files", self.api_base, project.id),
+                    &query,
+                )?
+                .ok_or_else(|| Error::NotFound(format!("mod {}", project.slug)))?;
+            for file in files.as_array().map(|f| f.as_slice()).unwrap_or(&[]) {
+                let mut version = parse_file(file, project);
+                if versions.iter().any(|v| v.version_id == version.version_id) {
+                    continue;
+                }
+                version.url = version
+                    .url
+                    .map(|url| rewrite_cdn(url, &CDN_HOSTS, self.cdn_url.as_deref()));
+                versions.push(version);
+            }
+        }
+        Ok(versions)
+    }
 
-    let save_path = output_dir.join(file_name);
-    download_file(&client, file, &save_path)?;
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+        download_version(
+            &self.client,
+            self,
+            resolved,
+            output_dir,
+            &self.network,
+            &self.cache,
+        )
+    }
 
-    Ok(save_path)
+    fn project_url(&self, project_id: &str) -> Option<String> {
+        Some(format!(
+            "https://www.curseforge.com/projects/{}",
+            project_id
+        ))
+    }
 }
 
-// 下载文件函数
-fn download_file(
-    client: &Client,
-    url: &str,
-    path: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Downloading from URL: {}", url); // Debugging log
-    let mut response = client.get(url).send()?;
-    let mut file = File::create(path)?;
-    std::io::copy(&mut response, &mut file)?;
-    Ok(())
+/// 解析响应并取出 `data` 字段
+fn parse_data(text: &str) -> Result<Value, Error> {
+    let json: Value = serde_json::from_str(text).map_err(|e| {
+        Error::Network(format!(
+            "JSON parsing failed: {}\nResponse content: {}",
+            e, text
+        ))
+    })?;
+    Ok(json["data"].clone())
+}
+
+fn parse_project(project: &Value) -> RemoteProject {
+    RemoteProject {
+        id: project["id"].to_string(),
+        slug: project["slug"].as_str().unwrap_or_default().to_string(),
+        title: project["name"].as_str().unwrap_or_default().to_string(),
+        authors: project["authors"]
+            .as_array()
+            .map(|authors| {
+                authors
+                    .iter()
+                    .filter_map(|a| a["name"].as_str())
+                    .map(|a| a.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        downloads: download_count(&project["downloadCount"]),
+    }
+}
+
+/// CurseForge 的下载次数可能以浮点数表示
+fn download_count(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_f64().map(|n| n as u64))
 }
 
-fn get_latest_compatible_version(
-    versions: &[Value],
-    mc_version: &str,
-    loader: &str,
-) -> Result<Value, Box<dyn Error>> {
-    // 按照发布时间降序排序版本
-    let mut sorted_versions = versions.to_vec();
-    sorted_versions.sort_by(|a, b| {
-        let a_time = a["dateAdded"].as_str().unwrap_or("");
-        let b_time = b["dateAdded"].as_str().unwrap_or("");
-        b_time.cmp(a_time) // 降序排列
-    });
-
-    // 查找最新版本，筛选支持目标 Minecraft 版本和加载器
-    sorted_versions
+/// 解析文件信息
+/// CurseForge 将游戏版本与加载器名称 (如 "Fabric") 一同放在 gameVersions 中
+fn parse_file(file: &Value, project: &RemoteProject) -> RemoteVersion {
+    let (loaders, game_versions): (Vec<String>, Vec<String>) = file["gameVersions"]
+        .as_array()
+        .map(|arr| arr.as_slice())
+        .unwrap_or(&[])
         .iter()
-        .find(|v| {
-            let game_versions = v["gameVersions"]
+        .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
+        .partition(|v| LOADER_NAMES.contains(&v.to_ascii_lowercase().as_str()));
+    let display_name = file["displayName"].as_str().unwrap_or_default();
+    let version_number = file["fileName"]
+        .as_str()
+        .and_then(|name| extract_version(name.trim_end_matches(".jar"), project, &game_versions))
+        .or_else(|| extract_version(display_name, project, &game_versions))
+        // 无法提取时保留显示名称并附加文件 ID, 使其无法按版本号比较, 改为按发布时间排序
+        .unwrap_or_else(|| format!("{} (#{})", display_name, file["id"]));
+    RemoteVersion {
+        project_id: project.id.clone(),
+        version_id: file["id"].to_string(),
+        game_versions,
+        loaders: loaders.iter().map(|l| l.to_ascii_lowercase()).collect(),
+        published: file["fileDate"].as_str().unwrap_or_default().to_string(),
+        channel: match file["releaseType"].as_i64() {
+            Some(2) => Channel::Beta,
+            Some(3) => Channel::Alpha,
+            _ => Channel::Release,
+        },
+        file_name: sanitize(
+            file["fileName"]
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("{}-{}.jar", project.slug, version_number)),
+        ),
+        url: file["downloadUrl"].as_str().map(|s| s.to_string()),
+        hashes: ExpectedHashes {
+            sha1: file["hashes"]
                 .as_array()
-                .map(|arr| arr.as_slice())
-                .unwrap_or(&[]);
-            let loaders = v["loaders"]
-                .as_array()
-                .map(|arr| arr.as_slice())
-                .unwrap_or(&[]);
-            // 确保 mc_version 和 loader 都是 &str 类型
-            let contains_mc_version = game_versions
-                .iter()
-                .any(|v| v.as_str().unwrap_or("").trim() == mc_version.trim());
-            let contains_loader = loaders
-                .iter()
-                .any(|v| v.as_str().unwrap_or("").trim() == loader.trim());
-            contains_mc_version && contains_loader
-        })
-        .cloned() // 克隆出匹配的版本
-        .ok_or_else(|| "No compatible version found.".into())
+                .and_then(|hashes| {
+                    hashes
+                        .iter()
+                        .find(|h| h["algo"].as_i64() == Some(HASH_ALGO_SHA1))
+                })
+                .and_then(|h| h["value"].as_str())
+                .map(|s| s.to_string()),
+            sha256: None,
+            sha512: None,
+        },
+        downloads: download_count(&file["downloadCount"]),
+        // 更新日志需要单独请求, 不在文件信息中
+        changelog: None,
+        version_number,
+    }
+}
+
+/// 从文件名或显示名称 (如 `sodium-fabric-0.5.8+mc1.20.1`, `Sodium 0.5.8 for 1.20.1`) 中提取版本号
+/// 去除项目名称, 加载器与文件支持的 Minecraft 版本后, 只剩一个以数字开头的部分时返回该部分
+fn extract_version(
+    name: &str,
+    project: &RemoteProject,
+    game_versions: &[String],
+) -> Option<String> {
+    let slug: Vec<String> = project
+        .slug
+        .split('-')
+        .map(|part| part.to_ascii_lowercase())
+        .collect();
+    let is_game_version = |token: &str| {
+        let token = token.strip_prefix("mc").unwrap_or(token);
+        game_versions
+            .iter()
+            .any(|mc| mc == token || mc.starts_with(&format!("{}.", token)))
+    };
+    let mut candidates = name
+        .split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter_map(|token| {
+            // 去除 `+mc1.20.1` 这类游戏版本构建元数据
+            let token = match token.split_once('+') {
+                Some((version, build)) if is_game_version(&build.to_ascii_lowercase()) => version,
+                _ => token,
+            };
+            let lower = token.to_ascii_lowercase();
+            let version = lower.strip_prefix('v').unwrap_or(&lower);
+            (version.starts_with(|c: char| c.is_ascii_digit())
+                && !is_game_version(&lower)
+                && !slug.contains(&lower))
+            .then(|| token.to_string())
+        });
+    let version = candidates.next()?;
+    candidates.next().is_none().then_some(version)
+}
+
+/// CurseForge 的加载器类型编号
+fn mod_loader_type(loader: &str) -> Option<u8> {
+    match loader {
+        "forge" => Some(1),
+        "fabric" => Some(4),
+        "quilt" => Some(5),
+        "neoforge" => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract_version;
+    use crate::api::provider::RemoteProject;
+
+    fn project(slug: &str) -> RemoteProject {
+        RemoteProject {
+            id: "1".to_string(),
+            slug: slug.to_string(),
+            title: String::new(),
+            authors: Vec::new(),
+            downloads: None,
+        }
+    }
+
+    fn extract(name: &str, slug: &str, game_versions: &[&str]) -> Option<String> {
+        let game_versions: Vec<String> = game_versions.iter().map(|v| v.to_string()).collect();
+        extract_version(name, &project(slug), &game_versions)
+    }
+
+    #[test]
+    fn extracts_version_from_file_names() {
+        let cases = [
+            ("sodium-fabric-0.5.8+mc1.20.1", "sodium", "0.5.8"),
+            ("jei-1.20.1-forge-15.2.0.27", "jei", "15.2.0.27"),
+            ("create-1.20.1-0.5.1.f", "create", "0.5.1.f"),
+            ("Botania-1.20.1-443-FORGE", "botania", "443"),
+            ("2dcrafting-v1.2.0", "2dcrafting", "v1.2.0"),
+            ("modmenu-7.2.2", "modmenu", "7.2.2"),
+        ];
+        for (name, slug, expected) in cases {
+            assert_eq!(
+                extract(name, slug, &["1.20.1", "Fabric"]).as_deref(),
+                Some(expected),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn extracts_version_from_display_names() {
+        assert_eq!(
+            extract("Sodium 0.5.8 for 1.20.1", "sodium", &["1.20.1"]).as_deref(),
+            Some("0.5.8")
+        );
+        assert_eq!(
+            extract("[1.20] Example Mod 2.1", "example-mod", &["1.20.1"]).as_deref(),
+            Some("2.1")
+        );
+    }
+
+    #[test]
+    fn ambiguous_names_have_no_version() {
+        // 版本号与 Minecraft 版本相同时无法区分
+        assert_eq!(extract("mymod-1.20.1", "mymod", &["1.20.1"]), None);
+        assert_eq!(extract("mymod-1.2-3.4", "mymod", &["1.20.1"]), None);
+        assert_eq!(extract("Sodium for Fabric", "sodium", &["1.20.1"]), None);
+    }
 }
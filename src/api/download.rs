@@ -0,0 +1,336 @@
+use super::http::{self, backoff_delay};
+use crate::cache::store::Cache;
+use crate::config::NetworkConfig;
+use crate::error::Error;
+use crate::utils::hash::{FileHashes, Hasher};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// 下载进度回调, 参数为已下载字节数与总大小 (未知时为 None)
+type Progress = Box<dyn Fn(u64, Option<u64>)>;
+
+thread_local! {
+    /// 当前线程下载文件时报告进度的回调
+    static PROGRESS: RefCell<Option<Progress>> = const { RefCell::new(None) };
+}
+
+/// 在当前线程中执行 `f`, 期间下载文件时以已下载字节数与总大小 (未知时为 None) 调用 `progress`
+pub fn with_progress<T>(progress: impl Fn(u64, Option<u64>) + 'static, f: impl FnOnce() -> T) -> T {
+    let previous = PROGRESS.replace(Some(Box::new(progress)));
+    let result = f();
+    PROGRESS.set(previous);
+    result
+}
+
+/// 向当前线程的回调报告下载进度
+fn report_progress(done: u64, total: Option<u64>) {
+    PROGRESS.with_borrow(|progress| {
+        if let Some(progress) = progress {
+            progress(done, total);
+        }
+    });
+}
+
+/// 平台提供的文件哈希值, 缺失的哈希不参与校验
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpectedHashes {
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub sha512: Option<String>,
+}
+
+impl ExpectedHashes {
+    /// 检查实际哈希是否与期望一致, 不一致时返回说明
+    fn verify(&self, actual: &FileHashes) -> Result<(), String> {
+        for (name, expected, actual) in [
+            ("sha512", &self.sha512, &actual.sha512),
+            ("sha256", &self.sha256, &actual.sha256),
+            ("sha1", &self.sha1, &actual.sha1),
+        ] {
+            if let Some(expected) = expected
+                && !expected.eq_ignore_ascii_case(actual)
+            {
+                return Err(format!(
+                    "{} mismatch: expected {}, got {}",
+                    name, expected, actual
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 下载文件并校验哈希
+/// 已知 SHA-1 且缓存中存在时直接从缓存复制, 否则下载并在校验后存入缓存
+/// 文件先写入同目录下的 `.part` 临时文件, 校验通过后才重命名为目标文件
+/// 哈希不一致或传输中断时按网络设置退避重新下载
+pub fn download_file(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    expected: &ExpectedHashes,
+    network: &NetworkConfig,
+    cache: &Cache,
+) -> Result<FileHashes, Error> {
+    if let Some(sha1) = &expected.sha1
+        && let Some(cached) = cache.restore_file(sha1, path)
+    {
+        if expected.verify(&cached).is_ok() {
+            let size = fs::metadata(path).map(|m| m.len()).ok();
+            report_progress(size.unwrap_or_default(), size);
+            return Ok(cached);
+        }
+        let _ = fs::remove_file(path);
+    }
+
+    // 本地文件只能由本地来源通过 copy_local 复制, 不信任远程响应与锁文件中的地址
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(Error::Network(format!("unsupported download URL: {}", url)));
+    }
+
+    let part_path = part_path(path);
+    let attempts = network.retries + 1;
+    let mut last_error = Error::Other(String::new());
+    for attempt in 1..=attempts {
+        match download_once(client, url, &part_path, network) {
+            Ok(actual) => match expected.verify(&actual) {
+                Ok(()) => {
+                    fs::rename(&part_path, path)?;
+                    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                    // 缓存写入失败不影响本次下载
+                    let _ = cache.store_file(&actual.sha1, &file_name, url, path);
+                    return Ok(actual);
+                }
+                Err(mismatch) => last_error = Error::HashMismatch(mismatch),
+            },
+            Err(Failure::Interrupted(e)) => last_error = e,
+            // 请求错误已由 http::send 重试过, 其余错误重新下载也无济于事
+            Err(Failure::Fatal(e)) => {
+                let _ = fs::remove_file(&part_path);
+                return Err(e);
+            }
+        }
+        let _ = fs::remove_file(&part_path);
+        if attempt < attempts {
+            let wait = backoff_delay(network, attempt);
+            log::warn!(
+                "Download of {} failed ({}), retrying in {:.1}s ({}/{})...",
+                url,
+                last_error,
+                wait.as_secs_f64(),
+                attempt,
+                network.retries
+            );
+            thread::sleep(wait);
+        }
+    }
+    let failed = format!("download of {} failed after {} attempts", url, attempts);
+    Err(match last_error {
+        Error::Network(detail) => Error::Network(format!("{}: {}", failed, detail)),
+        Error::HashMismatch(detail) => Error::HashMismatch(format!("{}: {}", failed, detail)),
+        other => other,
+    })
+}
+
+/// 单次下载失败的原因
+enum Failure {
+    /// 读取响应体时传输中断, 可以重新下载
+    Interrupted(Error),
+    /// 重新下载也无法解决的错误
+    Fatal(Error),
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        Failure::Fatal(e)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Fatal(e.into())
+    }
+}
+
+/// 从本地仓库复制文件并校验哈希, 仅供本地来源使用
+/// 与 [`download_file`] 一样先写入 `.part` 临时文件, 校验通过后存入缓存
+pub fn copy_local(
+    source: &Path,
+    path: &Path,
+    expected: &ExpectedHashes,
+    cache: &Cache,
+) -> Result<FileHashes, Error> {
+    let file = File::open(source)?;
+    let size = file.metadata().map(|m| m.len()).ok();
+    let part_path = part_path(path);
+    let actual = match write_part(file, size, &part_path) {
+        Ok(actual) => actual,
+        Err(Failure::Interrupted(e) | Failure::Fatal(e)) => {
+            let _ = fs::remove_file(&part_path);
+            return Err(e);
+        }
+    };
+    if let Err(mismatch) = expected.verify(&actual) {
+        let _ = fs::remove_file(&part_path);
+        return Err(Error::HashMismatch(mismatch));
+    }
+    fs::rename(&part_path, path)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let url = format!("file://{}", source.display());
+    let _ = cache.store_file(&actual.sha1, &file_name, &url, path);
+    Ok(actual)
+}
+
+/// 下载一次, 请求本身的临时错误由 [`http::send`] 重试
+fn download_once(
+    client: &Client,
+    url: &str,
+    part_path: &Path,
+    network: &NetworkConfig,
+) -> Result<FileHashes, Failure> {
+    let response = http::send(client.get(url), network, None)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Failure::Fatal(match status {
+            StatusCode::NOT_FOUND => Error::NotFound(url.to_string()),
+            StatusCode::TOO_MANY_REQUESTS => {
+                Error::RateLimited(http::retry_after(status, response.headers()))
+            }
+            status => Error::Network(format!("HTTP {}", status)),
+        }));
+    }
+    let size = response.content_length();
+    write_part(response, size, part_path)
+}
+
+/// 将内容写入临时文件, 边写入边计算哈希并报告进度
+fn write_part(
+    mut reader: impl Read,
+    total: Option<u64>,
+    part_path: &Path,
+) -> Result<FileHashes, Failure> {
+    let mut file = File::create(part_path)?;
+    let mut hasher = Hasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut done = 0;
+    report_progress(done, total);
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| Failure::Interrupted(Error::Network(e.to_string())))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        file.write_all(&buffer[..read])?;
+        done += read as u64;
+        report_progress(done, total);
+    }
+    file.sync_all()?;
+    Ok(hasher.finalize())
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExpectedHashes, download_file};
+    use crate::cache::store::Cache;
+    use crate::config::Config;
+    use crate::error::Error;
+    use crate::utils::hash::Hasher;
+    use crate::utils::testing::TestDir;
+    use reqwest::blocking::Client;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+
+    #[test]
+    fn verifies_expected_hashes() {
+        let mut hasher = Hasher::new();
+        hasher.update(b"abc");
+        let actual = hasher.finalize();
+        assert!(ExpectedHashes::default().verify(&actual).is_ok());
+
+        let mut expected = ExpectedHashes {
+            sha1: Some(ABC_SHA1.to_uppercase()),
+            sha256: None,
+            sha512: Some(actual.sha512.clone()),
+        };
+        assert!(expected.verify(&actual).is_ok());
+        expected.sha256 = Some("00".repeat(32));
+        let mismatch = expected.verify(&actual).unwrap_err();
+        assert!(mismatch.starts_with("sha256 mismatch"), "{}", mismatch);
+    }
+
+    #[test]
+    fn retries_hash_mismatches() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mod.jar", server.server_addr().to_ip().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = request.respond(Response::from_data(b"abd".to_vec()));
+            }
+        });
+
+        let dir = TestDir::new("download-mismatch");
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        config.network.retries = 1;
+        config.network.backoff = Default::default();
+        let expected = ExpectedHashes {
+            sha1: Some(ABC_SHA1.to_string()),
+            ..Default::default()
+        };
+        let path = dir.path().join("mod.jar");
+        let result = download_file(
+            &Client::new(),
+            &url,
+            &path,
+            &expected,
+            &config.network,
+            &Cache::new(&config.cache),
+        );
+        assert!(
+            matches!(result, Err(Error::HashMismatch(_))),
+            "{:?}",
+            result
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(!path.exists());
+        assert!(!dir.path().join("mod.jar.part").exists());
+    }
+
+    #[test]
+    fn rejects_local_urls() {
+        let dir = TestDir::new("download-file-url");
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        let path = dir.path().join("hostname");
+        let result = download_file(
+            &Client::new(),
+            "file:///etc/hostname",
+            &path,
+            &ExpectedHashes::default(),
+            &config.network,
+            &Cache::new(&config.cache),
+        );
+        assert!(matches!(result, Err(Error::Network(_))), "{:?}", result);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,527 @@
+use super::DownloadedMod;
+use super::download::ExpectedHashes;
+use super::http;
+use super::provider::{
+    Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved, download_version,
+    inspect_jar,
+};
+use crate::cache::store::Cache;
+use crate::config::{Config, NetworkConfig};
+use crate::error::Error;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// GitHub 官方 API 地址
+const API_BASE: &str = "https://api.github.com";
+/// 每次列出的 Release 数量
+const RELEASES_PER_PAGE: &str = "30";
+/// 文件名无法判断兼容性时, 最多下载检查的文件数量
+const MAX_INSPECTED_ASSETS: usize = 5;
+/// 可能出现在文件名中的加载器名称
+const LOADER_NAMES: [&str; 4] = ["forge", "fabric", "quilt", "neoforge"];
+/// 不是 Mod 本体的附属文件
+const SKIPPED_SUFFIXES: [&str; 3] = ["-sources.jar", "-javadoc.jar", "-dev.jar"];
+
+/// GitHub Releases 来源
+/// 仅处理在配置中映射了 `owner/repo` 的 Mod
+pub struct GitHubProvider {
+    api_base: String,
+    repos: BTreeMap<String, String>,
+    client: Client,
+    network: NetworkConfig,
+    cache: Cache,
+}
+
+/// 根据文件名判断的兼容性
+enum NameMatch {
+    /// 文件名中写明的可接受的 Minecraft 版本
+    Compatible(String),
+    Incompatible,
+    /// 文件名未同时写明版本与加载器, 需要读取 Jar 中的元数据
+    Unknown,
+}
+
+impl GitHubProvider {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/vnd.github+json"),
+        );
+        let token = config
+            .github
+            .token
+            .clone()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .filter(|t| !t.is_empty());
+        if let Some(token) = token {
+            let mut value = header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|e| e.to_string())?;
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Ok(GitHubProvider {
+            api_base: config
+                .github
+                .api_url
+                .as_deref()
+                .unwrap_or(API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+            repos: config.github.repos.clone(),
+            client: http::build_client(&config.network, headers)?,
+            network: config.network.clone(),
+            cache: Cache::new(&config.cache),
+        })
+    }
+
+    /// 发送 GET 请求并解析 JSON, 资源不存在时返回 None
+    fn get_json(&self, url: &str, query: &[(&str, String)]) -> Result<Option<Value>, Error> {
+        let Some(text) =
+            http::get_text(&self.client, url, query, &self.network, None, &self.cache)?
+        else {
+            return Ok(None);
+        };
+        let json = serde_json::from_str(&text).map_err(|e| {
+            Error::Network(format!(
+                "JSON parsing failed: {}\nResponse content: {}",
+                e, text
+            ))
+        })?;
+        Ok(Some(json))
+    }
+}
+
+impl ModProvider for GitHubProvider {
+    fn id(&self) -> &'static str {
+        "github"
+    }
+
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn identify(&self, mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+        Ok(self.repos.get(mod_id).map(|repo| project(repo)))
+    }
+
+    /// 项目 ID 为 `owner/repo`
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        Ok(id.contains('/').then(|| project(id)))
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        _mc_version: &str,
+        _loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error> {
+        let results = self
+            .get_json(
+                &format!("{}/search/repositories", self.api_base),
+                &[("q", format!("{} in:name", query))],
+            )?
+            .unwrap_or_default();
+        Ok(results["items"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| RemoteProject {
+                        id: item["full_name"].as_str().unwrap_or_default().to_string(),
+                        slug: item["name"].as_str().unwrap_or_default().to_string(),
+                        title: item["full_name"].as_str().unwrap_or_default().to_string(),
+                        authors: item["owner"]["login"]
+                            .as_str()
+                            .map(|owner| vec![owner.to_string()])
+                            .unwrap_or_default(),
+                        downloads: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// 从新到旧检查 Release 中的 Jar 文件, 优先按文件名判断
+    /// 文件名无法判断时下载并读取元数据, 找到兼容版本后不再下载检查更旧的文件
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error> {
+        let releases = self
+            .get_json(
+                &format!("{}/repos/{}/releases", self.api_base, project.id),
+                &[("per_page", RELEASES_PER_PAGE.to_string())],
+            )?
+            .ok_or_else(|| Error::NotFound(format!("repository {}", project.id)))?;
+        let mut versions = Vec::new();
+        let mut inspected = 0;
+        for release in releases.as_array().map(|r| r.as_slice()).unwrap_or(&[]) {
+            if release["draft"].as_bool().unwrap_or(false)
+                || !criteria.pin_matches(release["tag_name"].as_str().unwrap_or_default())
+            {
+                continue;
+            }
+            for asset in release["assets"]
+                .as_array()
+                .map(|a| a.as_slice())
+                .unwrap_or(&[])
+            {
+                let Some(mut version) = parse_asset(release, asset, project) else {
+                    continue;
+                };
+                match match_name(&version.file_name, &version.version_number, criteria) {
+                    NameMatch::Compatible(mc_version) => {
+                        version.game_versions = vec![mc_version];
+                        version.loaders = vec![criteria.loader.to_string()];
+                    }
+                    NameMatch::Incompatible => continue,
+                    NameMatch::Unknown => {
+                        if !versions.is_empty() || inspected >= MAX_INSPECTED_ASSETS {
+                            continue;
+                        }
+                        inspected += 1;
+                        if !inspect_jar(
+                            &self.client,
+                            &mut version,
+                            criteria,
+                            &self.network,
+                            &self.cache,
+                        )? {
+                            continue;
+                        }
+                    }
+                }
+                versions.push(version);
+            }
+        }
+        Ok(versions)
+    }
+
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+        download_version(
+            &self.client,
+            self,
+            resolved,
+            output_dir,
+            &self.network,
+            &self.cache,
+        )
+    }
+
+    fn project_url(&self, project_id: &str) -> Option<String> {
+        Some(format!("https://github.com/{}", project_id))
+    }
+}
+
+fn project(repo: &str) -> RemoteProject {
+    RemoteProject {
+        id: repo.to_string(),
+        slug: repo.rsplit('/').next().unwrap_or(repo).to_string(),
+        title: repo.to_string(),
+        authors: repo
+            .split_once('/')
+            .map(|(owner, _)| vec![owner.to_string()])
+            .unwrap_or_default(),
+        downloads: None,
+    }
+}
+
+/// 解析 Release 中的 Jar 文件, 跳过源码等附属文件
+fn parse_asset(release: &Value, asset: &Value, project: &RemoteProject) -> Option<RemoteVersion> {
+    let file_name = asset["name"].as_str()?;
+    let lower = file_name.to_lowercase();
+    if !lower.ends_with(".jar") || SKIPPED_SUFFIXES.iter().any(|s| lower.ends_with(s)) {
+        return None;
+    }
+    Some(RemoteVersion {
+        project_id: project.id.clone(),
+        version_id: asset["id"].to_string(),
+        version_number: release["tag_name"].as_str().unwrap_or_default().to_string(),
+        game_versions: Vec::new(),
+        loaders: Vec::new(),
+        published: release["published_at"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        // 预发布的 Release 视为测试版
+        channel: if release["prerelease"].as_bool().unwrap_or(false) {
+            Channel::Beta
+        } else {
+            Channel::Release
+        },
+        file_name: sanitize_filename::sanitize(file_name),
+        url: asset["browser_download_url"]
+            .as_str()
+            .map(|s| s.to_string()),
+        hashes: ExpectedHashes {
+            // GitHub 提供形如 `sha256:...` 的摘要
+            sha256: asset["digest"]
+                .as_str()
+                .and_then(|d| d.strip_prefix("sha256:"))
+                .map(|s| s.to_string()),
+            ..ExpectedHashes::default()
+        },
+        downloads: asset["download_count"].as_u64(),
+        changelog: release["body"].as_str().map(|s| s.to_string()),
+    })
+}
+
+/// 根据文件名中的 Minecraft 版本与加载器判断兼容性
+/// 文件名中的版本与 Release 标签 (`tag`) 相同时, 无法区分是 Mod 版本还是 Minecraft 版本, 视为无法判断
+fn match_name(file_name: &str, tag: &str, criteria: &Criteria) -> NameMatch {
+    let loader = criteria.loader;
+    let lower = file_name.to_lowercase();
+    let loaders: Vec<&str> = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|token| LOADER_NAMES.contains(token))
+        .collect();
+    if !loaders.is_empty() && !loaders.contains(&loader) {
+        return NameMatch::Incompatible;
+    }
+    if loaders.is_empty() {
+        return NameMatch::Unknown;
+    }
+    // 版本号前后不能紧跟数字或 `.数字`, 避免 1.20 匹配到 1.20.4
+    criteria
+        .game_versions()
+        .into_iter()
+        .find(|mc_version| {
+            let pattern = format!(
+                r"(^|[^0-9.]){}($|[^0-9.]|\.[^0-9])",
+                regex::escape(mc_version)
+            );
+            Regex::new(&pattern).is_ok_and(|re| re.is_match(&lower))
+        })
+        .filter(|mc_version| {
+            let tag = tag.trim().to_lowercase();
+            tag.strip_prefix('v').unwrap_or(&tag) != *mc_version
+        })
+        .map_or(NameMatch::Unknown, |mc_version| {
+            NameMatch::Compatible(mc_version.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GitHubProvider, NameMatch, match_name, parse_asset, project};
+    use crate::api::provider::{Channel, Criteria, ModProvider};
+    use crate::config::Config;
+    use crate::utils::testing::fabric_jar;
+    use serde_json::json;
+    use std::thread;
+    use tiny_http::{Header, Response, Server};
+
+    fn criteria<'a>(mc_version: &'a str, loader: &'a str) -> Criteria<'a> {
+        Criteria {
+            mc_version,
+            loader,
+            channel: Channel::Release,
+            installed: None,
+            allow_downgrade: true,
+            pin: None,
+            compatible_versions: false,
+        }
+    }
+
+    fn describe(result: NameMatch) -> String {
+        match result {
+            NameMatch::Compatible(mc_version) => mc_version,
+            NameMatch::Incompatible => "incompatible".to_string(),
+            NameMatch::Unknown => "unknown".to_string(),
+        }
+    }
+
+    #[test]
+    fn matches_file_names() {
+        let cases = [
+            // 文件名, Release 标签, 目标版本, 加载器, 结果
+            (
+                "mod-fabric-1.20.4-2.0.jar",
+                "v2.0",
+                "1.20.4",
+                "fabric",
+                "1.20.4",
+            ),
+            (
+                "mod-2.0+mc1.20.4-fabric.jar",
+                "2.0",
+                "1.20.4",
+                "fabric",
+                "1.20.4",
+            ),
+            ("Mod-Fabric-1.20.jar", "2.0", "1.20", "fabric", "1.20"),
+            (
+                "mod-forge-1.20.4-2.0.jar",
+                "2.0",
+                "1.20.4",
+                "fabric",
+                "incompatible",
+            ),
+            (
+                "mod-neoforge-1.20.4.jar",
+                "2.0",
+                "1.20.4",
+                "forge",
+                "incompatible",
+            ),
+            (
+                "mod-fabric+quilt-1.20.4.jar",
+                "2.0",
+                "1.20.4",
+                "quilt",
+                "1.20.4",
+            ),
+            // 版本号边界: 1.20 不匹配 1.20.4, 1.2 不匹配 1.20
+            ("mod-fabric-1.20.4.jar", "2.0", "1.20", "fabric", "unknown"),
+            ("mod-fabric-1.20.jar", "2.0", "1.2", "fabric", "unknown"),
+            ("mod-fabric-11.20.jar", "2.0", "1.20", "fabric", "unknown"),
+            // 没有加载器时无法判断
+            ("mod-1.20.4-2.0.jar", "2.0", "1.20.4", "fabric", "unknown"),
+            // 文件名中的版本是 Mod 自己的版本
+            (
+                "mymod-fabric-1.20.1.jar",
+                "v1.20.1",
+                "1.20.1",
+                "fabric",
+                "unknown",
+            ),
+            (
+                "mymod-fabric-1.20.1.jar",
+                "1.20.1",
+                "1.20.1",
+                "fabric",
+                "unknown",
+            ),
+        ];
+        for (file_name, tag, mc_version, loader, expected) in cases {
+            assert_eq!(
+                describe(match_name(file_name, tag, &criteria(mc_version, loader))),
+                expected,
+                "{} ({})",
+                file_name,
+                tag
+            );
+        }
+    }
+
+    #[test]
+    fn parses_release_assets() {
+        let project = project("owner/mod");
+        let release = json!({
+            "tag_name": "v2.0",
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": true,
+            "body": "Changelog",
+        });
+        let asset = json!({
+            "id": 7,
+            "name": "mod-fabric-1.20.4-2.0.jar",
+            "browser_download_url": "https://example.com/mod.jar",
+            "digest": "sha256:abc",
+            "download_count": 3,
+        });
+        let version = parse_asset(&release, &asset, &project).unwrap();
+        assert_eq!(version.version_number, "v2.0");
+        assert_eq!(version.version_id, "7");
+        assert_eq!(version.channel, Channel::Beta);
+        assert_eq!(version.hashes.sha256.as_deref(), Some("abc"));
+        assert_eq!(version.downloads, Some(3));
+        for name in ["mod-2.0-sources.jar", "mod-2.0-javadoc.jar", "mod-2.0.zip"] {
+            let asset = json!({ "id": 8, "name": name });
+            assert!(
+                parse_asset(&release, &asset, &project).is_none(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn lists_versions_from_a_local_server() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let releases = json!([
+            {
+                "tag_name": "v3.0",
+                "draft": true,
+                "assets": [{ "id": 1, "name": "mod-fabric-1.20.4-3.0.jar" }],
+            },
+            {
+                "tag_name": "v2.0",
+                "published_at": "2024-02-01T00:00:00Z",
+                "assets": [
+                    { "id": 2, "name": "mod-fabric-1.20.4-2.0.jar" },
+                    { "id": 3, "name": "mod-forge-1.20.4-2.0.jar" },
+                    { "id": 4, "name": "mod-fabric-1.20.4-2.0-sources.jar" },
+                ],
+            },
+            {
+                "tag_name": "v1.0",
+                "published_at": "2024-01-01T00:00:00Z",
+                "assets": [{
+                    "id": 5,
+                    "name": "mod-1.0.jar",
+                    "browser_download_url": format!("{}/download/mod-1.0.jar", base),
+                }],
+            },
+        ])
+        .to_string();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = match request.url().split('?').next() {
+                    Some("/repos/owner/mod/releases") => Response::from_data(releases.clone())
+                        .with_header(
+                            Header::from_bytes("Content-Type", "application/json").unwrap(),
+                        ),
+                    Some("/download/mod-1.0.jar") => {
+                        Response::from_data(fabric_jar("mod", "1.0.0", ">=1.20"))
+                    }
+                    _ => Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        let mut config = Config::default();
+        config.github.api_url = Some(base);
+        config.cache.enabled = false;
+        config.network.retries = 0;
+        let provider = GitHubProvider::new(&config).unwrap();
+        let versions = provider
+            .list_versions(&project("owner/mod"), &criteria("1.20.4", "fabric"))
+            .unwrap();
+        let ids: Vec<&str> = versions.iter().map(|v| v.version_id.as_str()).collect();
+        // 草稿, 其他加载器与附属文件被跳过; 文件名无法判断的文件在已有兼容版本时不再下载检查
+        assert_eq!(ids, ["2"]);
+        assert_eq!(versions[0].game_versions, ["1.20.4"]);
+
+        // 固定到旧版本时下载检查文件名无法判断的文件
+        let pinned = Criteria {
+            pin: Some("v1.0"),
+            ..criteria("1.20.4", "fabric")
+        };
+        let versions = provider
+            .list_versions(&project("owner/mod"), &pinned)
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version_id, "5");
+        assert_eq!(versions[0].loaders, ["fabric"]);
+        assert!(versions[0].hashes.sha512.is_some());
+        let incompatible = Criteria {
+            pin: Some("v1.0"),
+            ..criteria("1.19.4", "fabric")
+        };
+        assert!(
+            provider
+                .list_versions(&project("owner/mod"), &incompatible)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
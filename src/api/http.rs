@@ -0,0 +1,325 @@
+use super::ratelimit::RateLimiter;
+use crate::cache::store::Cache;
+use crate::config::NetworkConfig;
+use crate::error::Error;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode};
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 退避等待的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// 服务端要求等待的上限, 超过则视为失败
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// 按网络设置 (超时, 代理, CA 证书) 创建 HTTP 客户端
+pub fn build_client(network: &NetworkConfig, mut headers: HeaderMap) -> Result<Client, Error> {
+    headers.insert(
+        header::USER_AGENT,
+        header::HeaderValue::from_static("MiraMigrator/1.0"),
+    );
+    let mut builder = Client::builder()
+        .default_headers(headers)
+        .timeout(network.timeout);
+    // 未设置代理时 reqwest 自动读取 HTTPS_PROXY 等环境变量
+    match network.proxy.as_deref() {
+        Some("none") => builder = builder.no_proxy(),
+        Some(url) => {
+            let proxy = Proxy::all(url)
+                .map_err(|e| format!("Invalid proxy `{}`: {}", url, e))?
+                .no_proxy(network.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+        None => {}
+    }
+    if let Some(path) = &network.ca_bundle {
+        let pem = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let certificates =
+            Certificate::from_pem_bundle(&pem).map_err(|e| format!("{}: {}", path.display(), e))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// 发送请求, 临时错误 (超时, 连接失败, 429, 5xx) 按指数退避重试
+/// 若提供限速器, 每次请求前先等待限速器, 并根据 Modrinth 的限速响应头暂停后续请求
+pub fn send(
+    request: RequestBuilder,
+    network: &NetworkConfig,
+    limiter: Option<&RateLimiter>,
+) -> Result<Response, Error> {
+    let mut attempt = 0;
+    loop {
+        let this_request = request
+            .try_clone()
+            .ok_or("Request body cannot be retried")?;
+        if let Some(limiter) = limiter {
+            limiter.acquire();
+        }
+        let started = Instant::now();
+        let (method, url) = this_request
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| (r.method().to_string(), r.url().to_string()))
+            .unwrap_or_default();
+        let (error, wait) = match this_request.send() {
+            Ok(response) => {
+                log::debug!(
+                    "HTTP {} {} -> {} ({} ms, {} bytes)",
+                    method,
+                    url,
+                    response.status(),
+                    started.elapsed().as_millis(),
+                    response
+                        .content_length()
+                        .map_or("?".to_string(), |n| n.to_string())
+                );
+                if let Some(limiter) = limiter {
+                    observe_rate_limit(limiter, response.headers());
+                }
+                let status = response.status();
+                if !is_transient_status(status) {
+                    return Ok(response);
+                }
+                let wait = retry_after(status, response.headers());
+                if attempt >= network.retries || wait.is_some_and(|w| w > MAX_RETRY_AFTER) {
+                    return Ok(response);
+                }
+                (format!("HTTP {}", status), wait)
+            }
+            Err(e) => {
+                log::warn!(
+                    "HTTP {} {} failed after {} ms: {}",
+                    method,
+                    url,
+                    started.elapsed().as_millis(),
+                    e
+                );
+                if attempt >= network.retries || !is_transient_error(&e) {
+                    return Err(e.into());
+                }
+                (e.to_string(), None)
+            }
+        };
+        attempt += 1;
+        let wait = wait.unwrap_or_else(|| backoff_delay(network, attempt));
+        log::warn!(
+            "Request failed ({}), retrying in {:.1}s ({}/{})...",
+            error,
+            wait.as_secs_f64(),
+            attempt,
+            network.retries
+        );
+        thread::sleep(wait);
+    }
+}
+
+/// 发送 GET 请求并返回成功响应的正文, 资源不存在 (404) 时返回 None
+/// 未过期的缓存响应直接返回, 成功的响应写入元数据缓存
+pub fn get_text(
+    client: &Client,
+    url: &str,
+    query: &[(&str, String)],
+    network: &NetworkConfig,
+    limiter: Option<&RateLimiter>,
+    cache: &Cache,
+) -> Result<Option<String>, Error> {
+    let request = client.get(url).query(query);
+    let key = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .map(|r| r.url().to_string())
+        .unwrap_or_else(|| url.to_string());
+    fetch_cached(request, &key, network, limiter, cache)
+}
+
+/// 发送 JSON 正文的 POST 请求并返回成功响应的正文, 响应按地址与正文缓存
+pub fn post_json_text(
+    client: &Client,
+    url: &str,
+    body: &serde_json::Value,
+    network: &NetworkConfig,
+    limiter: Option<&RateLimiter>,
+    cache: &Cache,
+) -> Result<Option<String>, Error> {
+    let request = client.post(url).json(body);
+    let key = format!("POST {} {}", url, body);
+    fetch_cached(request, &key, network, limiter, cache)
+}
+
+fn fetch_cached(
+    request: RequestBuilder,
+    key: &str,
+    network: &NetworkConfig,
+    limiter: Option<&RateLimiter>,
+    cache: &Cache,
+) -> Result<Option<String>, Error> {
+    if let Some(body) = cache.get_metadata(key) {
+        log::debug!("Cached response for {}", key);
+        return Ok(Some(body));
+    }
+    let response = send(request, network, limiter)?;
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(Error::RateLimited(retry_after(status, response.headers())));
+    }
+    let body = response.text()?;
+    if !status.is_success() {
+        return Err(Error::Network(format!("{} {}", status, body)));
+    }
+    cache.put_metadata(key, &body);
+    Ok(Some(body))
+}
+
+/// 第 `attempt` 次重试前的等待时间: 指数增长并加入少量随机抖动
+pub fn backoff_delay(network: &NetworkConfig, attempt: u32) -> Duration {
+    let exp = network
+        .backoff
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let jitter_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_millis() as u64 % 250)
+        .unwrap_or(0);
+    exp.min(MAX_BACKOFF) + Duration::from_millis(jitter_ms)
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || matches!(status.as_u16(), 500 | 502 | 503 | 504)
+}
+
+/// 连接, 超时与读取正文失败可以重试; 无效地址或代理设置等请求错误重试也不会成功
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_body()
+}
+
+/// 服务端要求的等待时间: `Retry-After` (秒数或 HTTP 日期) 或 Modrinth 的 `X-Ratelimit-Reset` (秒)
+/// 限速窗口的重置时间只在被限速 (429 或剩余额度为 0) 时使用, 其余临时错误按退避重试
+pub(crate) fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    header_secs(headers, "retry-after")
+        .or_else(|| {
+            headers
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| until_http_date(v, Utc::now()))
+        })
+        .or_else(|| {
+            let limited =
+                status == StatusCode::TOO_MANY_REQUESTS || ratelimit_remaining(headers) == Some(0);
+            limited
+                .then(|| header_secs(headers, "x-ratelimit-reset"))
+                .flatten()
+        })
+}
+
+/// 距离 HTTP 日期 (RFC 9110: IMF-fixdate, RFC 850 与 asctime 格式) 的时间, 已过去时为 0
+fn until_http_date(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let date = DateTime::parse_from_rfc2822(value)
+        .map(|date| date.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%A, %d-%b-%y %H:%M:%S GMT")
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y"))
+                .map(|date| date.and_utc())
+        })
+        .ok()?;
+    Some((date - now).to_std().unwrap_or_default())
+}
+
+/// 剩余额度为 0 时, 暂停该来源的所有请求直到额度重置
+fn observe_rate_limit(limiter: &RateLimiter, headers: &HeaderMap) {
+    if ratelimit_remaining(headers) == Some(0)
+        && let Some(reset) = header_secs(headers, "x-ratelimit-reset")
+    {
+        limiter.pause_for(reset.min(MAX_RETRY_AFTER));
+    }
+}
+
+fn ratelimit_remaining(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+}
+
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_after, until_http_date};
+    use chrono::{TimeZone, Utc};
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_http_dates() {
+        let now = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 7).unwrap();
+        let expected = Some(Duration::from_secs(30));
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(until_http_date(value, now), expected, "{}", value);
+        }
+        assert_eq!(
+            until_http_date("Sun, 06 Nov 1994 08:48:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(until_http_date("soon", now), None);
+    }
+
+    #[test]
+    fn prefers_retry_after_seconds() {
+        let limited = StatusCode::TOO_MANY_REQUESTS;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("20"));
+        assert_eq!(
+            retry_after(limited, &headers),
+            Some(Duration::from_secs(20))
+        );
+        headers.insert("retry-after", HeaderValue::from_static("5"));
+        assert_eq!(retry_after(limited, &headers), Some(Duration::from_secs(5)));
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+        assert_eq!(retry_after(limited, &headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn ignores_ratelimit_reset_for_server_errors() {
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("50"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("120"));
+        assert_eq!(retry_after(unavailable, &headers), None);
+        // 额度耗尽时即使不是 429 也要等到窗口重置
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        assert_eq!(
+            retry_after(unavailable, &headers),
+            Some(Duration::from_secs(50))
+        );
+        headers.insert("retry-after", HeaderValue::from_static("3"));
+        assert_eq!(
+            retry_after(unavailable, &headers),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
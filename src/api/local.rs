@@ -0,0 +1,465 @@
+use super::DownloadedMod;
+use super::download::{ExpectedHashes, copy_local};
+use super::provider::{
+    Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved, downloaded_mod,
+    pick_version,
+};
+use crate::cache::store::Cache;
+use crate::config::Config;
+use crate::error::Error;
+use crate::utils::loader::{
+    detect_mod, get_minecraft_range, get_mod_id, get_mod_version, loader_name,
+};
+use crate::utils::version::version_in_range;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 本地仓库中的一个 Jar 文件
+#[derive(Debug, Clone)]
+struct LocalMod {
+    mod_id: String,
+    loader: String,
+    version: String,
+    /// 声明支持的 Minecraft 版本范围
+    minecraft: Option<String>,
+    path: PathBuf,
+    hashes: ExpectedHashes,
+}
+
+/// 离线本地仓库来源
+/// 从目录中的 Jar 文件或索引文件读取 Mod, 按 Mod ID 与声明的 Minecraft 版本范围匹配
+pub struct LocalProvider {
+    dir: Option<PathBuf>,
+    index: Option<PathBuf>,
+    cache: Cache,
+    /// 首次使用时扫描, 之后所有线程共享
+    mods: OnceLock<Result<Vec<LocalMod>, String>>,
+}
+
+impl LocalProvider {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        if config.local.dir.is_none() && config.local.index.is_none() {
+            return Err("`providers.local` needs `dir` or `index`".into());
+        }
+        Ok(LocalProvider {
+            dir: config.local.dir.clone(),
+            index: config.local.index.clone(),
+            cache: Cache::new(&config.cache),
+            mods: OnceLock::new(),
+        })
+    }
+
+    fn mods(&self) -> Result<&[LocalMod], Error> {
+        self.mods
+            .get_or_init(|| {
+                let mut mods = Vec::new();
+                if let Some(index) = &self.index {
+                    mods.extend(read_index(index).map_err(|e| e.to_string())?);
+                }
+                if let Some(dir) = &self.dir {
+                    scan_dir(dir, &mut mods).map_err(|e| format!("{}: {}", dir.display(), e))?;
+                }
+                Ok(mods)
+            })
+            .as_deref()
+            .map_err(|e| e.clone().into())
+    }
+}
+
+impl ModProvider for LocalProvider {
+    fn id(&self) -> &'static str {
+        "local"
+    }
+
+    fn name(&self) -> &'static str {
+        "Local"
+    }
+
+    fn identify(&self, mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+        self.find_project(mod_id)
+    }
+
+    /// 项目 ID 即 Mod ID
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        Ok(self
+            .mods()?
+            .iter()
+            .any(|m| m.mod_id == id)
+            .then(|| project(id)))
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        _mc_version: &str,
+        _loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error> {
+        let query = query.to_lowercase();
+        let mut ids: Vec<&str> = self
+            .mods()?
+            .iter()
+            .filter(|m| m.mod_id.to_lowercase().contains(&query))
+            .map(|m| m.mod_id.as_str())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids.into_iter().map(project).collect())
+    }
+
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error> {
+        let game_versions = criteria.game_versions();
+        Ok(self
+            .mods()?
+            .iter()
+            .filter(|m| m.mod_id == project.id && m.loader.eq_ignore_ascii_case(criteria.loader))
+            .filter_map(|m| {
+                // 记录声明的范围内可接受的 Minecraft 版本
+                let supported: Vec<String> = game_versions
+                    .iter()
+                    .filter(|mc| {
+                        m.minecraft
+                            .as_deref()
+                            .is_some_and(|range| version_in_range(mc, range))
+                    })
+                    .map(|mc| mc.to_string())
+                    .collect();
+                (!supported.is_empty()).then_some((m, supported))
+            })
+            .map(|(m, supported)| {
+                let file_name = m
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                RemoteVersion {
+                    project_id: m.mod_id.clone(),
+                    version_id: m.version.clone(),
+                    version_number: m.version.clone(),
+                    game_versions: supported,
+                    loaders: vec![m.loader.clone()],
+                    published: String::new(),
+                    channel: Channel::Release,
+                    file_name,
+                    url: Some(format!("file://{}", m.path.display())),
+                    hashes: m.hashes.clone(),
+                    downloads: None,
+                    changelog: None,
+                }
+            })
+            .collect())
+    }
+
+    /// 本地文件没有发布时间, 仅按版本号选择
+    fn resolve(
+        &self,
+        versions: Vec<RemoteVersion>,
+        criteria: &Criteria,
+    ) -> Result<Resolved, Error> {
+        let version = pick_version(versions, criteria)?;
+        let reason = format!(
+            "Newest local repository version declaring support for Minecraft {} on {}",
+            criteria.mc_version, criteria.loader
+        );
+        Ok(Resolved::new(version, reason, criteria))
+    }
+
+    /// 只从扫描到的仓库文件复制, 不按地址读取本地文件
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+        let version = &resolved.version;
+        let source = self
+            .mods()?
+            .iter()
+            .find(|m| {
+                m.mod_id == version.project_id
+                    && m.version == version.version_id
+                    && m.path.file_name() == Some(version.file_name.as_ref())
+            })
+            .ok_or_else(|| Error::NotFound(version.file_name.clone()))?;
+        let save_path = output_dir.join(&version.file_name);
+        copy_local(&source.path, &save_path, &version.hashes, &self.cache)?;
+        let url = format!("file://{}", source.path.display());
+        Ok(downloaded_mod(self, resolved, &url, save_path))
+    }
+}
+
+fn project(mod_id: &str) -> RemoteProject {
+    RemoteProject {
+        id: mod_id.to_string(),
+        slug: mod_id.to_string(),
+        title: mod_id.to_string(),
+        authors: Vec::new(),
+        downloads: None,
+    }
+}
+
+/// 读取 Jar 文件的元数据, 无法识别时返回 None
+fn read_jar(path: &Path) -> Option<LocalMod> {
+    let loader = loader_name(&detect_mod(path).ok()??)?;
+    Some(LocalMod {
+        mod_id: get_mod_id(path).ok()??,
+        loader: loader.to_string(),
+        version: get_mod_version(path).ok()??,
+        minecraft: get_minecraft_range(path).ok().flatten(),
+        path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        hashes: ExpectedHashes::default(),
+    })
+}
+
+/// 递归扫描目录中的 Jar 文件
+fn scan_dir(dir: &Path, mods: &mut Vec<LocalMod>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            scan_dir(&path, mods)?;
+        } else if path.extension().unwrap_or_default() == "jar"
+            && let Some(local) = read_jar(&path)
+        {
+            mods.push(local);
+        }
+    }
+    Ok(())
+}
+
+/// 读取索引文件 (TOML), 格式如下, `file` 相对于索引文件所在目录:
+///
+/// ```toml
+/// [[mods]]
+/// mod_id = "sodium"
+/// loader = "fabric"
+/// version = "0.5.8"
+/// minecraft = ">=1.20.4 <1.21"
+/// file = "fabric/sodium-fabric-0.5.8.jar"
+/// sha1 = "..."
+/// ```
+///
+/// 除 `file` 外的字段均可省略, 省略时从 Jar 文件中读取
+fn read_index(index: &Path) -> Result<Vec<LocalMod>, Error> {
+    let contents = fs::read_to_string(index)?;
+    let root = contents
+        .parse::<toml::Value>()
+        .map_err(|e| format!("{}: {}", index.display(), e))?;
+    let base = index.parent().unwrap_or(Path::new("."));
+    let mut mods = Vec::new();
+    for entry in root
+        .get("mods")
+        .and_then(|m| m.as_array())
+        .map(|m| m.as_slice())
+        .unwrap_or(&[])
+    {
+        let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(String::from);
+        let file = text("file")
+            .ok_or_else(|| format!("{}: every `[[mods]]` entry needs `file`", index.display()))?;
+        let path = base.join(&file);
+        let from_jar = read_jar(&path);
+        let Some(mod_id) = text("mod_id").or_else(|| from_jar.as_ref().map(|m| m.mod_id.clone()))
+        else {
+            continue;
+        };
+        let Some(loader) = text("loader").or_else(|| from_jar.as_ref().map(|m| m.loader.clone()))
+        else {
+            continue;
+        };
+        mods.push(LocalMod {
+            mod_id,
+            loader,
+            version: text("version")
+                .or_else(|| from_jar.as_ref().map(|m| m.version.clone()))
+                .unwrap_or_default(),
+            minecraft: text("minecraft").or_else(|| from_jar.and_then(|m| m.minecraft)),
+            path: fs::canonicalize(&path).unwrap_or(path),
+            hashes: ExpectedHashes {
+                sha1: text("sha1"),
+                sha256: None,
+                sha512: text("sha512"),
+            },
+        });
+    }
+    Ok(mods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalProvider, project, read_index};
+    use crate::api::provider::{Channel, Criteria, ModProvider, Resolved};
+    use crate::config::Config;
+    use crate::error::Error;
+    use crate::utils::testing::{TestDir, fabric_jar};
+    use std::fs;
+    use std::path::Path;
+
+    fn criteria(mc_version: &str) -> Criteria<'_> {
+        Criteria {
+            mc_version,
+            loader: "fabric",
+            channel: Channel::Release,
+            installed: None,
+            allow_downgrade: true,
+            pin: None,
+            compatible_versions: false,
+        }
+    }
+
+    fn provider(dir: &Path) -> LocalProvider {
+        let mut config = Config::default();
+        config.local.dir = Some(dir.to_path_buf());
+        config.cache.enabled = false;
+        LocalProvider::new(&config).unwrap()
+    }
+
+    #[test]
+    fn reads_index_entries() {
+        let dir = TestDir::new("local-index");
+        let jars = dir.subdir("jars");
+        fs::write(
+            jars.join("sodium.jar"),
+            fabric_jar("sodium", "0.5.8", ">=1.20"),
+        )
+        .unwrap();
+        let index = dir.path().join("index.toml");
+        fs::write(
+            &index,
+            r#"
+            [[mods]]
+            file = "jars/sodium.jar"
+
+            [[mods]]
+            file = "jars/sodium.jar"
+            version = "0.5.9"
+            minecraft = ">=1.20.4 <1.21"
+            sha1 = "abc"
+            sha512 = "def"
+
+            [[mods]]
+            file = "jars/missing.jar"
+            version = "1.0.0"
+
+            [[mods]]
+            file = "jars/missing.jar"
+            mod_id = "lithium"
+            loader = "fabric"
+            "#,
+        )
+        .unwrap();
+        let mods = read_index(&index).unwrap();
+        // 既未声明 Mod ID 也无法读取 Jar 的条目被跳过
+        assert_eq!(mods.len(), 3);
+        assert_eq!(mods[0].mod_id, "sodium");
+        assert_eq!(mods[0].loader, "fabric");
+        assert_eq!(mods[0].version, "0.5.8");
+        assert_eq!(mods[0].minecraft.as_deref(), Some(">=1.20"));
+        assert!(mods[0].hashes.sha1.is_none());
+        assert_eq!(
+            mods[0].path,
+            fs::canonicalize(jars.join("sodium.jar")).unwrap()
+        );
+        // 索引中的字段优先于 Jar 中的元数据
+        assert_eq!(mods[1].version, "0.5.9");
+        assert_eq!(mods[1].minecraft.as_deref(), Some(">=1.20.4 <1.21"));
+        assert_eq!(mods[1].hashes.sha1.as_deref(), Some("abc"));
+        assert_eq!(mods[1].hashes.sha512.as_deref(), Some("def"));
+        assert_eq!(mods[2].mod_id, "lithium");
+        assert_eq!(mods[2].version, "");
+        assert_eq!(mods[2].path, dir.path().join("jars/missing.jar"));
+
+        fs::write(&index, "[[mods]]\nmod_id = \"sodium\"\n").unwrap();
+        assert!(read_index(&index).is_err());
+    }
+
+    #[test]
+    fn lists_versions_in_range() {
+        let dir = TestDir::new("local-scan");
+        let nested = dir.subdir("nested/deeper");
+        fs::write(
+            dir.path().join("sodium-0.5.jar"),
+            fabric_jar("sodium", "0.5.0", ">=1.20 <1.20.5"),
+        )
+        .unwrap();
+        fs::write(
+            nested.join("sodium-0.6.jar"),
+            fabric_jar("sodium", "0.6.0", ">=1.21"),
+        )
+        .unwrap();
+        fs::write(nested.join("notes.txt"), "not a jar").unwrap();
+        fs::write(nested.join("broken.jar"), "not a zip").unwrap();
+        let provider = provider(dir.path());
+
+        assert!(provider.find_project("sodium").unwrap().is_some());
+        assert!(provider.find_project("lithium").unwrap().is_none());
+        let versions = provider
+            .list_versions(&project("sodium"), &criteria("1.20.4"))
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version_number, "0.5.0");
+        assert_eq!(versions[0].game_versions, ["1.20.4"]);
+        assert_eq!(versions[0].file_name, "sodium-0.5.jar");
+        let versions = provider
+            .list_versions(&project("sodium"), &criteria("1.21"))
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version_number, "0.6.0");
+        assert!(
+            provider
+                .list_versions(&project("sodium"), &criteria("1.19.4"))
+                .unwrap()
+                .is_empty()
+        );
+        let forge = Criteria {
+            loader: "forge",
+            ..criteria("1.20.4")
+        };
+        assert!(
+            provider
+                .list_versions(&project("sodium"), &forge)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn copies_listed_files() {
+        let dir = TestDir::new("local-download");
+        let repository = dir.subdir("repository");
+        let output = dir.subdir("mods");
+        let jar = fabric_jar("sodium", "0.5.0", ">=1.20");
+        fs::write(repository.join("sodium.jar"), &jar).unwrap();
+        let criteria = criteria("1.20.4");
+        let provider = provider(&repository);
+        let versions = provider
+            .list_versions(&project("sodium"), &criteria)
+            .unwrap();
+        let resolved = provider.resolve(versions, &criteria).unwrap();
+        let downloaded = provider.download(&resolved, &output).unwrap();
+        assert_eq!(downloaded.provider, "local");
+        assert_eq!(downloaded.path, output.join("sodium.jar"));
+        assert!(downloaded.url.starts_with("file://"), "{}", downloaded.url);
+        assert_eq!(fs::read(&downloaded.path).unwrap(), jar);
+
+        // 校验失败时不保留文件
+        let mut mismatch = resolved.version.clone();
+        mismatch.hashes.sha1 = Some("00".repeat(20));
+        let mismatch = Resolved::new(mismatch, String::new(), &criteria);
+        fs::remove_file(&downloaded.path).unwrap();
+        let result = provider.download(&mismatch, &output);
+        assert!(
+            matches!(result, Err(Error::HashMismatch(_))),
+            "{:?}",
+            result
+        );
+        assert!(!output.join("sodium.jar").exists());
+
+        // 只复制仓库中扫描到的文件
+        let mut outside = resolved.version.clone();
+        outside.file_name = "other.jar".to_string();
+        let outside = Resolved::new(outside, String::new(), &criteria);
+        assert!(matches!(
+            provider.download(&outside, &output),
+            Err(Error::NotFound(_))
+        ));
+    }
+}
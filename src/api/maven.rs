@@ -0,0 +1,308 @@
+use super::DownloadedMod;
+use super::download::ExpectedHashes;
+use super::http;
+use super::provider::{
+    Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved, download_version,
+    inspect_jar, pick_version,
+};
+use crate::cache::store::Cache;
+use crate::config::{Config, NetworkConfig};
+use crate::error::Error;
+use crate::utils::ordering::compare_maven;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 最多下载检查的版本数量
+const MAX_INSPECTED_VERSIONS: usize = 10;
+
+/// Maven 仓库来源
+/// 读取 `maven-metadata.xml` 中的版本列表, 从新到旧下载 Jar 文件检查声明的 Minecraft 版本范围
+pub struct MavenProvider {
+    repositories: Vec<String>,
+    artifacts: BTreeMap<String, String>,
+    client: Client,
+    network: NetworkConfig,
+    cache: Cache,
+}
+
+impl MavenProvider {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        if config.maven.repositories.is_empty() {
+            return Err("`providers.maven.repositories` must list at least one repository".into());
+        }
+        Ok(MavenProvider {
+            repositories: config
+                .maven
+                .repositories
+                .iter()
+                .map(|r| r.trim_end_matches('/').to_string())
+                .collect(),
+            artifacts: config.maven.artifacts.clone(),
+            client: http::build_client(&config.network, HeaderMap::new())?,
+            network: config.network.clone(),
+            cache: Cache::new(&config.cache),
+        })
+    }
+
+    fn get_text(&self, url: &str) -> Result<Option<String>, Error> {
+        http::get_text(&self.client, url, &[], &self.network, None, &self.cache)
+    }
+
+    /// 在第一个包含该构件的仓库中读取版本列表, 返回仓库中构件的根地址与版本
+    fn get_versions(&self, group: &str, artifact: &str) -> Result<(String, Vec<String>), Error> {
+        for repository in &self.repositories {
+            let base = format!("{}/{}/{}", repository, group.replace('.', "/"), artifact);
+            if let Some(metadata) = self.get_text(&format!("{}/maven-metadata.xml", base))? {
+                return Ok((base, parse_versions(&metadata)));
+            }
+        }
+        Err(Error::NotFound(format!("artifact {}:{}", group, artifact)))
+    }
+}
+
+impl ModProvider for MavenProvider {
+    fn id(&self) -> &'static str {
+        "maven"
+    }
+
+    fn name(&self) -> &'static str {
+        "Maven"
+    }
+
+    fn identify(&self, mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+        Ok(self
+            .artifacts
+            .get(mod_id)
+            .map(|coordinates| project(coordinates)))
+    }
+
+    /// 项目 ID 为 `group:artifact`
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        Ok(id.contains(':').then(|| project(id)))
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        _mc_version: &str,
+        _loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error> {
+        let query = query.to_lowercase();
+        Ok(self
+            .artifacts
+            .values()
+            .filter(|coordinates| coordinates.to_lowercase().contains(&query))
+            .map(|coordinates| project(coordinates))
+            .collect())
+    }
+
+    /// 从新到旧检查版本, 返回第一个声明支持目标版本与加载器的构件
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error> {
+        let (group, artifact) = project
+            .id
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid Maven coordinates: {}", project.id))?;
+        let (base, mut versions) = self.get_versions(group, artifact)?;
+        versions.retain(|v| !v.ends_with("-SNAPSHOT") && criteria.pin_matches(v));
+        versions.sort_by(|a, b| compare_maven(b, a));
+
+        for version_number in versions.iter().take(MAX_INSPECTED_VERSIONS) {
+            let file_name = format!("{}-{}.jar", artifact, version_number);
+            let url = format!("{}/{}/{}", base, version_number, file_name);
+            // 仓库中与 Jar 同名的 .sha1 文件, 内容可能附带文件名
+            let sha1 = self
+                .get_text(&format!("{}.sha1", url))?
+                .and_then(|text| text.split_whitespace().next().map(|s| s.to_lowercase()));
+            let mut version = RemoteVersion {
+                project_id: project.id.clone(),
+                version_id: version_number.clone(),
+                version_number: version_number.clone(),
+                game_versions: Vec::new(),
+                loaders: Vec::new(),
+                published: String::new(),
+                channel: Channel::Release,
+                file_name,
+                url: Some(url),
+                hashes: ExpectedHashes {
+                    sha1,
+                    ..ExpectedHashes::default()
+                },
+                downloads: None,
+                changelog: None,
+            };
+            if inspect_jar(
+                &self.client,
+                &mut version,
+                criteria,
+                &self.network,
+                &self.cache,
+            )? {
+                return Ok(vec![version]);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Maven 元数据没有各版本的发布时间, 仅按版本号选择
+    fn resolve(
+        &self,
+        versions: Vec<RemoteVersion>,
+        criteria: &Criteria,
+    ) -> Result<Resolved, Error> {
+        let version = pick_version(versions, criteria)?;
+        let reason = format!(
+            "Newest Maven artifact declaring support for Minecraft {} on {}",
+            criteria.mc_version, criteria.loader
+        );
+        Ok(Resolved::new(version, reason, criteria))
+    }
+
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+        download_version(
+            &self.client,
+            self,
+            resolved,
+            output_dir,
+            &self.network,
+            &self.cache,
+        )
+    }
+}
+
+fn project(coordinates: &str) -> RemoteProject {
+    RemoteProject {
+        id: coordinates.to_string(),
+        slug: coordinates
+            .rsplit(':')
+            .next()
+            .unwrap_or(coordinates)
+            .to_string(),
+        title: coordinates.to_string(),
+        authors: Vec::new(),
+        downloads: None,
+    }
+}
+
+/// 读取 `maven-metadata.xml` 中 `<versions>` 列出的版本
+fn parse_versions(metadata: &str) -> Vec<String> {
+    let versions = metadata
+        .split_once("<versions>")
+        .and_then(|(_, rest)| rest.split_once("</versions>"))
+        .map(|(versions, _)| versions)
+        .unwrap_or_default();
+    let Ok(re) = Regex::new(r"<version>\s*([^<\s]+)\s*</version>") else {
+        return Vec::new();
+    };
+    re.captures_iter(versions)
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MavenProvider, parse_versions, project};
+    use crate::api::provider::{Channel, Criteria, ModProvider};
+    use crate::config::Config;
+    use crate::utils::hash::to_hex;
+    use crate::utils::testing::fabric_jar;
+    use sha1::{Digest, Sha1};
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    fn criteria(mc_version: &str) -> Criteria<'_> {
+        Criteria {
+            mc_version,
+            loader: "fabric",
+            channel: Channel::Release,
+            installed: None,
+            allow_downgrade: true,
+            pin: None,
+            compatible_versions: false,
+        }
+    }
+
+    #[test]
+    fn parses_metadata_versions() {
+        let metadata = "<metadata>
+              <versioning>
+                <latest>9.9.9</latest>
+                <versions>
+                  <version>1.0.0</version>
+                  <version> 1.1.0 </version>
+                  <version>2.0.0-SNAPSHOT</version>
+                </versions>
+              </versioning>
+            </metadata>";
+        assert_eq!(
+            parse_versions(metadata),
+            ["1.0.0", "1.1.0", "2.0.0-SNAPSHOT"]
+        );
+        assert!(parse_versions("<metadata><version>1.0.0</version></metadata>").is_empty());
+        assert!(parse_versions("<versions><version>1.0.0</version>").is_empty());
+    }
+
+    #[test]
+    fn lists_versions_from_a_local_server() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let old_jar = fabric_jar("mod", "1.1.0", ">=1.20 <1.21");
+        let new_jar = fabric_jar("mod", "2.0.0", ">=1.21");
+        let old_sha1 = to_hex(&Sha1::digest(&old_jar));
+        let sha1_file = format!("{}  mod-1.1.0.jar", old_sha1.to_uppercase());
+        let metadata = "<metadata><versioning><versions>
+            <version>1.0.0</version>
+            <version>1.1.0</version>
+            <version>2.0.0</version>
+            <version>2.1.0-SNAPSHOT</version>
+            </versions></versioning></metadata>";
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let prefix = "/second/org/example/mod";
+                let response = match request.url().strip_prefix(prefix) {
+                    Some("/maven-metadata.xml") => Response::from_data(metadata),
+                    Some("/1.1.0/mod-1.1.0.jar") => Response::from_data(old_jar.clone()),
+                    Some("/1.1.0/mod-1.1.0.jar.sha1") => Response::from_data(sha1_file.clone()),
+                    Some("/2.0.0/mod-2.0.0.jar") => Response::from_data(new_jar.clone()),
+                    _ => Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        let mut config = Config::default();
+        config.maven.repositories = vec![format!("{}/first", base), format!("{}/second/", base)];
+        config.cache.enabled = false;
+        config.network.retries = 0;
+        let provider = MavenProvider::new(&config).unwrap();
+        let artifact = project("org.example:mod");
+        // 第一个仓库没有该构件; 快照被跳过, 2.0.0 声明的版本范围不包含 1.20.4
+        let versions = provider
+            .list_versions(&artifact, &criteria("1.20.4"))
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version_number, "1.1.0");
+        assert_eq!(versions[0].game_versions, ["1.20.4"]);
+        assert_eq!(versions[0].hashes.sha1.as_deref(), Some(old_sha1.as_str()));
+        assert_eq!(
+            versions[0].url.as_deref(),
+            Some(format!("{}/second/org/example/mod/1.1.0/mod-1.1.0.jar", base).as_str())
+        );
+
+        let versions = provider
+            .list_versions(&artifact, &criteria("1.21"))
+            .unwrap();
+        assert_eq!(versions[0].version_number, "2.0.0");
+        assert!(
+            provider
+                .list_versions(&project("org.example:missing"), &criteria("1.20.4"))
+                .is_err()
+        );
+    }
+}
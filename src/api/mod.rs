@@ -1,2 +1,64 @@
 pub mod curseforge;
+pub mod download;
+pub mod github;
+pub mod http;
+pub mod local;
+pub mod maven;
 pub mod modrinth;
+pub mod provider;
+pub mod ratelimit;
+
+use crate::CURSEFORGE_API_KEY;
+use crate::config::Config;
+use curseforge::CurseForgeProvider;
+use github::GitHubProvider;
+use local::LocalProvider;
+use maven::MavenProvider;
+use modrinth::ModrinthProvider;
+use provider::ModProvider;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// 已下载的模组文件及其来源信息
+#[derive(Debug, Clone)]
+pub struct DownloadedMod {
+    /// 下载来源 (modrinth / curseforge)
+    pub provider: String,
+    /// 平台上的项目 ID
+    pub project_id: String,
+    /// 平台上的版本 (文件) ID
+    pub version_id: String,
+    /// 版本号
+    pub version_number: String,
+    /// 文件名
+    pub file_name: String,
+    /// 下载地址
+    pub url: String,
+    /// 选择该版本的原因
+    pub reason: String,
+    /// 非精确匹配时, 该版本实际标注的 Minecraft 版本
+    pub compatible_with: Option<String>,
+    /// 本地保存路径
+    pub path: PathBuf,
+}
+
+/// 按配置中的顺序创建下载来源
+pub fn build_providers(config: &Config) -> Result<Vec<Box<dyn ModProvider>>, Box<dyn Error>> {
+    config
+        .providers
+        .iter()
+        .map(|id| -> Result<Box<dyn ModProvider>, Box<dyn Error>> {
+            match id.as_str() {
+                "modrinth" => Ok(Box::new(ModrinthProvider::new(config)?)),
+                "curseforge" => Ok(Box::new(CurseForgeProvider::new(
+                    config,
+                    CURSEFORGE_API_KEY,
+                )?)),
+                "github" => Ok(Box::new(GitHubProvider::new(config)?)),
+                "maven" => Ok(Box::new(MavenProvider::new(config)?)),
+                "local" => Ok(Box::new(LocalProvider::new(config)?)),
+                other => Err(format!("Unknown provider `{}` in `providers.order`", other).into()),
+            }
+        })
+        .collect()
+}
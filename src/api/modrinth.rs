@@ -1,136 +1,253 @@
+use super::DownloadedMod;
+use super::download::ExpectedHashes;
+use super::provider::{
+    Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved, download_version,
+    rewrite_cdn,
+};
+use super::{http, ratelimit};
+use crate::cache::store::Cache;
+use crate::config::{Config, NetworkConfig};
+use crate::error::Error;
+use crate::utils::hash::hash_file;
 use reqwest::blocking::Client;
 use reqwest::header;
 use sanitize_filename::sanitize;
-use serde_json::Value;
-use std::error::Error;
-use std::fs::File;
-use std::path::{Path, PathBuf};
-use std::time::Duration;
+use serde_json::{Value, json};
+use std::path::Path;
 
-pub fn download_modrinth_mod(
-    mod_id: &str,
-    mc_version: &str,
-    loader: &str,
-    output_dir: &Path,
-) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // 1. 配置HTTP客户端
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::ACCEPT,
-        header::HeaderValue::from_static("application/json"),
-    );
-    headers.insert(
-        header::USER_AGENT,
-        header::HeaderValue::from_static("MiraMigrator/1.0"),
-    );
+/// Modrinth 官方 API 地址
+const API_BASE: &str = "https://api.modrinth.com/v2";
+/// 官方 CDN 地址, 配置镜像时替换为 `cdn_url`
+const CDN_HOSTS: [&str; 1] = ["https://cdn.modrinth.com"];
 
-    let client = Client::builder()
-        .default_headers(headers)
-        .timeout(Duration::from_secs(30))
-        .build()?;
-    // 2. 验证项目存在
-    let project_url = format!("https://api.modrinth.com/v2/project/{}", mod_id);
-    let project_res = client.get(&project_url).send()?;
-    if !project_res.status().is_success() {
-        return Err(format!(
-            "The mod does not exist: {} {}",
-            project_res.status(),
-            project_res.text()?
-        )
-        .into());
+/// Modrinth 下载来源
+pub struct ModrinthProvider {
+    api_base: String,
+    cdn_url: Option<String>,
+    client: Client,
+    network: NetworkConfig,
+    cache: Cache,
+}
+
+impl ModrinthProvider {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/json"),
+        );
+        Ok(ModrinthProvider {
+            api_base: config
+                .modrinth
+                .api_url
+                .as_deref()
+                .unwrap_or(API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+            cdn_url: config.modrinth.cdn_url.clone(),
+            client: http::build_client(&config.network, headers)?,
+            network: config.network.clone(),
+            cache: Cache::new(&config.cache),
+        })
     }
 
-    // 3. 获取兼容版本
-    let versions_url = format!("https://api.modrinth.com/v2/project/{}/version", mod_id);
-    let versions_res = client
-        .get(&versions_url)
-        .query(&[("game_versions", mc_version), ("loaders", loader)])
-        .send()?;
+    /// 发送 GET 请求并解析 JSON, 资源不存在时返回 None
+    fn get_json(&self, url: &str, query: &[(&str, String)]) -> Result<Option<Value>, Error> {
+        let Some(text) = http::get_text(
+            &self.client,
+            url,
+            query,
+            &self.network,
+            Some(ratelimit::modrinth()),
+            &self.cache,
+        )?
+        else {
+            return Ok(None);
+        };
+        let json = serde_json::from_str(&text).map_err(|e| {
+            Error::Network(format!(
+                "JSON parsing failed: {}\nResponse content: {}",
+                e, text
+            ))
+        })?;
+        Ok(Some(json))
+    }
 
-    let versions_text = versions_res.text()?;
-    let versions: Vec<Value> = serde_json::from_str(&versions_text).map_err(|e| {
-        format!(
-            "JSON parsing failed: {}\nResponse content: {}",
-            e, versions_text
-        )
-    })?;
+    /// 按项目 ID 或 slug 查询项目
+    fn get_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        let project = self.get_json(&format!("{}/project/{}", self.api_base, id), &[])?;
+        Ok(project.map(|p| parse_project(&p)))
+    }
+}
 
-    // 4. 选择与目标版本兼容的最新版本
-    let version = get_latest_compatible_version(&versions, mc_version, loader)?;
-    // 5. 获取可下载文件
-    let file = version["files"]
-        .as_array()
-        .and_then(|files| {
-            files
-                .iter()
-                .find(|f| f["primary"].as_bool().unwrap_or(false))
-        })
-        .ok_or("The master file could not be found.")?;
+impl ModProvider for ModrinthProvider {
+    fn id(&self) -> &'static str {
+        "modrinth"
+    }
 
-    // 6. 安全下载文件
-    let download_url = file["url"].as_str().ok_or("Invalid download URL")?;
-    let file_name = sanitize(
-        file["filename"]
-            .as_str()
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| {
-                format!(
-                    "{}-{}.jar",
-                    mod_id,
-                    version["version_number"].as_str().unwrap_or("unknown")
-                )
-            }),
-    );
+    fn name(&self) -> &'static str {
+        "Modrinth"
+    }
 
-    let save_path = output_dir.join(file_name);
-    download_file(&client, download_url, &save_path)?;
+    fn identify(&self, mod_id: &str, jar: &Path) -> Result<Option<RemoteProject>, Error> {
+        // 1. 按文件哈希查询, 可识别 Mod ID 与 slug 不一致的项目
+        if let Ok(hashes) = hash_file(jar)
+            && let Some(version) = self.get_json(
+                &format!("{}/version_file/{}", self.api_base, hashes.sha1),
+                &[("algorithm", "sha1".to_string())],
+            )?
+            && let Some(project_id) = version["project_id"].as_str()
+            && let Some(project) = self.get_project(project_id)?
+        {
+            return Ok(Some(project));
+        }
+        // 2. 将 Mod ID 作为项目 ID 或 slug 查询
+        self.get_project(mod_id)
+    }
 
-    Ok(save_path)
-}
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+        self.get_project(id)
+    }
 
-// 添加下载文件函数
-fn download_file(
-    client: &Client,
-    url: &str,
-    path: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut response = client.get(url).send()?;
-    let mut file = File::create(path)?;
-    std::io::copy(&mut response, &mut file)?;
-    Ok(())
+    fn search(
+        &self,
+        query: &str,
+        mc_version: &str,
+        loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error> {
+        let facets = json!([
+            ["project_type:mod"],
+            [format!("categories:{}", loader)],
+            [format!("versions:{}", mc_version)],
+        ]);
+        let results = self
+            .get_json(
+                &format!("{}/search", self.api_base),
+                &[("query", query.to_string()), ("facets", facets.to_string())],
+            )?
+            .unwrap_or_default();
+        Ok(results["hits"]
+            .as_array()
+            .map(|hits| hits.iter().map(parse_project).collect())
+            .unwrap_or_default())
+    }
+
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error> {
+        // Modrinth 要求以 JSON 数组传递筛选条件
+        let versions = self
+            .get_json(
+                &format!("{}/project/{}/version", self.api_base, project.id),
+                &[
+                    ("game_versions", json!(criteria.game_versions()).to_string()),
+                    ("loaders", json!([criteria.loader]).to_string()),
+                ],
+            )?
+            .ok_or_else(|| Error::NotFound(format!("mod {}", project.slug)))?;
+        Ok(versions
+            .as_array()
+            .map(|versions| {
+                versions
+                    .iter()
+                    .filter_map(|v| parse_version(v, &project.slug))
+                    .map(|mut v| {
+                        v.url = v
+                            .url
+                            .map(|url| rewrite_cdn(url, &CDN_HOSTS, self.cdn_url.as_deref()));
+                        v
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+        download_version(
+            &self.client,
+            self,
+            resolved,
+            output_dir,
+            &self.network,
+            &self.cache,
+        )
+    }
+
+    fn project_url(&self, project_id: &str) -> Option<String> {
+        Some(format!("https://modrinth.com/mod/{}", project_id))
+    }
 }
 
-fn get_latest_compatible_version(
-    versions: &[Value],
-    mc_version: &str,
-    loader: &str,
-) -> Result<Value, Box<dyn Error>> {
-    // 按照发布时间降序排序版本
-    let mut sorted_versions = versions.to_vec();
-    sorted_versions.sort_by(|a, b| {
-        let a_time = a["date_published"].as_str().unwrap_or("");
-        let b_time = b["date_published"].as_str().unwrap_or("");
-        b_time.cmp(a_time) // 降序排列
-    });
+/// 解析项目信息, 兼容项目接口与搜索结果两种格式
+fn parse_project(project: &Value) -> RemoteProject {
+    let text = |key: &str| project[key].as_str().unwrap_or_default().to_string();
+    RemoteProject {
+        id: project["id"]
+            .as_str()
+            .or(project["project_id"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        slug: text("slug"),
+        title: text("title"),
+        // 仅搜索结果包含作者
+        authors: project["author"]
+            .as_str()
+            .map(|author| vec![author.to_string()])
+            .unwrap_or_default(),
+        downloads: project["downloads"].as_u64(),
+    }
+}
 
-    // 查找最新版本，筛选支持目标 Minecraft 版本和加载器
-    sorted_versions
+/// 解析版本信息, 使用其中的主文件; 没有文件时返回 None
+fn parse_version(version: &Value, slug: &str) -> Option<RemoteVersion> {
+    let files = version["files"].as_array()?;
+    let file = files
         .iter()
-        .find(|v| {
-            let game_versions = v["game_versions"]
-                .as_array()
-                .cloned()
-                .unwrap_or_else(Vec::new);
-            let loaders = v["loaders"].as_array().cloned().unwrap_or_else(Vec::new);
-            // 这里保证 mc_version 和 loader 都是 &str 类型
-            let contains_mc_version = game_versions
-                .iter()
-                .any(|v| v.as_str().unwrap_or("").trim() == mc_version.trim());
-            let contains_loader = loaders
-                .iter()
-                .any(|v| v.as_str().unwrap_or("").trim() == loader.trim());
-            contains_mc_version && contains_loader
-        })
-        .cloned() // 克隆出匹配的版本
-        .ok_or_else(|| "No compatible version found.".into())
+        .find(|f| f["primary"].as_bool().unwrap_or(false))
+        .or(files.first())?;
+    let strings = |key: &str| {
+        version[key]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let version_number = version["version_number"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    Some(RemoteVersion {
+        project_id: version["project_id"].as_str().unwrap_or(slug).to_string(),
+        version_id: version["id"].as_str().unwrap_or_default().to_string(),
+        game_versions: strings("game_versions"),
+        loaders: strings("loaders"),
+        published: version["date_published"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        channel: version["version_type"]
+            .as_str()
+            .and_then(Channel::parse)
+            .unwrap_or_default(),
+        file_name: sanitize(
+            file["filename"]
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("{}-{}.jar", slug, version_number)),
+        ),
+        url: file["url"].as_str().map(|s| s.to_string()),
+        hashes: ExpectedHashes {
+            sha1: file["hashes"]["sha1"].as_str().map(|s| s.to_string()),
+            sha256: None,
+            sha512: file["hashes"]["sha512"].as_str().map(|s| s.to_string()),
+        },
+        downloads: version["downloads"].as_u64(),
+        changelog: version["changelog"].as_str().map(|s| s.to_string()),
+        version_number,
+    })
 }
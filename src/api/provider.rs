@@ -0,0 +1,626 @@
+use super::DownloadedMod;
+use super::download::{ExpectedHashes, download_file};
+use crate::cache::store::Cache;
+use crate::config::NetworkConfig;
+use crate::error::Error;
+use crate::utils::loader::{detect_mod, get_minecraft_range, loader_name};
+use crate::utils::ordering::{compare_mod_versions, compare_timestamps};
+use crate::utils::version::{compatible_versions, version_in_range};
+use reqwest::blocking::Client;
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicUsize};
+
+/// 平台上的项目
+#[derive(Debug, Clone)]
+pub struct RemoteProject {
+    /// 平台上的项目 ID
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    /// 作者名称, 平台未提供时为空
+    pub authors: Vec<String>,
+    /// 总下载次数, 平台未提供时为 None
+    pub downloads: Option<u64>,
+}
+
+/// 版本的发布渠道, 按稳定性从高到低排列
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Channel {
+    #[default]
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    /// 解析配置或平台 API 中的渠道名称
+    pub fn parse(name: &str) -> Option<Channel> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "release" => Some(Channel::Release),
+            "beta" => Some(Channel::Beta),
+            "alpha" => Some(Channel::Alpha),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Release => "release",
+            Channel::Beta => "beta",
+            Channel::Alpha => "alpha",
+        }
+    }
+}
+
+/// 选择版本的条件
+#[derive(Debug, Clone)]
+pub struct Criteria<'a> {
+    pub mc_version: &'a str,
+    pub loader: &'a str,
+    /// 允许的最低稳定性, 仅在没有更稳定的兼容版本时才使用较不稳定的版本
+    pub channel: Channel,
+    /// 当前安装的版本号
+    pub installed: Option<&'a str>,
+    /// 是否允许选择比当前安装版本更旧的版本
+    pub allow_downgrade: bool,
+    /// 固定安装的版本号, 设置时忽略渠道与降级限制
+    pub pin: Option<&'a str>,
+    /// 没有精确匹配的版本时, 是否接受为协议兼容的其他 Minecraft 版本发布的版本
+    pub compatible_versions: bool,
+}
+
+impl Criteria<'_> {
+    /// 可接受的 Minecraft 版本, 第一个为目标版本
+    pub fn game_versions(&self) -> Vec<&str> {
+        let mut versions = vec![self.mc_version];
+        if self.compatible_versions {
+            versions.extend(compatible_versions(self.mc_version));
+        }
+        versions
+    }
+
+    /// 版本号是否符合固定的版本, 未固定时总是符合
+    pub fn pin_matches(&self, version_number: &str) -> bool {
+        self.pin.is_none_or(|pin| {
+            version_number == pin
+                || compare_mod_versions(version_number, pin, self.loader) == Some(Ordering::Equal)
+        })
+    }
+}
+
+/// 平台上的单个版本 (文件)
+#[derive(Debug, Clone)]
+pub struct RemoteVersion {
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    /// 支持的 Minecraft 版本
+    pub game_versions: Vec<String>,
+    /// 支持的加载器 (小写)
+    pub loaders: Vec<String>,
+    /// 发布时间 (RFC 3339)
+    pub published: String,
+    pub channel: Channel,
+    pub file_name: String,
+    /// 下载地址, 作者禁止第三方分发时为 None
+    pub url: Option<String>,
+    pub hashes: ExpectedHashes,
+    /// 下载次数, 平台未提供时为 None
+    pub downloads: Option<u64>,
+    /// 更新日志, 平台未提供时为 None
+    pub changelog: Option<String>,
+}
+
+impl RemoteVersion {
+    /// 是否同时支持目标 Minecraft 版本与加载器
+    pub fn supports(&self, mc_version: &str, loader: &str) -> bool {
+        self.game_versions
+            .iter()
+            .any(|v| v.trim() == mc_version.trim())
+            && self
+                .loaders
+                .iter()
+                .any(|l| l.trim().eq_ignore_ascii_case(loader.trim()))
+    }
+
+    /// 更新日志中第一行正文 (跳过 Markdown 标题, 去除列表与引用标记), 超过 `max_chars` 个字符时截断
+    pub fn changelog_excerpt(&self, max_chars: usize) -> Option<String> {
+        let line = self
+            .changelog
+            .as_deref()?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.trim_start_matches(['-', '*', '>', ' ']).trim())
+            .find(|line| !line.is_empty())?;
+        if line.chars().count() > max_chars {
+            Some(format!(
+                "{}...",
+                line.chars().take(max_chars).collect::<String>()
+            ))
+        } else {
+            Some(line.to_string())
+        }
+    }
+
+    /// 发布日期 (YYYY-MM-DD), 未知时为空
+    pub fn published_date(&self) -> &str {
+        self.published.get(..10).unwrap_or(&self.published)
+    }
+}
+
+/// 选中的版本及选择原因
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub version: RemoteVersion,
+    pub reason: String,
+    /// 非精确匹配时, 该版本实际标注的 Minecraft 版本
+    pub compatible_with: Option<String>,
+}
+
+impl Resolved {
+    /// 选中的版本不支持目标版本而是协议兼容的其他版本时, 在原因中注明
+    pub fn new(version: RemoteVersion, reason: String, criteria: &Criteria) -> Resolved {
+        let compatible_with = if version.supports(criteria.mc_version, criteria.loader) {
+            None
+        } else {
+            criteria
+                .game_versions()
+                .into_iter()
+                .find(|mc| version.supports(mc, criteria.loader))
+                .map(str::to_string)
+        };
+        let reason = match criteria.pin {
+            Some(pin) => format!("Version pinned to {} in the config", pin),
+            None => reason,
+        };
+        let reason = match &compatible_with {
+            Some(mc) => format!(
+                "{} (compatible, not exact: built for Minecraft {})",
+                reason, mc
+            ),
+            None => reason,
+        };
+        Resolved {
+            version,
+            reason,
+            compatible_with,
+        }
+    }
+}
+
+/// Mod 下载来源
+/// 迁移时按配置顺序依次调用: 识别项目 -> 列出版本 -> 选择版本 -> 下载
+pub trait ModProvider: Send + Sync {
+    /// 配置文件中使用的来源 ID (如 `modrinth`)
+    fn id(&self) -> &'static str;
+
+    /// 显示名称 (如 `Modrinth`)
+    fn name(&self) -> &'static str;
+
+    /// 根据本地 Jar 文件与 Mod ID 找到平台上的项目, 找不到时返回 None
+    fn identify(&self, mod_id: &str, jar: &Path) -> Result<Option<RemoteProject>, Error>;
+
+    /// 按配置中指定的项目 ID 或 slug 查询项目, 找不到时返回 None
+    fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error>;
+
+    /// 按名称搜索项目, 结果按平台的相关度排序
+    fn search(
+        &self,
+        query: &str,
+        mc_version: &str,
+        loader: &str,
+    ) -> Result<Vec<RemoteProject>, Error>;
+
+    /// 列出项目中支持可接受的 Minecraft 版本 ([`Criteria::game_versions`]) 与加载器的版本
+    fn list_versions(
+        &self,
+        project: &RemoteProject,
+        criteria: &Criteria,
+    ) -> Result<Vec<RemoteVersion>, Error>;
+
+    /// 从候选版本中选出要安装的版本, 默认在最稳定的可用渠道中选择版本号最新的兼容版本
+    fn resolve(
+        &self,
+        versions: Vec<RemoteVersion>,
+        criteria: &Criteria,
+    ) -> Result<Resolved, Error> {
+        let version = pick_version(versions, criteria)?;
+        let reason = format!(
+            "Newest {} {} supporting Minecraft {} on {}, by version number",
+            self.name(),
+            version.channel.name(),
+            criteria.mc_version,
+            criteria.loader
+        );
+        Ok(Resolved::new(version, reason, criteria))
+    }
+
+    /// 下载选中的版本到 `output_dir`
+    fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error>;
+
+    /// 项目页面地址, 用于迁移报告; 来源没有项目页面时返回 None
+    fn project_url(&self, _project_id: &str) -> Option<String> {
+        None
+    }
+}
+
+/// 选出兼容的版本: 优先使用精确支持目标版本的版本, 排除比当前安装版本更旧的版本,
+/// 确定允许范围内最稳定的可用渠道, 再选出该渠道中版本号最新的版本, 版本号相同时比较发布时间
+pub fn pick_version(
+    versions: Vec<RemoteVersion>,
+    criteria: &Criteria,
+) -> Result<RemoteVersion, Error> {
+    candidate_versions(versions, criteria)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| no_version_supports(criteria))
+}
+
+/// 所有可选择的兼容版本, 按 [`pick_version`] 的优先顺序排列 (第一个即默认选择的版本)
+/// 先按渠道从稳定到不稳定, 同一渠道内从新到旧; 固定版本时只包含符合的版本
+pub fn candidate_versions(
+    versions: Vec<RemoteVersion>,
+    criteria: &Criteria,
+) -> Result<Vec<RemoteVersion>, Error> {
+    let game_versions = criteria.game_versions();
+    let (exact, compatible): (Vec<RemoteVersion>, Vec<RemoteVersion>) = versions
+        .into_iter()
+        .filter(|v| {
+            game_versions
+                .iter()
+                .any(|mc| v.supports(mc, criteria.loader))
+        })
+        .partition(|v| v.supports(criteria.mc_version, criteria.loader));
+    let compatible = if exact.is_empty() { compatible } else { exact };
+    if let Some(pin) = criteria.pin {
+        let mut pinned: Vec<RemoteVersion> = compatible
+            .into_iter()
+            .filter(|v| criteria.pin_matches(&v.version_number))
+            .collect();
+        if pinned.is_empty() {
+            return Err(Error::NoCompatibleVersion(format!(
+                "the pinned version {} is not available for Minecraft {} on {}",
+                pin, criteria.mc_version, criteria.loader
+            )));
+        }
+        pinned.sort_by(|a, b| newer(b, a, criteria.loader));
+        return Ok(pinned);
+    }
+    if compatible.is_empty() {
+        return Err(no_version_supports(criteria));
+    }
+    let compatible: Vec<RemoteVersion> = match criteria.installed {
+        Some(installed) if !criteria.allow_downgrade => compatible
+            .into_iter()
+            .filter(|v| {
+                compare_mod_versions(&v.version_number, installed, criteria.loader)
+                    != Some(Ordering::Less)
+            })
+            .collect(),
+        _ => compatible,
+    };
+    let Some(most_stable) = compatible.iter().map(|v| v.channel).min() else {
+        return Err(Error::NoCompatibleVersion(format!(
+            "only versions older than the installed {} are compatible; set `allow_downgrade = true` to allow them",
+            criteria.installed.unwrap_or_default()
+        )));
+    };
+    if most_stable > criteria.channel {
+        return Err(Error::NoCompatibleVersion(format!(
+            "no {} found, only {} versions; set `channel = \"{}\"` to allow them",
+            criteria.channel.name(),
+            most_stable.name(),
+            most_stable.name()
+        )));
+    }
+    let mut allowed: Vec<RemoteVersion> = compatible
+        .into_iter()
+        .filter(|v| v.channel <= criteria.channel)
+        .collect();
+    allowed.sort_by(|a, b| {
+        a.channel
+            .cmp(&b.channel)
+            .then_with(|| newer(b, a, criteria.loader))
+    });
+    Ok(allowed)
+}
+
+fn no_version_supports(criteria: &Criteria) -> Error {
+    Error::NoCompatibleVersion(format!(
+        "no version supports Minecraft {} on {}",
+        criteria.mc_version, criteria.loader
+    ))
+}
+
+/// 按版本号比较, 无法比较或相同时比较发布时间
+fn newer(a: &RemoteVersion, b: &RemoteVersion, loader: &str) -> Ordering {
+    compare_mod_versions(&a.version_number, &b.version_number, loader)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| compare_timestamps(&a.published, &b.published))
+}
+
+/// 将官方 CDN 地址替换为配置的镜像地址
+pub fn rewrite_cdn(url: String, official: &[&str], cdn: Option<&str>) -> String {
+    let Some(cdn) = cdn else {
+        return url;
+    };
+    match official.iter().find_map(|host| url.strip_prefix(host)) {
+        Some(path) => format!("{}{}", cdn.trim_end_matches('/'), path),
+        None => url,
+    }
+}
+
+/// 下载选中的版本并校验哈希, 供各来源的 [`ModProvider::download`] 使用
+pub fn download_version(
+    client: &Client,
+    provider: &dyn ModProvider,
+    resolved: &Resolved,
+    output_dir: &Path,
+    network: &NetworkConfig,
+    cache: &Cache,
+) -> Result<DownloadedMod, Error> {
+    let version = &resolved.version;
+    let url = version
+        .url
+        .as_deref()
+        .ok_or_else(|| Error::DistributionDisabled(version.file_name.clone()))?;
+    let save_path = output_dir.join(&version.file_name);
+    download_file(client, url, &save_path, &version.hashes, network, cache)?;
+    Ok(downloaded_mod(provider, resolved, url, save_path))
+}
+
+/// 记录已保存到 `path` 的版本
+pub fn downloaded_mod(
+    provider: &dyn ModProvider,
+    resolved: &Resolved,
+    url: &str,
+    path: PathBuf,
+) -> DownloadedMod {
+    let version = &resolved.version;
+    DownloadedMod {
+        provider: provider.id().to_string(),
+        project_id: version.project_id.clone(),
+        version_id: version.version_id.clone(),
+        version_number: version.version_number.clone(),
+        file_name: version.file_name.clone(),
+        url: url.to_string(),
+        reason: resolved.reason.clone(),
+        compatible_with: resolved.compatible_with.clone(),
+        path,
+    }
+}
+
+/// 下载文件并读取其中声明的加载器与 Minecraft 版本范围, 供无法从元数据判断兼容性的来源使用
+/// 兼容时记录文件的哈希值 (之后的下载可直接从缓存复制) 与范围内可接受的 Minecraft 版本, 返回 true
+pub fn inspect_jar(
+    client: &Client,
+    version: &mut RemoteVersion,
+    criteria: &Criteria,
+    network: &NetworkConfig,
+    cache: &Cache,
+) -> Result<bool, Error> {
+    let Some(url) = &version.url else {
+        return Ok(false);
+    };
+    let dir = InspectDir::create()?;
+    let path = dir.0.join(&version.file_name);
+    let hashes = download_file(client, url, &path, &version.hashes, network, cache)?;
+    let declared_loader = detect_mod(&path).ok().flatten();
+    let range = get_minecraft_range(&path).ok().flatten();
+    drop(dir);
+    if declared_loader.as_ref().and_then(loader_name) != Some(criteria.loader) {
+        return Ok(false);
+    }
+    let game_versions: Vec<String> = criteria
+        .game_versions()
+        .into_iter()
+        .filter(|mc| {
+            range
+                .as_deref()
+                .is_some_and(|range| version_in_range(mc, range))
+        })
+        .map(str::to_string)
+        .collect();
+    if game_versions.is_empty() {
+        return Ok(false);
+    }
+    version.game_versions = game_versions;
+    version.loaders = vec![criteria.loader.to_string()];
+    version.hashes = ExpectedHashes {
+        sha1: Some(hashes.sha1),
+        sha256: Some(hashes.sha256),
+        sha512: Some(hashes.sha512),
+    };
+    Ok(true)
+}
+
+/// 下载检查用的临时目录, 每次检查使用单独的目录, 离开作用域时连同其中的文件一起删除
+/// 多个迁移线程同时检查时不会删除彼此的目录
+struct InspectDir(PathBuf);
+
+impl InspectDir {
+    fn create() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "MiraMigrator-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, atomic::Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        Ok(InspectDir(dir))
+    }
+}
+
+impl Drop for InspectDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, Criteria, InspectDir, RemoteVersion, Resolved, candidate_versions};
+    use crate::error::Error;
+    use std::fs;
+
+    fn version(number: &str, channel: Channel, published: &str, mc_version: &str) -> RemoteVersion {
+        RemoteVersion {
+            project_id: "sodium".to_string(),
+            version_id: number.to_string(),
+            version_number: number.to_string(),
+            game_versions: vec![mc_version.to_string()],
+            loaders: vec!["fabric".to_string()],
+            published: published.to_string(),
+            channel,
+            file_name: format!("sodium-{}.jar", number),
+            url: None,
+            hashes: Default::default(),
+            downloads: None,
+            changelog: None,
+        }
+    }
+
+    /// 1.20.4 的各渠道版本, 发布时间与版本号顺序一致
+    fn versions() -> Vec<RemoteVersion> {
+        vec![
+            version("0.5.7", Channel::Release, "2024-01-01T00:00:00Z", "1.20.4"),
+            version("0.5.8", Channel::Release, "2024-02-01T00:00:00Z", "1.20.4"),
+            version(
+                "0.6.0-beta.1",
+                Channel::Beta,
+                "2024-03-01T00:00:00Z",
+                "1.20.4",
+            ),
+            version(
+                "0.6.0-alpha.2",
+                Channel::Alpha,
+                "2024-02-15T00:00:00Z",
+                "1.20.4",
+            ),
+            version("0.5.9", Channel::Release, "2024-02-10T00:00:00Z", "1.20.2"),
+        ]
+    }
+
+    fn criteria(channel: Channel) -> Criteria<'static> {
+        Criteria {
+            mc_version: "1.20.4",
+            loader: "fabric",
+            channel,
+            installed: None,
+            allow_downgrade: true,
+            pin: None,
+            compatible_versions: false,
+        }
+    }
+
+    fn numbers(result: Result<Vec<RemoteVersion>, Error>) -> Vec<String> {
+        result
+            .unwrap()
+            .into_iter()
+            .map(|v| v.version_number)
+            .collect()
+    }
+
+    #[test]
+    fn prefers_stable_channels() {
+        assert_eq!(
+            numbers(candidate_versions(versions(), &criteria(Channel::Release))),
+            ["0.5.8", "0.5.7"]
+        );
+        // 较不稳定的渠道排在所有稳定版本之后, 即使发布得更晚
+        assert_eq!(
+            numbers(candidate_versions(versions(), &criteria(Channel::Alpha))),
+            ["0.5.8", "0.5.7", "0.6.0-beta.1", "0.6.0-alpha.2"]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_configured_channel() {
+        let unstable: Vec<RemoteVersion> = versions()
+            .into_iter()
+            .filter(|v| v.channel != Channel::Release)
+            .collect();
+        let Err(Error::NoCompatibleVersion(message)) =
+            candidate_versions(unstable.clone(), &criteria(Channel::Release))
+        else {
+            panic!("expected no compatible version");
+        };
+        assert!(message.contains("channel = \"beta\""), "{}", message);
+        assert_eq!(
+            numbers(candidate_versions(
+                unstable.clone(),
+                &criteria(Channel::Beta)
+            )),
+            ["0.6.0-beta.1"]
+        );
+        assert_eq!(
+            numbers(candidate_versions(unstable, &criteria(Channel::Alpha))),
+            ["0.6.0-beta.1", "0.6.0-alpha.2"]
+        );
+    }
+
+    #[test]
+    fn filters_downgrades() {
+        let mut criteria = criteria(Channel::Release);
+        criteria.installed = Some("0.5.8");
+        assert_eq!(
+            numbers(candidate_versions(versions(), &criteria)),
+            ["0.5.8", "0.5.7"]
+        );
+        criteria.allow_downgrade = false;
+        assert_eq!(
+            numbers(candidate_versions(versions(), &criteria)),
+            ["0.5.8"]
+        );
+        criteria.installed = Some("0.6.0");
+        assert!(matches!(
+            candidate_versions(versions(), &criteria),
+            Err(Error::NoCompatibleVersion(message)) if message.contains("allow_downgrade")
+        ));
+        // 固定版本时不受降级限制
+        criteria.pin = Some("0.5.7");
+        assert_eq!(
+            numbers(candidate_versions(versions(), &criteria)),
+            ["0.5.7"]
+        );
+    }
+
+    #[test]
+    fn prefers_exact_matches_over_compatible_versions() {
+        let mut criteria = criteria(Channel::Release);
+        criteria.mc_version = "1.20.1";
+        let builds = vec![
+            version("2.0", Channel::Release, "2024-03-01T00:00:00Z", "1.20"),
+            version("1.9", Channel::Release, "2024-01-01T00:00:00Z", "1.20.1"),
+        ];
+        // 默认不接受其他 Minecraft 版本的构建
+        assert!(candidate_versions(builds[..1].to_vec(), &criteria).is_err());
+
+        criteria.compatible_versions = true;
+        assert_eq!(
+            numbers(candidate_versions(builds.clone(), &criteria)),
+            ["1.9"]
+        );
+        let compatible = candidate_versions(builds[..1].to_vec(), &criteria).unwrap();
+        assert_eq!(compatible.len(), 1);
+        let resolved = Resolved::new(compatible[0].clone(), "Newest".to_string(), &criteria);
+        assert_eq!(resolved.compatible_with.as_deref(), Some("1.20"));
+        assert!(resolved.reason.contains("compatible, not exact"));
+    }
+
+    #[test]
+    fn inspect_dirs_are_unique_and_removed_on_drop() {
+        let first = InspectDir::create().unwrap();
+        let second = InspectDir::create().unwrap();
+        assert_ne!(first.0, second.0);
+        fs::write(first.0.join("partial.jar"), b"partial").unwrap();
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.0.exists());
+    }
+}
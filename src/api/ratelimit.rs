@@ -0,0 +1,55 @@
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Modrinth 公开的速率限制: 每分钟 300 次请求
+const MODRINTH_REQUESTS_PER_MINUTE: u32 = 300;
+/// CurseForge 未公开具体限制, 使用较保守的值
+const CURSEFORGE_REQUESTS_PER_MINUTE: u32 = 120;
+
+/// 简单的请求间隔限速器, 多个线程共享同一个时间槽
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(60) / requests_per_minute.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// 等待直到可以发出下一个请求
+    pub fn acquire(&self) {
+        let wait = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot - now
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+
+    /// 暂停之后的所有请求一段时间 (例如服务端额度耗尽时)
+    pub fn pause_for(&self, duration: Duration) {
+        let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        *next_slot = (*next_slot).max(Instant::now() + duration);
+    }
+}
+
+/// Modrinth API 共享限速器
+pub fn modrinth() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(MODRINTH_REQUESTS_PER_MINUTE))
+}
+
+/// CurseForge API 共享限速器
+pub fn curseforge() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(CURSEFORGE_REQUESTS_PER_MINUTE))
+}
@@ -0,0 +1,382 @@
+use crate::config::CacheConfig;
+use crate::utils::hash::{FileHashes, hash_file, to_hex};
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 缓存中的 Jar 文件
+#[derive(Debug, Clone)]
+pub struct CachedFile {
+    pub sha1: String,
+    pub file_name: String,
+    pub size: u64,
+    /// 最近一次使用的时间
+    pub last_used: SystemTime,
+}
+
+/// 缓存清理结果
+#[derive(Debug, Default)]
+pub struct PruneSummary {
+    pub files: usize,
+    pub metadata: usize,
+    pub bytes: u64,
+}
+
+/// 跨运行, 跨实例共享的下载缓存
+/// Jar 文件按 SHA-1 存放在 `files/` 中, API 响应按请求地址存放在 `metadata/` 中
+#[derive(Debug, Clone)]
+pub struct Cache {
+    root: Option<PathBuf>,
+    metadata_ttl: Duration,
+    max_age: Duration,
+}
+
+impl Cache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Cache {
+            root: config.enabled.then(|| config.dir.clone()).flatten(),
+            metadata_ttl: config.metadata_ttl,
+            max_age: config.max_age,
+        }
+    }
+
+    /// 缓存根目录, 禁用缓存时为 None
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// 从缓存复制文件到 `path`, 返回缓存文件的哈希值
+    /// 未命中时返回 None, 缓存文件损坏时将其删除
+    pub fn restore_file(&self, sha1: &str, path: &Path) -> Option<FileHashes> {
+        let cached = self.file_path(sha1)?;
+        if !cached.is_file() {
+            return None;
+        }
+        let hashes = match hash_file(&cached) {
+            Ok(hashes) if hashes.sha1.eq_ignore_ascii_case(sha1) => hashes,
+            _ => {
+                let _ = fs::remove_file(&cached);
+                let _ = fs::remove_file(cached.with_extension("json"));
+                return None;
+            }
+        };
+        fs::copy(&cached, path).ok()?;
+        // 更新修改时间, 作为最近使用时间
+        if let Ok(file) = File::options().append(true).open(&cached) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(hashes)
+    }
+
+    /// 将已校验的文件加入缓存
+    pub fn store_file(
+        &self,
+        sha1: &str,
+        file_name: &str,
+        url: &str,
+        path: &Path,
+    ) -> io::Result<()> {
+        let Some(cached) = self.file_path(sha1) else {
+            return Ok(());
+        };
+        if let Some(parent) = cached.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let part = part_path(&cached);
+        fs::copy(path, &part)?;
+        fs::rename(&part, &cached)?;
+        let info = json!({ "file_name": file_name, "url": url });
+        fs::write(cached.with_extension("json"), info.to_string())?;
+        Ok(())
+    }
+
+    /// 读取未过期的 API 响应
+    pub fn get_metadata(&self, key: &str) -> Option<String> {
+        let path = self.metadata_path(key)?;
+        let entry: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        let fetched_at = entry["fetched_at"].as_u64()?;
+        if now_secs().saturating_sub(fetched_at) > self.metadata_ttl.as_secs() {
+            return None;
+        }
+        entry["body"].as_str().map(|s| s.to_string())
+    }
+
+    /// 保存 API 响应
+    pub fn put_metadata(&self, key: &str, body: &str) {
+        let Some(path) = self.metadata_path(key) else {
+            return;
+        };
+        let entry = json!({ "key": key, "fetched_at": now_secs(), "body": body });
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let part = part_path(&path);
+        if fs::write(&part, entry.to_string()).is_ok() {
+            let _ = fs::rename(&part, &path);
+        }
+    }
+
+    /// 列出缓存中的所有 Jar 文件, 按最近使用时间降序
+    pub fn list_files(&self) -> io::Result<Vec<CachedFile>> {
+        let mut files = Vec::new();
+        for path in self.entries("files", "jar")? {
+            let metadata = fs::metadata(&path)?;
+            let sha1 = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let file_name = fs::read_to_string(path.with_extension("json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                .and_then(|v| v["file_name"].as_str().map(|s| s.to_string()))
+                .unwrap_or_else(|| sha1.clone());
+            files.push(CachedFile {
+                sha1,
+                file_name,
+                size: metadata.len(),
+                last_used: metadata.modified().unwrap_or(UNIX_EPOCH),
+            });
+        }
+        files.sort_by_key(|f| Reverse(f.last_used));
+        Ok(files)
+    }
+
+    /// 缓存的 API 响应数量
+    pub fn metadata_count(&self) -> io::Result<usize> {
+        Ok(self.entries("metadata", "json")?.len())
+    }
+
+    /// 删除过期的 API 响应, 以及超过最长保留时间未使用的 Jar 文件
+    pub fn prune(&self) -> io::Result<PruneSummary> {
+        let mut summary = PruneSummary::default();
+        let now = SystemTime::now();
+        for path in self.entries("files", "jar")? {
+            let metadata = fs::metadata(&path)?;
+            let age = now
+                .duration_since(metadata.modified().unwrap_or(UNIX_EPOCH))
+                .unwrap_or_default();
+            if age > self.max_age {
+                fs::remove_file(&path)?;
+                let _ = fs::remove_file(path.with_extension("json"));
+                summary.files += 1;
+                summary.bytes += metadata.len();
+            }
+        }
+        for path in self.entries("metadata", "json")? {
+            let expired = fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                .and_then(|v| v["fetched_at"].as_u64())
+                .is_none_or(|t| now_secs().saturating_sub(t) > self.metadata_ttl.as_secs());
+            if expired {
+                summary.bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                fs::remove_file(&path)?;
+                summary.metadata += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// 清空缓存目录
+    pub fn clear(&self) -> io::Result<()> {
+        if let Some(root) = &self.root
+            && root.exists()
+        {
+            fs::remove_dir_all(root)?;
+        }
+        Ok(())
+    }
+
+    fn file_path(&self, sha1: &str) -> Option<PathBuf> {
+        let sha1 = sha1.to_ascii_lowercase();
+        if sha1.len() < 2 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(
+            self.root
+                .as_ref()?
+                .join("files")
+                .join(&sha1[..2])
+                .join(format!("{}.jar", sha1)),
+        )
+    }
+
+    fn metadata_path(&self, key: &str) -> Option<PathBuf> {
+        let digest = to_hex(&Sha1::digest(key.as_bytes()));
+        Some(
+            self.root
+                .as_ref()?
+                .join("metadata")
+                .join(&digest[..2])
+                .join(format!("{}.json", digest)),
+        )
+    }
+
+    /// 列出缓存子目录中指定扩展名的文件
+    fn entries(&self, dir: &str, extension: &str) -> io::Result<Vec<PathBuf>> {
+        let mut entries = Vec::new();
+        let Some(root) = &self.root else {
+            return Ok(entries);
+        };
+        let dir = root.join(dir);
+        if !dir.is_dir() {
+            return Ok(entries);
+        }
+        for shard in fs::read_dir(dir)? {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(shard)? {
+                let path = entry?.path();
+                if path.extension().unwrap_or_default() == extension {
+                    entries.push(path);
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// 写入缓存用的临时文件名, 避免多个线程或进程写入同一临时文件
+fn part_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    path.with_extension(format!(
+        "{}-{}.part",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+    use crate::config::CacheConfig;
+    use crate::utils::hash::hash_file;
+    use crate::utils::testing::TestDir;
+    use serde_json::json;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    fn cache(dir: &TestDir, metadata_ttl: Duration, max_age: Duration) -> Cache {
+        Cache::new(&CacheConfig {
+            enabled: true,
+            dir: Some(dir.path().join("cache")),
+            metadata_ttl,
+            max_age,
+        })
+    }
+
+    #[test]
+    fn stores_and_restores_files() {
+        let dir = TestDir::new("cache-files");
+        let cache = cache(&dir, Duration::ZERO, Duration::from_secs(60));
+        let source = dir.path().join("sodium.jar");
+        fs::write(&source, b"jar").unwrap();
+        let hashes = hash_file(&source).unwrap();
+        cache
+            .store_file(&hashes.sha1, "sodium.jar", "https://example.com", &source)
+            .unwrap();
+
+        let restored = dir.path().join("restored.jar");
+        assert_eq!(
+            cache.restore_file(&hashes.sha1.to_uppercase(), &restored),
+            Some(hashes.clone())
+        );
+        assert_eq!(fs::read(&restored).unwrap(), b"jar");
+        let files = cache.list_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, "sodium.jar");
+        assert_eq!(files[0].size, 3);
+
+        // 内容与 SHA-1 不符的缓存文件被删除
+        let cached = cache.file_path(&hashes.sha1).unwrap();
+        fs::write(&cached, b"corrupt").unwrap();
+        assert_eq!(cache.restore_file(&hashes.sha1, &restored), None);
+        assert!(!cached.exists());
+        assert_eq!(cache.restore_file("../../escape", &restored), None);
+    }
+
+    #[test]
+    fn disabled_caches_store_nothing() {
+        let dir = TestDir::new("cache-disabled");
+        let cache = Cache::new(&CacheConfig {
+            enabled: false,
+            dir: Some(dir.path().join("cache")),
+            metadata_ttl: Duration::from_secs(60),
+            max_age: Duration::from_secs(60),
+        });
+        let source = dir.path().join("mod.jar");
+        fs::write(&source, b"jar").unwrap();
+        let sha1 = hash_file(&source).unwrap().sha1;
+        cache.store_file(&sha1, "mod.jar", "", &source).unwrap();
+        cache.put_metadata("key", "body");
+        assert_eq!(
+            cache.restore_file(&sha1, &dir.path().join("copy.jar")),
+            None
+        );
+        assert_eq!(cache.get_metadata("key"), None);
+        assert!(!dir.path().join("cache").exists());
+    }
+
+    #[test]
+    fn expires_metadata() {
+        let dir = TestDir::new("cache-metadata");
+        let cache = cache(&dir, Duration::from_secs(60), Duration::from_secs(60));
+        cache.put_metadata("https://example.com/a", "fresh");
+        assert_eq!(
+            cache.get_metadata("https://example.com/a").as_deref(),
+            Some("fresh")
+        );
+        let stale = cache.metadata_path("https://example.com/b").unwrap();
+        fs::create_dir_all(stale.parent().unwrap()).unwrap();
+        let entry = json!({ "key": "https://example.com/b", "fetched_at": 1, "body": "stale" });
+        fs::write(&stale, entry.to_string()).unwrap();
+        assert_eq!(cache.get_metadata("https://example.com/b"), None);
+
+        let summary = cache.prune().unwrap();
+        assert_eq!(summary.metadata, 1);
+        assert_eq!(cache.metadata_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn prunes_unused_files() {
+        let dir = TestDir::new("cache-prune");
+        let cache = cache(&dir, Duration::from_secs(60), Duration::from_secs(60 * 60));
+        for (name, contents) in [("old.jar", "old jar"), ("new.jar", "new")] {
+            let source = dir.path().join(name);
+            fs::write(&source, contents).unwrap();
+            let sha1 = hash_file(&source).unwrap().sha1;
+            cache.store_file(&sha1, name, "", &source).unwrap();
+            if name == "old.jar" {
+                let cached = cache.file_path(&sha1).unwrap();
+                let last_used = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+                File::options()
+                    .append(true)
+                    .open(cached)
+                    .unwrap()
+                    .set_modified(last_used)
+                    .unwrap();
+            }
+        }
+        let summary = cache.prune().unwrap();
+        assert_eq!(summary.files, 1);
+        assert_eq!(summary.bytes, 7);
+        let files = cache.list_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, "new.jar");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::utils::output::{OutputFormat, Verbosity};
+
+/// 命令行子命令
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// 交互式版本迁移 (默认)
+    Start,
+    /// 在全屏界面中查看并执行迁移
+    Tui,
+    /// 按锁文件安装
+    Install { locked: bool },
+    /// 管理下载缓存
+    Cache(CacheAction),
+    /// 管理 Mod ID 映射数据
+    Mappings(MappingsAction),
+    /// 启动本地 HTTP API
+    Serve,
+    /// 显示帮助
+    Help,
+}
+
+/// `cache` 子命令的操作
+#[derive(Debug, PartialEq)]
+pub enum CacheAction {
+    /// 列出缓存内容
+    List,
+    /// 清理过期内容
+    Prune,
+    /// 清空缓存
+    Clear,
+}
+
+/// `mappings` 子命令的操作
+#[derive(Debug, PartialEq)]
+pub enum MappingsAction {
+    /// 显示映射数据的来源与数量
+    Show,
+    /// 下载最新的映射数据
+    Update,
+}
+
+/// 解析后的命令行参数
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    /// 覆盖配置文件中的并发数量
+    pub jobs: Option<usize>,
+    /// 有多个可选版本时让用户选择
+    pub interactive: bool,
+    /// 覆盖配置文件中的输出详细程度
+    pub verbosity: Option<Verbosity>,
+    /// 标准输出的格式
+    pub output: OutputFormat,
+}
+
+/// 解析命令行参数 (不含程序名)
+/// 无法识别的参数作为错误返回
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek().map(String::as_str) {
+        Some("start") => Command::Start,
+        Some("tui") => Command::Tui,
+        Some("install") => Command::Install { locked: false },
+        Some("cache") => {
+            args.next();
+            let action = match args.next().as_deref() {
+                Some("list") | None => CacheAction::List,
+                Some("prune") => CacheAction::Prune,
+                Some("clear") => CacheAction::Clear,
+                Some(other) => return Err(other.to_string()),
+            };
+            return parse_options(Command::Cache(action), args);
+        }
+        Some("mappings") => {
+            args.next();
+            let action = match args.next().as_deref() {
+                Some("show") | None => MappingsAction::Show,
+                Some("update") => MappingsAction::Update,
+                Some(other) => return Err(other.to_string()),
+            };
+            return parse_options(Command::Mappings(action), args);
+        }
+        Some("serve") => Command::Serve,
+        Some("help") | Some("-h") | Some("--help") => Command::Help,
+        Some(arg) if !arg.starts_with('-') => return Err(arg.to_string()),
+        _ => {
+            // 省略子命令时默认为 start
+            return parse_options(Command::Start, args);
+        }
+    };
+    args.next();
+    parse_options(command, args)
+}
+
+/// 在解析其他参数之前读取 `--output`, 使参数错误也能以请求的格式输出
+/// 缺少或无法识别的格式视为默认格式
+pub fn requested_output(args: &[String]) -> OutputFormat {
+    args.iter()
+        .position(|arg| arg == "--output")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|value| OutputFormat::parse(value))
+        .unwrap_or_default()
+}
+
+fn parse_options<I: Iterator<Item = String>>(
+    mut command: Command,
+    mut args: I,
+) -> Result<Args, String> {
+    let mut jobs = None;
+    let mut interactive = false;
+    let mut verbosity = None;
+    let mut output = OutputFormat::default();
+    while let Some(arg) = args.next() {
+        match (&mut command, arg.as_str()) {
+            (Command::Install { locked }, "--locked") => *locked = true,
+            (Command::Start | Command::Tui | Command::Serve, "-j" | "--jobs") => {
+                let value = args.next().ok_or_else(|| arg.clone())?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => jobs = Some(n),
+                    _ => return Err(format!("{} {}", arg, value)),
+                }
+            }
+            (Command::Start, "-i" | "--interactive") => interactive = true,
+            (Command::Start | Command::Install { .. }, "-q" | "--quiet") => {
+                verbosity = Some(Verbosity::Quiet)
+            }
+            (Command::Start | Command::Install { .. } | Command::Serve, "-v" | "--verbose") => {
+                verbosity = Some(Verbosity::Verbose)
+            }
+            (Command::Start | Command::Install { .. }, "--output") => {
+                let value = args.next().ok_or_else(|| arg.clone())?;
+                output = OutputFormat::parse(&value).ok_or_else(|| format!("{} {}", arg, value))?;
+            }
+            _ => return Err(arg),
+        }
+    }
+    Ok(Args {
+        command,
+        jobs,
+        interactive,
+        verbosity,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, requested_output};
+    use crate::utils::output::OutputFormat;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_the_output_format_before_invalid_arguments() {
+        let invalid = args(&["start", "--output", "json-lines", "--bogus"]);
+        assert!(parse_args(invalid.clone()).is_err());
+        assert_eq!(requested_output(&invalid), OutputFormat::JsonLines);
+        assert_eq!(
+            requested_output(&args(&["--output", "xml"])),
+            OutputFormat::Text
+        );
+        assert_eq!(requested_output(&args(&["--output"])), OutputFormat::Text);
+    }
+}
@@ -0,0 +1,831 @@
+use crate::api::provider::Channel;
+use crate::report::report::ReportFormat;
+use crate::utils::output::Verbosity;
+use log::LevelFilter;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use toml::Value;
+
+/// 配置文件名, 位于 Mods 目录中
+pub const CONFIG_FILE_NAME: &str = "miramigrator.toml";
+
+/// 默认同时迁移的 Mod 数量
+const DEFAULT_JOBS: usize = 4;
+/// 默认请求超时 (秒)
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// 默认重试次数
+const DEFAULT_RETRIES: u32 = 3;
+/// 默认首次重试等待时间 (毫秒)
+const DEFAULT_BACKOFF_MS: u64 = 500;
+/// 默认 API 响应缓存有效期 (秒)
+const DEFAULT_METADATA_TTL_SECS: u64 = 60 * 60;
+/// 默认缓存文件最长保留时间 (天)
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+/// 默认下载来源尝试顺序
+const DEFAULT_PROVIDERS: [&str; 2] = ["modrinth", "curseforge"];
+/// MCIM 镜像的 Modrinth API 地址
+const MCIM_MODRINTH_API: &str = "https://mod.mcimirror.top/modrinth/v2";
+/// MCIM 镜像的 CurseForge API 地址
+const MCIM_CURSEFORGE_API: &str = "https://mod.mcimirror.top/curseforge/v1";
+/// MCIM 镜像的文件下载地址
+const MCIM_CDN: &str = "https://mod.mcimirror.top";
+/// 默认的映射数据更新地址
+const DEFAULT_MAPPINGS_URL: &str =
+    "https://raw.githubusercontent.com/Anhecio/MiraMigrator/main/src/mapping/mappings.toml";
+/// 默认日志文件, 位于 Mods 目录中
+const DEFAULT_LOG_FILE: &str = "miramigrator.log";
+/// 默认单个日志文件的大小上限 (KiB)
+const DEFAULT_LOG_MAX_SIZE_KB: u64 = 1024;
+/// 默认保留的日志文件数量 (含当前文件)
+const DEFAULT_LOG_MAX_FILES: usize = 3;
+/// 默认的迁移报告文件名 (不含扩展名), 位于 Mods 目录中
+const DEFAULT_REPORT_FILE: &str = "miramigrator-report";
+/// 默认的 HTTP API 监听地址, 仅允许本机访问
+const DEFAULT_SERVE_ADDRESS: &str = "127.0.0.1:8765";
+/// 默认的本地映射覆盖文件, 位于 Mods 目录中
+const DEFAULT_MAPPINGS_OVERRIDES: &str = "miramigrator-mappings.toml";
+
+/// MiraMigrator 配置
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// 同时解析与下载的 Mod 数量
+    pub jobs: usize,
+    /// 允许的最低发布渠道
+    pub channel: Channel,
+    /// 是否允许安装比当前版本更旧的版本
+    pub allow_downgrade: bool,
+    /// 没有精确匹配时, 是否接受为协议兼容的其他 Minecraft 版本发布的版本
+    pub compatible_versions: bool,
+    /// 有多个可选版本时是否让用户选择, 否则自动选择
+    pub interactive: bool,
+    /// 输出详细程度
+    pub verbosity: Verbosity,
+    /// 各 Mod 的单独规则, 以 Mod ID 为键
+    pub mods: BTreeMap<String, ModRules>,
+    /// 下载来源的尝试顺序 (来源 ID)
+    pub providers: Vec<String>,
+    pub local: LocalRepoConfig,
+    pub modrinth: EndpointConfig,
+    pub curseforge: EndpointConfig,
+    pub github: GitHubConfig,
+    pub maven: MavenConfig,
+    pub mappings: MappingsConfig,
+    pub network: NetworkConfig,
+    pub cache: CacheConfig,
+    pub log: LogConfig,
+    pub report: ReportConfig,
+    pub serve: ServeConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            jobs: DEFAULT_JOBS,
+            channel: Channel::default(),
+            allow_downgrade: false,
+            compatible_versions: false,
+            interactive: false,
+            verbosity: Verbosity::default(),
+            mods: BTreeMap::new(),
+            providers: DEFAULT_PROVIDERS.iter().map(|p| p.to_string()).collect(),
+            local: LocalRepoConfig::default(),
+            modrinth: EndpointConfig::default(),
+            curseforge: EndpointConfig::default(),
+            github: GitHubConfig::default(),
+            maven: MavenConfig::default(),
+            mappings: MappingsConfig::default(),
+            network: NetworkConfig::default(),
+            cache: CacheConfig::default(),
+            log: LogConfig::default(),
+            report: ReportConfig::default(),
+            serve: ServeConfig::default(),
+        }
+    }
+}
+
+/// 单个 Mod 的规则 (`[mods.<id>]`), 在查询任何来源之前应用
+#[derive(Debug, Clone, Default)]
+pub struct ModRules {
+    /// 跳过该 Mod, 保留原文件
+    pub ignore: bool,
+    /// 固定安装的版本号
+    pub pin: Option<String>,
+    /// 只从该来源 (来源 ID) 获取
+    pub provider: Option<String>,
+    /// 直接使用的项目 ID 或 slug, 跳过识别
+    pub project: Option<String>,
+    /// 各来源 (来源 ID) 上的项目 ID 或 slug, 未设置 `project` 时使用, 交互选择的项目保存在这里
+    pub projects: BTreeMap<String, String>,
+    /// 覆盖全局的最低发布渠道
+    pub channel: Option<Channel>,
+    /// 覆盖全局的降级设置
+    pub allow_downgrade: Option<bool>,
+}
+
+impl Config {
+    /// 该 Mod 允许的最低发布渠道
+    pub fn channel_for(&self, mod_id: &str) -> Channel {
+        self.mods
+            .get(mod_id)
+            .and_then(|rules| rules.channel)
+            .unwrap_or(self.channel)
+    }
+
+    /// 该 Mod 是否允许安装比当前版本更旧的版本
+    pub fn allow_downgrade_for(&self, mod_id: &str) -> bool {
+        self.mods
+            .get(mod_id)
+            .and_then(|rules| rules.allow_downgrade)
+            .unwrap_or(self.allow_downgrade)
+    }
+}
+
+/// 离线本地仓库设置, 供 `local` 来源使用
+#[derive(Debug, Clone, Default)]
+pub struct LocalRepoConfig {
+    /// 存放 Jar 文件的目录, 递归扫描并从 Jar 中读取元数据
+    pub dir: Option<PathBuf>,
+    /// 索引文件, 列出各 Jar 文件及其元数据
+    pub index: Option<PathBuf>,
+}
+
+/// 在线来源的 API 与文件下载地址, 未设置时使用官方地址
+#[derive(Debug, Clone, Default)]
+pub struct EndpointConfig {
+    /// API 根地址, 如 `https://api.modrinth.com/v2`
+    pub api_url: Option<String>,
+    /// 替换官方 CDN 的文件下载根地址
+    pub cdn_url: Option<String>,
+}
+
+/// GitHub Releases 来源设置
+#[derive(Debug, Clone, Default)]
+pub struct GitHubConfig {
+    /// API 根地址, 默认为 `https://api.github.com`
+    pub api_url: Option<String>,
+    /// 访问令牌, 未设置时读取 `GITHUB_TOKEN` 环境变量
+    pub token: Option<String>,
+    /// Mod ID 到 `owner/repo` 的映射
+    pub repos: BTreeMap<String, String>,
+}
+
+/// Maven 仓库来源设置
+#[derive(Debug, Clone, Default)]
+pub struct MavenConfig {
+    /// 按顺序查找的仓库地址
+    pub repositories: Vec<String>,
+    /// Mod ID 到 `group:artifact` 的映射
+    pub artifacts: BTreeMap<String, String>,
+}
+
+/// Mod ID 映射数据设置
+#[derive(Debug, Clone)]
+pub struct MappingsConfig {
+    /// `mappings update` 下载的地址
+    pub url: String,
+    /// 下载的映射数据保存位置, 默认为用户数据目录下的 MiraMigrator/mappings.toml
+    pub file: Option<PathBuf>,
+    /// 本地覆盖文件, 优先于内置与下载的映射
+    pub overrides: PathBuf,
+}
+
+impl Default for MappingsConfig {
+    fn default() -> Self {
+        MappingsConfig {
+            url: DEFAULT_MAPPINGS_URL.to_string(),
+            file: dirs::data_dir().map(|dir| dir.join("MiraMigrator").join("mappings.toml")),
+            overrides: PathBuf::from(DEFAULT_MAPPINGS_OVERRIDES),
+        }
+    }
+}
+
+/// 网络请求设置
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// 单次请求超时
+    pub timeout: Duration,
+    /// 临时错误的最大重试次数
+    pub retries: u32,
+    /// 首次重试前的等待时间, 之后每次翻倍
+    pub backoff: Duration,
+    /// 代理地址 (`http://`, `https://`, `socks5://`, `socks5h://`), `none` 表示不使用代理
+    /// 未设置时读取 `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` 与 `NO_PROXY` 环境变量
+    pub proxy: Option<String>,
+    /// 不经过代理的主机, 以逗号分隔, 格式同 `NO_PROXY`
+    pub no_proxy: Option<String>,
+    /// 额外信任的 CA 证书 (PEM), 用于会解密 TLS 的代理
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            retries: DEFAULT_RETRIES,
+            backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+        }
+    }
+}
+
+/// 持久化下载缓存设置
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// 缓存目录, 默认为用户缓存目录下的 MiraMigrator
+    pub dir: Option<PathBuf>,
+    /// API 响应缓存有效期
+    pub metadata_ttl: Duration,
+    /// 未被使用的 Jar 文件在 `cache prune` 时的最长保留时间
+    pub max_age: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            dir: dirs::cache_dir().map(|dir| dir.join("MiraMigrator")),
+            metadata_ttl: Duration::from_secs(DEFAULT_METADATA_TTL_SECS),
+            max_age: Duration::from_secs(DEFAULT_MAX_AGE_DAYS * 24 * 60 * 60),
+        }
+    }
+}
+
+/// 日志文件设置
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub enabled: bool,
+    /// 日志文件路径, 相对于 Mods 目录
+    pub file: PathBuf,
+    /// 写入日志文件的最低级别
+    pub level: LevelFilter,
+    /// 单个日志文件的大小上限, 超过时轮换
+    pub max_size: u64,
+    /// 保留的日志文件数量 (含当前文件)
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            enabled: true,
+            file: PathBuf::from(DEFAULT_LOG_FILE),
+            level: LevelFilter::Debug,
+            max_size: DEFAULT_LOG_MAX_SIZE_KB * 1024,
+            max_files: DEFAULT_LOG_MAX_FILES,
+        }
+    }
+}
+
+/// 迁移报告设置
+#[derive(Debug, Clone)]
+pub struct ReportConfig {
+    /// 写入的报告格式, 为空时不写入报告
+    pub formats: Vec<ReportFormat>,
+    /// 报告文件路径 (不含扩展名), 相对于 Mods 目录
+    pub file: PathBuf,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        ReportConfig {
+            formats: ReportFormat::ALL.to_vec(),
+            file: PathBuf::from(DEFAULT_REPORT_FILE),
+        }
+    }
+}
+
+/// HTTP API 设置 (`serve` 子命令)
+#[derive(Debug, Clone)]
+pub struct ServeConfig {
+    /// 监听地址
+    pub address: String,
+    /// 访问令牌, 未设置时读取 `MIRAMIGRATOR_TOKEN` 环境变量, 仍未设置时每次启动随机生成
+    pub token: Option<String>,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        ServeConfig {
+            address: DEFAULT_SERVE_ADDRESS.to_string(),
+            token: None,
+        }
+    }
+}
+
+impl Config {
+    /// 读取配置文件, 文件不存在时使用默认配置
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        let root = contents
+            .parse::<Value>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Config::from_toml(&root)
+    }
+
+    fn from_toml(root: &Value) -> Result<Self, Box<dyn Error>> {
+        let mut config = Config::default();
+        if let Some(migration) = root.get("migration") {
+            if let Some(jobs) = get_integer(migration, "migration.jobs", "jobs")? {
+                config.jobs = positive(jobs, "migration.jobs")?;
+            }
+            if let Some(channel) = get_channel(migration, "migration.channel")? {
+                config.channel = channel;
+            }
+            if let Some(allow) =
+                get_bool(migration, "migration.allow_downgrade", "allow_downgrade")?
+            {
+                config.allow_downgrade = allow;
+            }
+            if let Some(compatible) = get_bool(
+                migration,
+                "migration.compatible_versions",
+                "compatible_versions",
+            )? {
+                config.compatible_versions = compatible;
+            }
+            if let Some(interactive) = get_bool(migration, "migration.interactive", "interactive")?
+            {
+                config.interactive = interactive;
+            }
+        }
+        if let Some(output) = root.get("output")
+            && let Some(verbosity) = get_string(output, "output.verbosity", "verbosity")?
+        {
+            config.verbosity = Verbosity::parse(&verbosity)
+                .ok_or("`output.verbosity` must be \"quiet\", \"normal\" or \"verbose\"")?;
+        }
+        if let Some(mods) = root.get("mods") {
+            let mods = mods.as_table().ok_or("`mods` must be a table")?;
+            for (mod_id, table) in mods {
+                let name = format!("mods.{}", mod_id);
+                if !table.is_table() {
+                    return Err(format!("`{}` must be a table", name).into());
+                }
+                let rules = ModRules {
+                    ignore: get_bool(table, &format!("{}.ignore", name), "ignore")?
+                        .unwrap_or(false),
+                    pin: get_string(table, &format!("{}.pin", name), "pin")?,
+                    provider: get_string(table, &format!("{}.provider", name), "provider")?,
+                    project: get_string(table, &format!("{}.project", name), "project")?,
+                    projects: get_string_table(table, &format!("{}.projects", name), "projects")?,
+                    channel: get_channel(table, &format!("{}.channel", name))?,
+                    allow_downgrade: get_bool(
+                        table,
+                        &format!("{}.allow_downgrade", name),
+                        "allow_downgrade",
+                    )?,
+                };
+                config.mods.insert(mod_id.clone(), rules);
+            }
+        }
+        if let Some(providers) = root.get("providers")
+            && let Some(order) = get_string_array(providers, "providers.order", "order")?
+        {
+            if order.is_empty() {
+                return Err("`providers.order` must list at least one provider".into());
+            }
+            config.providers = order;
+        }
+        if let Some(providers) = root.get("providers")
+            && let Some(mirror) = get_string(providers, "providers.mirror", "mirror")?
+        {
+            match mirror.as_str() {
+                "mcim" => {
+                    config.modrinth.api_url = Some(MCIM_MODRINTH_API.to_string());
+                    config.modrinth.cdn_url = Some(MCIM_CDN.to_string());
+                    config.curseforge.api_url = Some(MCIM_CURSEFORGE_API.to_string());
+                    config.curseforge.cdn_url = Some(MCIM_CDN.to_string());
+                }
+                "official" => {}
+                other => {
+                    return Err(format!("Unknown mirror `{}` in `providers.mirror`", other).into());
+                }
+            }
+        }
+        for (key, endpoint) in [
+            ("modrinth", &mut config.modrinth),
+            ("curseforge", &mut config.curseforge),
+        ] {
+            let Some(table) = root.get("providers").and_then(|p| p.get(key)) else {
+                continue;
+            };
+            let name = format!("providers.{}", key);
+            if let Some(url) = get_string(table, &format!("{}.api_url", name), "api_url")? {
+                endpoint.api_url = Some(url);
+            }
+            if let Some(url) = get_string(table, &format!("{}.cdn_url", name), "cdn_url")? {
+                endpoint.cdn_url = Some(url);
+            }
+        }
+        if let Some(github) = root.get("providers").and_then(|p| p.get("github")) {
+            if let Some(url) = get_string(github, "providers.github.api_url", "api_url")? {
+                config.github.api_url = Some(url);
+            }
+            if let Some(token) = get_string(github, "providers.github.token", "token")? {
+                config.github.token = Some(token);
+            }
+            if let Some(repos) = github.get("repos") {
+                let repos = repos
+                    .as_table()
+                    .ok_or("`providers.github.repos` must be a table")?;
+                for (mod_id, repo) in repos {
+                    let name = format!("providers.github.repos.{}", mod_id);
+                    let repo = repo
+                        .as_str()
+                        .filter(|r| r.split('/').filter(|p| !p.is_empty()).count() == 2)
+                        .ok_or_else(|| format!("`{}` must be \"owner/repo\"", name))?;
+                    config.github.repos.insert(mod_id.clone(), repo.to_string());
+                }
+            }
+        }
+        if let Some(maven) = root.get("providers").and_then(|p| p.get("maven")) {
+            if let Some(repositories) =
+                get_string_array(maven, "providers.maven.repositories", "repositories")?
+            {
+                config.maven.repositories = repositories;
+            }
+            if let Some(artifacts) = maven.get("artifacts") {
+                let artifacts = artifacts
+                    .as_table()
+                    .ok_or("`providers.maven.artifacts` must be a table")?;
+                for (mod_id, artifact) in artifacts {
+                    let name = format!("providers.maven.artifacts.{}", mod_id);
+                    let artifact = artifact
+                        .as_str()
+                        .filter(|a| a.split(':').filter(|p| !p.is_empty()).count() == 2)
+                        .ok_or_else(|| format!("`{}` must be \"group:artifact\"", name))?;
+                    config
+                        .maven
+                        .artifacts
+                        .insert(mod_id.clone(), artifact.to_string());
+                }
+            }
+        }
+        if let Some(local) = root.get("providers").and_then(|p| p.get("local")) {
+            if let Some(dir) = get_string(local, "providers.local.dir", "dir")? {
+                config.local.dir = Some(PathBuf::from(dir));
+            }
+            if let Some(index) = get_string(local, "providers.local.index", "index")? {
+                config.local.index = Some(PathBuf::from(index));
+            }
+        }
+        if let Some(mappings) = root.get("mappings") {
+            if let Some(url) = get_string(mappings, "mappings.url", "url")? {
+                config.mappings.url = url;
+            }
+            if let Some(file) = get_string(mappings, "mappings.file", "file")? {
+                config.mappings.file = Some(PathBuf::from(file));
+            }
+            if let Some(overrides) = get_string(mappings, "mappings.overrides", "overrides")? {
+                config.mappings.overrides = PathBuf::from(overrides);
+            }
+        }
+        if let Some(network) = root.get("network") {
+            if let Some(timeout) = get_integer(network, "network.timeout_secs", "timeout_secs")? {
+                config.network.timeout =
+                    Duration::from_secs(positive(timeout, "network.timeout_secs")? as u64);
+            }
+            if let Some(retries) = get_integer(network, "network.retries", "retries")? {
+                config.network.retries =
+                    u32::try_from(retries).map_err(|_| "`network.retries` must not be negative")?;
+            }
+            if let Some(backoff) = get_integer(network, "network.backoff_ms", "backoff_ms")? {
+                config.network.backoff =
+                    Duration::from_millis(positive(backoff, "network.backoff_ms")? as u64);
+            }
+            if let Some(proxy) = get_string(network, "network.proxy", "proxy")? {
+                config.network.proxy = Some(proxy);
+            }
+            if let Some(no_proxy) = get_string(network, "network.no_proxy", "no_proxy")? {
+                config.network.no_proxy = Some(no_proxy);
+            }
+            if let Some(ca_bundle) = get_string(network, "network.ca_bundle", "ca_bundle")? {
+                config.network.ca_bundle = Some(PathBuf::from(ca_bundle));
+            }
+        }
+        if let Some(log) = root.get("log") {
+            if let Some(enabled) = get_bool(log, "log.enabled", "enabled")? {
+                config.log.enabled = enabled;
+            }
+            if let Some(file) = get_string(log, "log.file", "file")? {
+                config.log.file = PathBuf::from(file);
+            }
+            if let Some(level) = get_string(log, "log.level", "level")? {
+                config.log.level = level.parse().map_err(|_| {
+                    "`log.level` must be \"off\", \"error\", \"warn\", \"info\", \"debug\" or \"trace\""
+                })?;
+            }
+            if let Some(size) = get_integer(log, "log.max_size_kb", "max_size_kb")? {
+                config.log.max_size = (positive(size, "log.max_size_kb")? as u64)
+                    .checked_mul(1024)
+                    .ok_or("`log.max_size_kb` is too large")?;
+            }
+            if let Some(files) = get_integer(log, "log.max_files", "max_files")? {
+                config.log.max_files = positive(files, "log.max_files")?;
+            }
+        }
+        if let Some(report) = root.get("report") {
+            if let Some(formats) = get_string_array(report, "report.formats", "formats")? {
+                config.report.formats = formats
+                    .iter()
+                    .map(|name| {
+                        ReportFormat::parse(name).ok_or_else(|| {
+                            format!(
+                                "Unknown format `{}` in `report.formats`, expected \"json\", \"markdown\" or \"html\"",
+                                name
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            if let Some(file) = get_string(report, "report.file", "file")? {
+                config.report.file = PathBuf::from(file);
+            }
+        }
+        if let Some(serve) = root.get("serve") {
+            if let Some(address) = get_string(serve, "serve.address", "address")? {
+                config.serve.address = address;
+            }
+            if let Some(token) = get_string(serve, "serve.token", "token")? {
+                if token.is_empty() {
+                    return Err("`serve.token` must not be empty".into());
+                }
+                config.serve.token = Some(token);
+            }
+        }
+        if let Some(cache) = root.get("cache") {
+            if let Some(enabled) = get_bool(cache, "cache.enabled", "enabled")? {
+                config.cache.enabled = enabled;
+            }
+            if let Some(dir) = get_string(cache, "cache.dir", "dir")? {
+                config.cache.dir = Some(PathBuf::from(dir));
+            }
+            if let Some(ttl) = get_integer(cache, "cache.metadata_ttl_secs", "metadata_ttl_secs")? {
+                config.cache.metadata_ttl = Duration::from_secs(
+                    u64::try_from(ttl)
+                        .map_err(|_| "`cache.metadata_ttl_secs` must be a non-negative integer")?,
+                );
+            }
+            if let Some(days) = get_integer(cache, "cache.max_age_days", "max_age_days")? {
+                config.cache.max_age = Duration::from_secs(
+                    (positive(days, "cache.max_age_days")? as u64)
+                        .checked_mul(24 * 60 * 60)
+                        .ok_or("`cache.max_age_days` is too large")?,
+                );
+            }
+        }
+        for (mod_id, rules) in &config.mods {
+            if let Some(provider) = &rules.provider
+                && !config.providers.contains(provider)
+            {
+                return Err(format!(
+                    "`mods.{}.provider` is `{}`, which is not listed in `providers.order`",
+                    mod_id, provider
+                )
+                .into());
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// 多个迁移线程可能同时保存选择, 依次写入配置文件
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 将规则写入配置文件的 `[mods.<id>]` 表, 覆盖同名的键
+/// 按行修改, 保留文件中的其他内容与注释; 修改后的文件无法读取时不写入并返回错误
+pub fn save_mod_rules(
+    path: &Path,
+    mod_id: &str,
+    rules: &[(&str, &str)],
+) -> Result<(), Box<dyn Error>> {
+    let _lock = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let contents = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let header = format!("[mods.{}]", toml_key(mod_id));
+    let entries: Vec<String> = rules
+        .iter()
+        .map(|(key, value)| format!("{} = {}", key, Value::from(*value)))
+        .collect();
+    let mut lines: Vec<&str> = contents.lines().collect();
+    match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            // 删除表中将被覆盖的键
+            let mut index = start + 1;
+            for _ in start + 1..end {
+                let key = lines[index].split('=').next().unwrap_or_default().trim();
+                if lines[index].contains('=') && rules.iter().any(|(k, _)| *k == key) {
+                    lines.remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                lines.insert(start + 1 + i, entry);
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push("");
+            }
+            lines.push(&header);
+            lines.extend(entries.iter().map(String::as_str));
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    let root = updated
+        .parse::<Value>()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Config::from_toml(&root)?;
+    fs::write(path, updated)?;
+    Ok(())
+}
+
+/// TOML 键, 含有裸键不允许的字符时加引号
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+/// 读取整数配置项, 类型错误时返回带键名的错误
+fn get_integer(table: &Value, name: &str, key: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_integer()
+            .map(Some)
+            .ok_or_else(|| format!("`{}` must be an integer", name).into()),
+    }
+}
+
+/// 读取布尔配置项
+fn get_bool(table: &Value, name: &str, key: &str) -> Result<Option<bool>, Box<dyn Error>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| format!("`{}` must be true or false", name).into()),
+    }
+}
+
+/// 读取字符串配置项
+fn get_string(table: &Value, name: &str, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_str()
+            .map(|s| Some(s.to_string()))
+            .ok_or_else(|| format!("`{}` must be a string", name).into()),
+    }
+}
+
+/// 读取发布渠道配置项 (`release`, `beta` 或 `alpha`)
+fn get_channel(table: &Value, name: &str) -> Result<Option<Channel>, Box<dyn Error>> {
+    match get_string(table, name, "channel")? {
+        None => Ok(None),
+        Some(channel) => Channel::parse(&channel)
+            .map(Some)
+            .ok_or_else(|| format!("`{}` must be \"release\", \"beta\" or \"alpha\"", name).into()),
+    }
+}
+
+/// 读取值均为字符串的表配置项, 缺失时为空表
+fn get_string_table(
+    table: &Value,
+    name: &str,
+    key: &str,
+) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let Some(value) = table.get(key) else {
+        return Ok(BTreeMap::new());
+    };
+    value
+        .as_table()
+        .and_then(|entries| {
+            entries
+                .iter()
+                .map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect::<Option<BTreeMap<_, _>>>()
+        })
+        .ok_or_else(|| format!("`{}` must be a table of strings", name).into())
+}
+
+/// 读取字符串数组配置项
+fn get_string_array(
+    table: &Value,
+    name: &str,
+    key: &str,
+) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_array()
+            .and_then(|arr| {
+                arr.iter()
+                    .map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .map(Some)
+            .ok_or_else(|| format!("`{}` must be an array of strings", name).into()),
+    }
+}
+
+fn positive(value: i64, name: &str) -> Result<usize, Box<dyn Error>> {
+    if value < 1 {
+        return Err(format!("`{}` must be at least 1", name).into());
+    }
+    Ok(value as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, save_mod_rules};
+    use crate::utils::testing::TestDir;
+    use std::fs;
+    use std::time::Duration;
+
+    fn parse(contents: &str) -> Result<Config, String> {
+        Config::from_toml(&contents.parse().unwrap()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn compatible_versions_are_off_by_default() {
+        assert!(!Config::default().compatible_versions);
+        assert!(!parse("[migration]\njobs = 2").unwrap().compatible_versions);
+        let config = parse("[migration]\ncompatible_versions = true").unwrap();
+        assert!(config.compatible_versions);
+    }
+
+    #[test]
+    fn validates_the_metadata_ttl() {
+        let config = parse("[cache]\nmetadata_ttl_secs = 0").unwrap();
+        assert_eq!(config.cache.metadata_ttl, Duration::ZERO);
+        assert_eq!(
+            parse("[cache]\nmetadata_ttl_secs = -1").unwrap_err(),
+            "`cache.metadata_ttl_secs` must be a non-negative integer"
+        );
+    }
+
+    #[test]
+    fn rejects_overflowing_cache_ages() {
+        let config = parse("[cache]\nmax_age_days = 7").unwrap();
+        assert_eq!(config.cache.max_age, Duration::from_secs(7 * 24 * 60 * 60));
+        assert_eq!(
+            parse(&format!("[cache]\nmax_age_days = {}", i64::MAX)).unwrap_err(),
+            "`cache.max_age_days` is too large"
+        );
+    }
+
+    #[test]
+    fn rejects_overflowing_log_sizes() {
+        let config = parse("[log]\nmax_size_kb = 2").unwrap();
+        assert_eq!(config.log.max_size, 2048);
+        assert_eq!(
+            parse(&format!("[log]\nmax_size_kb = {}", i64::MAX)).unwrap_err(),
+            "`log.max_size_kb` is too large"
+        );
+    }
+
+    #[test]
+    fn saves_projects_per_provider() {
+        let dir = TestDir::new("config-save-projects");
+        let path = dir.path().join("miramigrator.toml");
+        fs::write(&path, "[mods.sodium]\nchannel = \"beta\"\n").unwrap();
+        save_mod_rules(&path, "sodium", &[("projects.modrinth", "AANobbMI")]).unwrap();
+        save_mod_rules(&path, "sodium", &[("projects.curseforge", "394468")]).unwrap();
+        save_mod_rules(&path, "sodium", &[("projects.modrinth", "sodium")]).unwrap();
+
+        let config = parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        let rules = &config.mods["sodium"];
+        assert_eq!(rules.projects["modrinth"], "sodium");
+        assert_eq!(rules.projects["curseforge"], "394468");
+        assert_eq!(rules.provider, None);
+        assert_eq!(rules.project, None);
+        assert!(rules.channel.is_some());
+    }
+}
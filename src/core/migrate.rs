@@ -0,0 +1,939 @@
+use super::search::{Picker, SearchQuery, search_project};
+use crate::api::download;
+use crate::api::provider::{Criteria, ModProvider, RemoteVersion, Resolved, candidate_versions};
+use crate::config::{Config, ModRules};
+use crate::error::Error;
+use crate::lock::lockfile::LockedMod;
+use crate::mapping::database::Mappings;
+use crate::utils::loader::{
+    ModLoader, detect_mod, get_mod_authors, get_mod_dependencies, get_mod_id, get_mod_name,
+    get_mod_version, loader_name,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 让用户选择版本时最多列出的版本数量
+const MAX_VERSION_CHOICES: usize = 10;
+
+/// 从 Jar 文件中读取到的 Mod 信息
+#[derive(Debug, Clone)]
+pub struct ScannedMod {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub mod_id: Option<String>,
+    pub version: Option<String>,
+    pub loader: Option<ModLoader>,
+    /// 显示名称, 用于按名称搜索
+    pub name: Option<String>,
+    pub authors: Vec<String>,
+}
+
+impl ScannedMod {
+    /// 读取 Jar 文件的 Mod 元数据
+    pub fn read(jar_file: &fs::DirEntry) -> Self {
+        let path = jar_file.path();
+        ScannedMod {
+            file_name: jar_file.file_name().to_string_lossy().to_string(),
+            mod_id: get_mod_id(&path).ok().flatten(),
+            version: get_mod_version(&path).ok().flatten(),
+            loader: detect_mod(&path).ok().flatten(),
+            name: get_mod_name(&path).ok().flatten(),
+            authors: get_mod_authors(&path).unwrap_or_default(),
+            path,
+        }
+    }
+}
+
+/// 某个下载来源的失败原因
+#[derive(Debug, Clone)]
+pub struct ProviderError {
+    /// 来源显示名称 (Modrinth / CurseForge)
+    pub provider: String,
+    pub error: Error,
+}
+
+/// 单个 Mod 的迁移结果
+#[derive(Debug)]
+pub enum MigrationOutcome {
+    /// 迁移成功
+    Migrated {
+        /// 成功的来源显示名称
+        provider: String,
+        new_version: Option<String>,
+        /// 非精确匹配时, 新版本实际标注的 Minecraft 版本
+        compatible_with: Option<String>,
+        /// 项目页面地址
+        link: Option<String>,
+        /// 新版本新增的必需依赖 (Mod ID), 不会自动安装
+        added_dependencies: Vec<String>,
+        locked: Box<LockedMod>,
+        /// 在成功之前失败的来源
+        errors: Vec<ProviderError>,
+    },
+    /// 按配置跳过, 保留原文件
+    Ignored,
+    /// 无法读取 Mod ID 或版本
+    Unrecognized,
+    /// 无法识别的加载器
+    UnknownLoader,
+    /// 所有来源均失败
+    Failed { errors: Vec<ProviderError> },
+}
+
+impl MigrationOutcome {
+    /// 第 `failed` 个来源失败后接着尝试的来源
+    pub fn next_provider(&self, failed: usize) -> Option<&str> {
+        match self {
+            MigrationOutcome::Migrated {
+                provider, errors, ..
+            } => Some(
+                errors
+                    .get(failed + 1)
+                    .map_or(provider.as_str(), |e| e.provider.as_str()),
+            ),
+            MigrationOutcome::Failed { errors } => {
+                errors.get(failed + 1).map(|e| e.provider.as_str())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 迁移结果及耗时
+#[derive(Debug)]
+pub struct MigrationResult {
+    pub scanned: ScannedMod,
+    pub outcome: MigrationOutcome,
+    pub elapsed: Duration,
+}
+
+/// 一次迁移中所有 Mod 共用的参数
+pub struct Migration<'a> {
+    pub mc_version: &'a str,
+    /// 下载目录, 迁移完成后移回 Mods 目录
+    pub cache_dir: &'a Path,
+    /// 按顺序尝试的下载来源
+    pub providers: &'a [Box<dyn ModProvider>],
+    pub mappings: &'a Mappings,
+    pub config: &'a Config,
+    /// 需要用户选择时调用, 为 None 时不询问
+    pub picker: Option<&'a dyn Picker>,
+}
+
+/// 某个来源上选定的版本, 尚未下载
+#[derive(Debug)]
+pub struct PlannedVersion {
+    /// 来源在 [`Migration::providers`] 中的序号
+    pub provider: usize,
+    pub resolved: Resolved,
+    /// 可选的其他版本, 按默认选择的优先顺序排列, 不受固定版本限制
+    pub candidates: Vec<RemoteVersion>,
+    /// 在选定之前失败的来源
+    pub errors: Vec<ProviderError>,
+}
+
+/// 单个 Mod 的迁移计划
+#[derive(Debug)]
+pub enum Plan {
+    /// 已选定版本, 等待下载
+    Ready(Box<PlannedVersion>),
+    /// 无需或无法下载, 结果已确定
+    Done(MigrationOutcome),
+}
+
+/// 没有单独规则的 Mod 使用的规则
+static NO_RULES: ModRules = ModRules {
+    ignore: false,
+    pin: None,
+    provider: None,
+    project: None,
+    projects: BTreeMap::new(),
+    channel: None,
+    allow_downgrade: None,
+};
+
+/// 选择版本所需的 Mod 信息与条件
+struct Prepared<'a> {
+    query: SearchQuery<'a>,
+    criteria: Criteria<'a>,
+    rules: &'a ModRules,
+}
+
+/// 并发迁移所有 Mod, 最多同时处理 `config.jobs` 个
+/// `progress` 收到 Mod 的序号, 已下载字节数与总大小 (未知时为 None)
+/// `on_result` 按扫描顺序依次收到每个结果及其序号
+pub fn migrate_mods<P, F>(
+    mods: Vec<ScannedMod>,
+    migration: &Migration,
+    progress: P,
+    mut on_result: F,
+) -> Vec<MigrationResult>
+where
+    P: Fn(usize, u64, Option<u64>) + Clone + Send + Sync + 'static,
+    F: FnMut(usize, &MigrationResult),
+{
+    let mut results = Vec::with_capacity(mods.len());
+    run_parallel(
+        mods,
+        migration.config.jobs,
+        |index, scanned| {
+            let progress = progress.clone();
+            download::with_progress(
+                move |done, total| progress(index, done, total),
+                || migrate_mod(scanned, migration),
+            )
+        },
+        |index, result| {
+            on_result(index, &result);
+            results.push(result);
+        },
+    );
+    results
+}
+
+/// 并发为所有 Mod 选择版本但不下载, `on_plan` 按扫描顺序依次收到每个 Mod 的序号与计划
+pub fn plan_mods<F>(mods: &[ScannedMod], migration: &Migration, on_plan: F)
+where
+    F: FnMut(usize, Plan),
+{
+    run_parallel(
+        mods.iter().collect(),
+        migration.config.jobs,
+        |_, scanned| plan_mod(scanned, migration),
+        on_plan,
+    );
+}
+
+/// 并发执行迁移计划, `progress` 收到计划的序号, 已下载字节数与总大小 (未知时为 None)
+/// `on_result` 按顺序依次收到每个计划的序号与结果
+pub fn apply_plans<P, F>(
+    plans: Vec<(ScannedMod, Plan)>,
+    migration: &Migration,
+    progress: P,
+    on_result: F,
+) where
+    P: Fn(usize, u64, Option<u64>) + Clone + Send + Sync + 'static,
+    F: FnMut(usize, MigrationResult),
+{
+    run_parallel(
+        plans,
+        migration.config.jobs,
+        |index, (scanned, plan)| {
+            let progress = progress.clone();
+            download::with_progress(
+                move |done, total| progress(index, done, total),
+                || apply_plan(scanned, plan, migration),
+            )
+        },
+        on_result,
+    )
+}
+
+/// 用最多 `jobs` 个线程依次处理 `items`, `on_result` 按原顺序依次收到每项的序号与结果
+fn run_parallel<T, R, W, F>(items: Vec<T>, jobs: usize, work: W, mut on_result: F)
+where
+    T: Send,
+    R: Send,
+    W: Fn(usize, T) -> R + Sync,
+    F: FnMut(usize, R),
+{
+    let total = items.len();
+    let jobs = jobs.clamp(1, total.max(1));
+    let queue = Mutex::new(items.into_iter().enumerate().collect::<VecDeque<_>>());
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let queue = &queue;
+            let work = &work;
+            scope.spawn(move || {
+                loop {
+                    let next = queue.lock().ok().and_then(|mut q| q.pop_front());
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+                    let result = work(index, item);
+                    if tx.send((index, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // 按原顺序输出结果
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (index, result) in rx {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&next) {
+                on_result(next, result);
+                next += 1;
+            }
+        }
+    })
+}
+
+/// 迁移单个 Mod: 先应用配置中的规则, 再依次尝试各下载来源, 完成后删除原文件 (按配置跳过的 Mod 保留原文件)
+pub fn migrate_mod(scanned: ScannedMod, migration: &Migration) -> MigrationResult {
+    let time = Instant::now();
+    let plan = plan_mod(&scanned, migration);
+    let mut result = apply_plan(scanned, plan, migration);
+    result.elapsed = time.elapsed();
+    result
+}
+
+/// 应用配置中的规则, 依次尝试各下载来源直到选定版本, 不下载
+pub fn plan_mod(scanned: &ScannedMod, migration: &Migration) -> Plan {
+    let prepared = match prepare(scanned, migration) {
+        Ok(prepared) => prepared,
+        Err(outcome) => return Plan::Done(*outcome),
+    };
+    let mut errors = Vec::new();
+    match next_version(&prepared, &scanned.path, migration, 0, &mut errors) {
+        Some((provider, resolved, candidates)) => Plan::Ready(Box::new(PlannedVersion {
+            provider,
+            resolved,
+            candidates,
+            errors,
+        })),
+        None => Plan::Done(MigrationOutcome::Failed { errors }),
+    }
+}
+
+/// 下载计划中选定的版本, 下载失败时继续尝试之后的来源, 完成后删除原文件 (跳过的 Mod 保留原文件)
+pub fn apply_plan(scanned: ScannedMod, plan: Plan, migration: &Migration) -> MigrationResult {
+    let time = Instant::now();
+    let outcome = match plan {
+        Plan::Done(MigrationOutcome::Ignored) => {
+            return MigrationResult {
+                scanned,
+                outcome: MigrationOutcome::Ignored,
+                elapsed: time.elapsed(),
+            };
+        }
+        Plan::Done(outcome) => outcome,
+        Plan::Ready(planned) => download_planned(&scanned, *planned, migration),
+    };
+    // 原文件已备份, 识别项目时仍需读取, 因此最后删除
+    let _ = fs::remove_file(&scanned.path);
+    MigrationResult {
+        scanned,
+        outcome,
+        elapsed: time.elapsed(),
+    }
+}
+
+/// 读取该 Mod 的规则并确定选择版本的条件, 跳过或无法迁移时返回对应的结果
+fn prepare<'a>(
+    scanned: &'a ScannedMod,
+    migration: &'a Migration,
+) -> Result<Prepared<'a>, Box<MigrationOutcome>> {
+    let config = migration.config;
+    let rules = scanned
+        .mod_id
+        .as_ref()
+        .and_then(|mod_id| config.mods.get(mod_id))
+        .unwrap_or(&NO_RULES);
+    if rules.ignore {
+        log::info!("{}: ignored by the config", scanned.file_name);
+        return Err(Box::new(MigrationOutcome::Ignored));
+    }
+    match (&scanned.mod_id, &scanned.version, &scanned.loader) {
+        (Some(mod_id), Some(version), Some(loader)) => {
+            let Some(loader) = loader_name(loader) else {
+                log::warn!("{}: unknown mod loader {:?}", scanned.file_name, loader);
+                return Err(Box::new(MigrationOutcome::UnknownLoader));
+            };
+            Ok(Prepared {
+                query: SearchQuery {
+                    mod_id,
+                    name: scanned.name.as_deref(),
+                    authors: &scanned.authors,
+                },
+                criteria: Criteria {
+                    mc_version: migration.mc_version,
+                    loader,
+                    channel: config.channel_for(mod_id),
+                    installed: Some(version),
+                    allow_downgrade: config.allow_downgrade_for(mod_id),
+                    pin: rules.pin.as_deref(),
+                    compatible_versions: config.compatible_versions,
+                },
+                rules,
+            })
+        }
+        (Some(_), Some(_), None) => {
+            log::warn!("{}: unknown mod loader", scanned.file_name);
+            Err(Box::new(MigrationOutcome::UnknownLoader))
+        }
+        _ => {
+            log::warn!("{}: no mod ID or version in the jar", scanned.file_name);
+            Err(Box::new(MigrationOutcome::Unrecognized))
+        }
+    }
+}
+
+/// 从序号为 `start` 的来源起依次尝试, 返回第一个选定版本的来源序号, 版本与可选版本
+/// 失败的来源记录到 `errors`; 指定了来源时只尝试该来源, 本地文件读写失败时不再尝试其他来源
+fn next_version(
+    prepared: &Prepared,
+    jar: &Path,
+    migration: &Migration,
+    start: usize,
+    errors: &mut Vec<ProviderError>,
+) -> Option<(usize, Resolved, Vec<RemoteVersion>)> {
+    let rules = prepared.rules;
+    let providers = migration
+        .providers
+        .iter()
+        .enumerate()
+        .skip(start)
+        .filter(|(_, p)| rules.provider.as_deref().is_none_or(|id| p.id() == id));
+    for (index, provider) in providers {
+        match find_version(provider.as_ref(), prepared, jar, migration) {
+            Ok((resolved, candidates)) => {
+                log::info!(
+                    "{}: selected {} {} from {}: {}",
+                    prepared.query.mod_id,
+                    resolved.version.version_number,
+                    resolved.version.version_id,
+                    provider.name(),
+                    resolved.reason
+                );
+                return Some((index, resolved, candidates));
+            }
+            Err(error) => {
+                log::warn!(
+                    "{}: no version from {}: {}",
+                    prepared.query.mod_id,
+                    provider.name(),
+                    error
+                );
+                let fall_back = error.should_fall_back();
+                errors.push(ProviderError {
+                    provider: provider.name().to_string(),
+                    error,
+                });
+                if !fall_back {
+                    return None;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// 下载选定的版本, 失败或下载的文件不是有效的 Mod 时从之后的来源重新选择
+fn download_planned(
+    scanned: &ScannedMod,
+    planned: PlannedVersion,
+    migration: &Migration,
+) -> MigrationOutcome {
+    let PlannedVersion {
+        mut provider,
+        mut resolved,
+        mut errors,
+        ..
+    } = planned;
+    let prepared = match prepare(scanned, migration) {
+        Ok(prepared) => prepared,
+        Err(outcome) => return *outcome,
+    };
+    loop {
+        let source = migration.providers[provider].as_ref();
+        let result = source
+            .download(&resolved, migration.cache_dir)
+            .and_then(|downloaded| {
+                if let Err(e) = detect_mod(&downloaded.path) {
+                    let _ = fs::remove_file(&downloaded.path);
+                    return Err(Error::CorruptJar(format!(
+                        "{}: {}",
+                        downloaded.file_name, e
+                    )));
+                }
+                let locked =
+                    LockedMod::new(prepared.query.mod_id, prepared.criteria.loader, &downloaded)?;
+                Ok((downloaded, locked))
+            });
+        match result {
+            Ok((downloaded, locked)) => {
+                log::info!(
+                    "{}: downloaded {} from {}",
+                    prepared.query.mod_id,
+                    downloaded.file_name,
+                    source.name()
+                );
+                let added_dependencies = added_dependencies(&scanned.path, &downloaded.path);
+                if !added_dependencies.is_empty() {
+                    log::info!(
+                        "{}: new required dependencies: {}",
+                        prepared.query.mod_id,
+                        added_dependencies.join(", ")
+                    );
+                }
+                return MigrationOutcome::Migrated {
+                    provider: source.name().to_string(),
+                    new_version: get_mod_version(&downloaded.path).ok().flatten(),
+                    compatible_with: downloaded.compatible_with.clone(),
+                    link: source.project_url(&downloaded.project_id),
+                    added_dependencies,
+                    locked: Box::new(locked),
+                    errors,
+                };
+            }
+            Err(error) => {
+                log::warn!(
+                    "{}: download from {} failed: {}",
+                    prepared.query.mod_id,
+                    source.name(),
+                    error
+                );
+                let fall_back = error.should_fall_back();
+                errors.push(ProviderError {
+                    provider: source.name().to_string(),
+                    error,
+                });
+                if !fall_back {
+                    return MigrationOutcome::Failed { errors };
+                }
+            }
+        }
+        match next_version(
+            &prepared,
+            &scanned.path,
+            migration,
+            provider + 1,
+            &mut errors,
+        ) {
+            Some((next, next_resolved, _)) => {
+                provider = next;
+                resolved = next_resolved;
+            }
+            None => return MigrationOutcome::Failed { errors },
+        }
+    }
+}
+
+/// 新版本声明而原文件未声明的必需依赖, 无法读取时视为没有
+fn added_dependencies(old_jar: &Path, new_jar: &Path) -> Vec<String> {
+    let old = get_mod_dependencies(old_jar).unwrap_or_default();
+    get_mod_dependencies(new_jar)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| !old.contains(id))
+        .collect()
+}
+
+/// 从单个来源识别项目并选择版本, 同时返回可选的其他版本
+/// 查找顺序: 配置中指定的项目 -> 映射数据中的项目 -> 识别 -> 按名称搜索
+fn find_version(
+    provider: &dyn ModProvider,
+    prepared: &Prepared,
+    jar: &Path,
+    migration: &Migration,
+) -> Result<(Resolved, Vec<RemoteVersion>), Error> {
+    let Prepared {
+        query,
+        criteria,
+        rules,
+    } = prepared;
+    let mod_id = query.mod_id;
+    let configured = rules
+        .project
+        .as_deref()
+        .or_else(|| rules.projects.get(provider.id()).map(String::as_str));
+    let (project, source) = match configured {
+        Some(id) => (
+            provider
+                .find_project(id)?
+                .ok_or_else(|| Error::NotFound(format!("project {}", id)))?,
+            "config",
+        ),
+        None => {
+            // 映射的项目不存在时继续识别
+            let mapped = migration.mappings.project(mod_id, provider.id());
+            let found = match mapped.map(|id| provider.find_project(id)).transpose()? {
+                Some(Some(project)) => Some((project, "mappings")),
+                _ => provider
+                    .identify(mod_id, jar)?
+                    .map(|project| (project, "identification")),
+            };
+            match found {
+                Some(found) => found,
+                None => (
+                    search_project(provider, query, criteria, migration.picker)?,
+                    "name search",
+                ),
+            }
+        }
+    };
+    log::debug!(
+        "{}: {} project {} ({}) found by {}",
+        mod_id,
+        provider.name(),
+        project.title,
+        project.id,
+        source
+    );
+    let versions = provider.list_versions(&project, criteria)?;
+    log::debug!(
+        "{}: {} versions for Minecraft {} ({})",
+        mod_id,
+        versions.len(),
+        criteria.mc_version,
+        criteria.loader
+    );
+    let unpinned = Criteria {
+        pin: None,
+        ..criteria.clone()
+    };
+    let mut candidates = candidate_versions(versions.clone(), &unpinned).unwrap_or_default();
+    candidates.truncate(MAX_VERSION_CHOICES);
+    let resolved = match migration.picker {
+        // 固定版本时不再询问
+        Some(picker) if migration.config.interactive && criteria.pin.is_none() => {
+            choose_version(provider, mod_id, versions, criteria, picker)?
+        }
+        _ => provider.resolve(versions, criteria)?,
+    };
+    Ok((resolved, candidates))
+}
+
+/// 有多个可选版本时让用户选择, 只有一个或用户使用默认选择时按来源的规则选择
+fn choose_version(
+    provider: &dyn ModProvider,
+    mod_id: &str,
+    versions: Vec<RemoteVersion>,
+    criteria: &Criteria,
+    picker: &dyn Picker,
+) -> Result<Resolved, Error> {
+    let mut candidates = candidate_versions(versions.clone(), criteria)?;
+    if candidates.len() > 1 {
+        candidates.truncate(MAX_VERSION_CHOICES);
+        if let Some(index) = picker.pick_version(mod_id, provider, &candidates)
+            && index < candidates.len()
+        {
+            let version = candidates.swap_remove(index);
+            return Ok(Resolved::new(
+                version,
+                "Chosen by the user".to_string(),
+                criteria,
+            ));
+        }
+    }
+    provider.resolve(versions, criteria)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Migration, Plan, ScannedMod, plan_mods, run_parallel};
+    use crate::api::DownloadedMod;
+    use crate::api::provider::{
+        Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved,
+    };
+    use crate::config::{Config, ModRules};
+    use crate::error::Error;
+    use crate::mapping::database::Mappings;
+    use crate::utils::loader::ModLoader;
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    /// 按项目 ID 返回固定版本列表的来源, 记录查找过的项目
+    struct StubProvider {
+        id: &'static str,
+        /// 项目 ID -> 版本号
+        projects: BTreeMap<&'static str, Vec<&'static str>>,
+        /// 列出版本前的等待时间, 以项目 ID 为键
+        delays: BTreeMap<&'static str, Duration>,
+        lookups: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl StubProvider {
+        fn new(id: &'static str, projects: &[(&'static str, &[&'static str])]) -> Self {
+            StubProvider {
+                id,
+                projects: projects
+                    .iter()
+                    .map(|(project, versions)| (*project, versions.to_vec()))
+                    .collect(),
+                delays: BTreeMap::new(),
+                lookups: Arc::default(),
+            }
+        }
+
+        fn project(&self, id: &str) -> Option<RemoteProject> {
+            self.lookups.lock().unwrap().push(id.to_string());
+            self.projects.contains_key(id).then(|| RemoteProject {
+                id: id.to_string(),
+                slug: id.to_string(),
+                title: id.to_string(),
+                authors: Vec::new(),
+                downloads: None,
+            })
+        }
+    }
+
+    impl ModProvider for StubProvider {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn name(&self) -> &'static str {
+            self.id
+        }
+
+        /// Mod ID 即项目 ID
+        fn identify(&self, mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+            Ok(self.project(mod_id))
+        }
+
+        fn find_project(&self, id: &str) -> Result<Option<RemoteProject>, Error> {
+            Ok(self.project(id))
+        }
+
+        fn search(
+            &self,
+            _query: &str,
+            _mc_version: &str,
+            _loader: &str,
+        ) -> Result<Vec<RemoteProject>, Error> {
+            Ok(Vec::new())
+        }
+
+        fn list_versions(
+            &self,
+            project: &RemoteProject,
+            _criteria: &Criteria,
+        ) -> Result<Vec<RemoteVersion>, Error> {
+            if let Some(delay) = self.delays.get(project.id.as_str()) {
+                thread::sleep(*delay);
+            }
+            Ok(self.projects[project.id.as_str()]
+                .iter()
+                .map(|number| RemoteVersion {
+                    project_id: project.id.clone(),
+                    version_id: number.to_string(),
+                    version_number: number.to_string(),
+                    game_versions: vec!["1.20.4".to_string()],
+                    loaders: vec!["fabric".to_string()],
+                    published: String::new(),
+                    channel: Channel::Release,
+                    file_name: format!("{}-{}.jar", project.id, number),
+                    url: None,
+                    hashes: Default::default(),
+                    downloads: None,
+                    changelog: None,
+                })
+                .collect())
+        }
+
+        fn download(
+            &self,
+            _resolved: &Resolved,
+            _output_dir: &Path,
+        ) -> Result<DownloadedMod, Error> {
+            Err(Error::Other("downloads are not stubbed".to_string()))
+        }
+    }
+
+    fn scanned(mod_id: &str) -> ScannedMod {
+        ScannedMod {
+            file_name: format!("{}.jar", mod_id),
+            path: PathBuf::from(format!("{}.jar", mod_id)),
+            mod_id: Some(mod_id.to_string()),
+            version: Some("0.1.0".to_string()),
+            loader: Some(ModLoader::Fabric),
+            name: None,
+            authors: Vec::new(),
+        }
+    }
+
+    /// 为每个 Mod 制定计划, 返回按回调顺序排列的序号与选定的 `来源/项目@版本`
+    fn plan(
+        mods: &[ScannedMod],
+        providers: &[Box<dyn ModProvider>],
+        config: &Config,
+    ) -> Vec<(usize, String)> {
+        let mappings = Mappings::default();
+        let migration = Migration {
+            mc_version: "1.20.4",
+            cache_dir: Path::new("cache"),
+            providers,
+            mappings: &mappings,
+            config,
+            picker: None,
+        };
+        let mut plans = Vec::new();
+        plan_mods(mods, &migration, |index, plan| {
+            let selected = match plan {
+                Plan::Ready(planned) => format!(
+                    "{}/{}@{}",
+                    providers[planned.provider].id(),
+                    planned.resolved.version.project_id,
+                    planned.resolved.version.version_number
+                ),
+                Plan::Done(outcome) => format!("{:?}", outcome),
+            };
+            plans.push((index, selected));
+        });
+        plans
+    }
+
+    #[test]
+    fn runs_in_parallel_and_keeps_the_input_order() {
+        let mut seen = Vec::new();
+        run_parallel(
+            (0..8u64).collect(),
+            4,
+            |index, item| {
+                // 靠前的项目完成得更晚
+                thread::sleep(Duration::from_millis((8 - item) * 5));
+                (index, thread::current().id())
+            },
+            |index, (echoed, worker)| seen.push((index, echoed, worker)),
+        );
+        assert_eq!(
+            seen.iter().map(|(index, _, _)| *index).collect::<Vec<_>>(),
+            (0..8).collect::<Vec<_>>()
+        );
+        assert!(seen.iter().all(|(index, echoed, _)| index == echoed));
+        let workers: Vec<_> = seen.iter().map(|(_, _, worker)| *worker).collect();
+        assert!(workers.iter().any(|worker| *worker != workers[0]));
+    }
+
+    #[test]
+    fn plans_come_back_in_scan_order() {
+        let ids = ["a", "b", "c", "d", "e", "f"];
+        let mut provider = StubProvider::new("stub", &ids.map(|id| (id, &["1.0.0", "1.1.0"][..])));
+        for (i, id) in ids.iter().enumerate() {
+            provider
+                .delays
+                .insert(id, Duration::from_millis((ids.len() - i) as u64 * 10));
+        }
+        let providers: Vec<Box<dyn ModProvider>> = vec![Box::new(provider)];
+        let config = Config {
+            jobs: 3,
+            ..Config::default()
+        };
+        let mods: Vec<ScannedMod> = ids.iter().map(|id| scanned(id)).collect();
+        let plans = plan(&mods, &providers, &config);
+        assert_eq!(
+            plans,
+            ids.iter()
+                .enumerate()
+                .map(|(i, id)| (i, format!("stub/{}@1.1.0", id)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// 只有 `rules` 一条规则的配置
+    fn config_with(mod_id: &str, rules: ModRules) -> Config {
+        let mut config = Config::default();
+        config.mods.insert(mod_id.to_string(), rules);
+        config
+    }
+
+    #[test]
+    fn skips_ignored_mods() {
+        let config = config_with(
+            "sodium",
+            ModRules {
+                ignore: true,
+                ..Default::default()
+            },
+        );
+        let provider = StubProvider::new("stub", &[("sodium", &["1.0.0"])]);
+        let lookups = provider.lookups.clone();
+        let providers: Vec<Box<dyn ModProvider>> = vec![Box::new(provider)];
+        let plans = plan(&[scanned("sodium")], &providers, &config);
+        assert_eq!(plans, [(0, "Ignored".to_string())]);
+        assert!(lookups.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn installs_pinned_versions() {
+        let providers: Vec<Box<dyn ModProvider>> = vec![Box::new(StubProvider::new(
+            "stub",
+            &[("sodium", &["1.0.0", "1.1.0"])],
+        ))];
+        let pinned = |pin: &str| {
+            let rules = ModRules {
+                pin: Some(pin.to_string()),
+                ..Default::default()
+            };
+            plan(
+                &[scanned("sodium")],
+                &providers,
+                &config_with("sodium", rules),
+            )
+        };
+        assert_eq!(pinned("1.0.0"), [(0, "stub/sodium@1.0.0".to_string())]);
+        assert_eq!(pinned("1.0"), [(0, "stub/sodium@1.0.0".to_string())]);
+        let (_, missing) = &pinned("2.0.0")[0];
+        assert!(missing.starts_with("Failed"), "{}", missing);
+    }
+
+    #[test]
+    fn restricts_mods_to_the_configured_provider() {
+        let first = StubProvider::new("first", &[("sodium", &["1.1.0"])]);
+        let first_lookups = first.lookups.clone();
+        let providers: Vec<Box<dyn ModProvider>> = vec![
+            Box::new(first),
+            Box::new(StubProvider::new("second", &[("sodium", &["1.0.0"])])),
+        ];
+        let config = config_with(
+            "sodium",
+            ModRules {
+                provider: Some("second".to_string()),
+                ..Default::default()
+            },
+        );
+        let plans = plan(&[scanned("sodium")], &providers, &config);
+        assert_eq!(plans, [(0, "second/sodium@1.0.0".to_string())]);
+        assert!(first_lookups.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn uses_configured_projects() {
+        let first = StubProvider::new("first", &[("AANobbMI", &["1.0.0"]), ("other", &["1.0.0"])]);
+        let first_lookups = first.lookups.clone();
+        let providers: Vec<Box<dyn ModProvider>> = vec![
+            Box::new(first),
+            Box::new(StubProvider::new("second", &[("394468", &["1.1.0"])])),
+        ];
+
+        // `project` 用于所有来源, 跳过识别
+        let config = config_with(
+            "sodium",
+            ModRules {
+                project: Some("AANobbMI".to_string()),
+                ..Default::default()
+            },
+        );
+        let plans = plan(&[scanned("sodium")], &providers, &config);
+        assert_eq!(plans, [(0, "first/AANobbMI@1.0.0".to_string())]);
+        assert_eq!(*first_lookups.lock().unwrap(), ["AANobbMI"]);
+
+        // `projects.<来源>` 只用于对应的来源, 其他来源照常识别并可继续回退
+        first_lookups.lock().unwrap().clear();
+        let config = config_with(
+            "sodium",
+            ModRules {
+                projects: [("second".to_string(), "394468".to_string())].into(),
+                ..Default::default()
+            },
+        );
+        let plans = plan(&[scanned("sodium")], &providers, &config);
+        assert_eq!(plans, [(0, "second/394468@1.1.0".to_string())]);
+        assert_eq!(*first_lookups.lock().unwrap(), ["sodium"]);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::api::provider::{Criteria, ModProvider, RemoteProject, RemoteVersion};
+use crate::error::Error;
+use std::collections::BTreeSet;
+
+/// 自动接受的最低相似度
+const AUTO_ACCEPT_SCORE: f64 = 0.9;
+/// 自动接受时与第二个候选的最小差距
+const AUTO_ACCEPT_MARGIN: f64 = 0.1;
+/// 作为候选的最低相似度
+const MIN_CANDIDATE_SCORE: f64 = 0.4;
+/// 作者一致时增加的相似度
+const AUTHOR_BONUS: f64 = 0.1;
+/// 最多列出的候选数量
+const MAX_CANDIDATES: usize = 5;
+
+/// 按名称搜索到的候选项目及其相似度 (0 ~ 1)
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub project: RemoteProject,
+    pub score: f64,
+}
+
+/// 需要用户从多个候选中选择时调用, 由界面实现
+/// 界面可将选中的项目保存到配置文件 (`[mods.<id>]` 的 `projects.<来源>`), 之后的迁移不再询问
+/// 只记录该来源上的项目, 不限定来源, 之后该来源失败时仍会依次尝试其他来源
+pub trait Picker: Sync {
+    /// 从候选项目中选择, 返回选中的序号, 都不是时返回 None
+    fn pick_project(
+        &self,
+        query: &SearchQuery,
+        provider: &dyn ModProvider,
+        candidates: &[Candidate],
+    ) -> Option<usize>;
+
+    /// 从可选版本中选择 (第一个为默认选择的版本), 返回选中的序号, 使用默认选择时返回 None
+    fn pick_version(
+        &self,
+        mod_id: &str,
+        provider: &dyn ModProvider,
+        versions: &[RemoteVersion],
+    ) -> Option<usize>;
+}
+
+/// 用于搜索的 Mod 信息
+pub struct SearchQuery<'a> {
+    pub mod_id: &'a str,
+    /// Jar 中声明的显示名称
+    pub name: Option<&'a str>,
+    /// Jar 中声明的作者
+    pub authors: &'a [String],
+}
+
+impl SearchQuery<'_> {
+    /// 显示名称, Jar 中未声明时使用 Mod ID
+    pub fn display_name(&self) -> &str {
+        self.name.unwrap_or(self.mod_id)
+    }
+}
+
+/// 用 Mod 的显示名称与 Mod ID 搜索项目, 按名称相似度与作者评分
+/// 高置信度的唯一匹配自动接受, 否则交由 `picker` 选择; 没有 `picker` 时返回错误并列出候选
+pub fn search_project(
+    provider: &dyn ModProvider,
+    query: &SearchQuery,
+    criteria: &Criteria,
+    picker: Option<&dyn Picker>,
+) -> Result<RemoteProject, Error> {
+    // Mod ID 常以下划线分隔, 而平台 slug 使用连字符
+    let id_query = query.mod_id.replace('_', "-");
+    let mut names: Vec<&str> = query.name.into_iter().collect();
+    names.push(&id_query);
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+    let mut seen = BTreeSet::new();
+    let mut candidates = Vec::new();
+    for name in &names {
+        for project in provider.search(name, criteria.mc_version, criteria.loader)? {
+            if !seen.insert(project.id.clone()) {
+                continue;
+            }
+            let score = score(&project, &names, query.authors);
+            if score >= MIN_CANDIDATE_SCORE {
+                candidates.push(Candidate { project, score });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(MAX_CANDIDATES);
+    log::debug!(
+        "{}: search candidates on {}: {}",
+        query.mod_id,
+        provider.name(),
+        candidates
+            .iter()
+            .map(|c| format!("{} ({:.2})", c.project.slug, c.score))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let Some(best) = candidates.first() else {
+        return Err(Error::NotFound(format!("mod {}", query.mod_id)));
+    };
+    let confident = best.score >= AUTO_ACCEPT_SCORE
+        && candidates
+            .get(1)
+            .is_none_or(|second| best.score - second.score >= AUTO_ACCEPT_MARGIN);
+    if confident {
+        log::info!(
+            "{}: accepted {} by name (score {:.2})",
+            query.mod_id,
+            best.project.slug,
+            best.score
+        );
+        return Ok(candidates.swap_remove(0).project);
+    }
+    match picker {
+        Some(picker) => match picker.pick_project(query, provider, &candidates) {
+            Some(index) if index < candidates.len() => {
+                let chosen = candidates.swap_remove(index).project;
+                log::info!("{}: user chose {}", query.mod_id, chosen.slug);
+                Ok(chosen)
+            }
+            _ => Err(Error::NotFound(format!(
+                "no project was chosen for {}",
+                query.display_name()
+            ))),
+        },
+        None => Err(Error::NotFound(format!(
+            "no confident match for {}; closest: {}",
+            query.display_name(),
+            candidates
+                .iter()
+                .map(|c| format!("{} ({:.0}%)", c.project.title, c.score * 100.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// 候选项目的相似度: 名称与标题或 slug 的最高相似度, 作者一致时额外加分
+fn score(project: &RemoteProject, names: &[&str], authors: &[String]) -> f64 {
+    let name_score = names
+        .iter()
+        .flat_map(|name| {
+            [
+                similarity(name, &project.title),
+                similarity(name, &project.slug),
+            ]
+        })
+        .fold(0.0, f64::max);
+    let same_author = authors.iter().any(|author| {
+        project
+            .authors
+            .iter()
+            .any(|a| normalize(a) == normalize(author))
+    });
+    if same_author {
+        (name_score + AUTHOR_BONUS).min(1.0)
+    } else {
+        name_score
+    }
+}
+
+/// 只保留小写字母与数字, 忽略空格与标点的差异
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 两个名称的 Dice 相似度 (按相邻字符对计算), 规范化后相同时为 1
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let bigrams = |s: &str| {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()
+    };
+    let (a, mut b) = (bigrams(&a), bigrams(&b));
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(i) = b.iter().position(|p| p == pair) {
+            b.swap_remove(i);
+            shared += 1;
+        }
+    }
+    (2 * shared) as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, Picker, SearchQuery, score, search_project, similarity};
+    use crate::api::DownloadedMod;
+    use crate::api::provider::{
+        Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved,
+    };
+    use crate::error::Error;
+    use std::path::Path;
+
+    fn project(slug: &str, title: &str, authors: &[&str]) -> RemoteProject {
+        RemoteProject {
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            title: title.to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            downloads: None,
+        }
+    }
+
+    /// 任何查询都返回同一组项目的来源
+    struct SearchResults(Vec<RemoteProject>);
+
+    impl ModProvider for SearchResults {
+        fn id(&self) -> &'static str {
+            "stub"
+        }
+
+        fn name(&self) -> &'static str {
+            "Stub"
+        }
+
+        fn identify(&self, _mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+            Ok(None)
+        }
+
+        fn find_project(&self, _id: &str) -> Result<Option<RemoteProject>, Error> {
+            Ok(None)
+        }
+
+        fn search(
+            &self,
+            _query: &str,
+            _mc_version: &str,
+            _loader: &str,
+        ) -> Result<Vec<RemoteProject>, Error> {
+            Ok(self.0.clone())
+        }
+
+        fn list_versions(
+            &self,
+            _project: &RemoteProject,
+            _criteria: &Criteria,
+        ) -> Result<Vec<RemoteVersion>, Error> {
+            Ok(Vec::new())
+        }
+
+        fn download(
+            &self,
+            _resolved: &Resolved,
+            _output_dir: &Path,
+        ) -> Result<DownloadedMod, Error> {
+            Err(Error::Other("downloads are not stubbed".to_string()))
+        }
+    }
+
+    /// 总是选择固定序号的界面
+    struct FixedPicker(Option<usize>);
+
+    impl Picker for FixedPicker {
+        fn pick_project(
+            &self,
+            _query: &SearchQuery,
+            _provider: &dyn ModProvider,
+            _candidates: &[Candidate],
+        ) -> Option<usize> {
+            self.0
+        }
+
+        fn pick_version(
+            &self,
+            _mod_id: &str,
+            _provider: &dyn ModProvider,
+            _versions: &[RemoteVersion],
+        ) -> Option<usize> {
+            None
+        }
+    }
+
+    fn search(
+        projects: Vec<RemoteProject>,
+        name: &str,
+        picker: Option<&dyn Picker>,
+    ) -> Result<RemoteProject, Error> {
+        let criteria = Criteria {
+            mc_version: "1.20.4",
+            loader: "fabric",
+            channel: Channel::Release,
+            installed: None,
+            allow_downgrade: false,
+            pin: None,
+            compatible_versions: false,
+        };
+        let query = SearchQuery {
+            mod_id: "mod_id",
+            name: Some(name),
+            authors: &[],
+        };
+        search_project(&SearchResults(projects), &query, &criteria, picker)
+    }
+
+    #[test]
+    fn scores_dice_similarity() {
+        assert_eq!(similarity("Sodium", "sodium"), 1.0);
+        assert_eq!(similarity("Just Enough Items", "just-enough-items"), 1.0);
+        // ni ig gh ht 与 na ac ch ht 共有 1 对
+        assert_eq!(similarity("night", "nacht"), 0.25);
+        // 重复的字符对只匹配一次
+        assert_eq!(similarity("aaaa", "aa"), 0.5);
+        assert_eq!(similarity("a", "b"), 0.0);
+        assert_eq!(similarity("", "sodium"), 0.0);
+        assert_eq!(similarity("!!", "??"), 0.0);
+    }
+
+    #[test]
+    fn adds_an_author_bonus() {
+        let candidate = project("night", "Night", &["Jelly Fish"]);
+        assert_eq!(score(&candidate, &["nacht"], &[]), 0.25);
+        let authors = ["jellyfish".to_string()];
+        assert_eq!(score(&candidate, &["nacht"], &authors), 0.35);
+        // 加分后不超过 1
+        assert_eq!(score(&candidate, &["Night"], &authors), 1.0);
+    }
+
+    #[test]
+    fn accepts_confident_matches() {
+        let projects = vec![
+            project("sodium-extra", "Sodium Extra", &[]),
+            project("sodium", "Sodium", &[]),
+        ];
+        assert_eq!(search(projects, "Sodium", None).unwrap().slug, "sodium");
+    }
+
+    #[test]
+    fn asks_when_matches_are_close() {
+        let projects = vec![
+            project("iris", "Iris Shaders", &[]),
+            project("iris-fork", "Iris Shaders", &[]),
+        ];
+        let Err(Error::NotFound(message)) = search(projects.clone(), "Iris Shader", None) else {
+            panic!("expected an ambiguous match");
+        };
+        assert!(message.contains("closest: Iris Shaders"), "{}", message);
+        let chosen = search(projects.clone(), "Iris Shader", Some(&FixedPicker(Some(1))));
+        assert_eq!(chosen.unwrap().slug, "iris-fork");
+        assert!(matches!(
+            search(projects, "Iris Shader", Some(&FixedPicker(None))),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn drops_dissimilar_results() {
+        let projects = vec![project("create", "Create", &[])];
+        assert!(matches!(
+            search(projects, "Sodium", Some(&FixedPicker(Some(0)))),
+            Err(Error::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// 识别, 选择与下载 Mod 时的错误
+/// 迁移引擎据此决定是否换用其他来源, 界面据此显示本地化的提示
+#[derive(Debug)]
+pub enum Error {
+    /// 项目, 仓库或文件不存在
+    NotFound(String),
+    /// 项目存在, 但没有符合条件的版本
+    NoCompatibleVersion(String),
+    /// 连接失败, 超时, 服务端错误或响应无法解析
+    Network(String),
+    /// 重试后仍被平台限速, 附带服务端要求的等待时间
+    RateLimited(Option<Duration>),
+    /// 本地文件读写失败
+    Io(io::Error),
+    /// Jar 文件损坏或无法读取
+    CorruptJar(String),
+    /// 下载的文件与平台提供的哈希不一致, 重新下载后仍然如此
+    HashMismatch(String),
+    /// 作者禁止第三方分发该文件, 附带文件名
+    DistributionDisabled(String),
+    /// 其他错误, 如配置或用户未作选择
+    Other(String),
+}
+
+impl Error {
+    /// 是否值得换用其他来源; 本地文件读写失败时其他来源同样会失败
+    pub fn should_fall_back(&self) -> bool {
+        !matches!(self, Error::Io(_))
+    }
+
+    /// 错误类型的名称, 用于迁移报告等机器可读的输出
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::NoCompatibleVersion(_) => "no_compatible_version",
+            Error::Network(_) => "network",
+            Error::RateLimited(_) => "rate_limited",
+            Error::Io(_) => "io",
+            Error::CorruptJar(_) => "corrupt_jar",
+            Error::HashMismatch(_) => "hash_mismatch",
+            Error::DistributionDisabled(_) => "distribution_disabled",
+            Error::Other(_) => "other",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(what) => write!(f, "Not found: {}", what),
+            Error::NoCompatibleVersion(detail) => write!(f, "No compatible version: {}", detail),
+            Error::Network(detail) => write!(f, "Network error: {}", detail),
+            Error::RateLimited(Some(wait)) => {
+                write!(f, "Rate limited, retry after {}s", wait.as_secs().max(1))
+            }
+            Error::RateLimited(None) => write!(f, "Rate limited"),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::CorruptJar(detail) => write!(f, "Corrupt jar: {}", detail),
+            Error::HashMismatch(detail) => write!(f, "Hash mismatch: {}", detail),
+            Error::DistributionDisabled(file) => write!(
+                f,
+                "The author has disabled third-party distribution of {}",
+                file
+            ),
+            Error::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::NotFound(s) => Error::NotFound(s.clone()),
+            Error::NoCompatibleVersion(s) => Error::NoCompatibleVersion(s.clone()),
+            Error::Network(s) => Error::Network(s.clone()),
+            Error::RateLimited(wait) => Error::RateLimited(*wait),
+            Error::Io(e) => Error::Io(io::Error::new(e.kind(), e.to_string())),
+            Error::CorruptJar(s) => Error::CorruptJar(s.clone()),
+            Error::HashMismatch(s) => Error::HashMismatch(s.clone()),
+            Error::DistributionDisabled(s) => Error::DistributionDisabled(s.clone()),
+            Error::Other(s) => Error::Other(s.clone()),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Network(e.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Other(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Other(message.to_string())
+    }
+}
+
+/// 配置等尚未细分的错误
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        Error::Other(e.to_string())
+    }
+}
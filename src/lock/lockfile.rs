@@ -0,0 +1,380 @@
+use crate::api::DownloadedMod;
+use crate::api::download::{ExpectedHashes, download_file};
+use crate::api::http::build_client;
+use crate::cache::store::Cache;
+use crate::config::{Config, NetworkConfig};
+use crate::error;
+use crate::utils::hash::hash_file;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use toml::{Table, Value};
+
+/// 锁文件名, 位于 Mods 目录中
+pub const LOCKFILE_NAME: &str = "miramigrator.lock";
+/// 锁文件格式版本
+const LOCKFILE_FORMAT: i64 = 1;
+
+/// 锁文件中记录的单个模组
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedMod {
+    pub mod_id: String,
+    pub loader: String,
+    pub provider: String,
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    pub file_name: String,
+    pub url: String,
+    pub sha1: String,
+    pub sha512: String,
+    pub reason: String,
+}
+
+impl LockedMod {
+    /// 根据下载结果创建锁定记录, 哈希值由本地文件计算
+    pub fn new(mod_id: &str, loader: &str, downloaded: &DownloadedMod) -> io::Result<Self> {
+        let hashes = hash_file(&downloaded.path)?;
+        Ok(LockedMod {
+            mod_id: mod_id.to_string(),
+            loader: loader.to_string(),
+            provider: downloaded.provider.clone(),
+            project_id: downloaded.project_id.clone(),
+            version_id: downloaded.version_id.clone(),
+            version_number: downloaded.version_number.clone(),
+            file_name: downloaded.file_name.clone(),
+            url: downloaded.url.clone(),
+            sha1: hashes.sha1,
+            sha512: hashes.sha512,
+            reason: downloaded.reason.clone(),
+        })
+    }
+
+    fn to_toml(&self) -> Value {
+        let mut table = Table::new();
+        for (key, value) in [
+            ("mod_id", &self.mod_id),
+            ("loader", &self.loader),
+            ("provider", &self.provider),
+            ("project_id", &self.project_id),
+            ("version_id", &self.version_id),
+            ("version_number", &self.version_number),
+            ("file_name", &self.file_name),
+            ("url", &self.url),
+            ("sha1", &self.sha1),
+            ("sha512", &self.sha512),
+            ("reason", &self.reason),
+        ] {
+            table.insert(key.to_string(), Value::String(value.clone()));
+        }
+        Value::Table(table)
+    }
+
+    fn from_toml(value: &Value) -> Result<Self, Box<dyn Error>> {
+        let field = |key: &str| -> Result<String, Box<dyn Error>> {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| format!("Lockfile entry is missing `{}`.", key).into())
+        };
+        Ok(LockedMod {
+            mod_id: field("mod_id")?,
+            loader: field("loader")?,
+            provider: field("provider")?,
+            project_id: field("project_id")?,
+            version_id: field("version_id")?,
+            version_number: field("version_number").unwrap_or_default(),
+            file_name: field("file_name")?,
+            url: field("url")?,
+            sha1: field("sha1")?,
+            sha512: field("sha512")?,
+            reason: field("reason").unwrap_or_default(),
+        })
+    }
+}
+
+/// 一次迁移安装结果的锁文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    pub minecraft_version: String,
+    pub mods: Vec<LockedMod>,
+}
+
+impl Lockfile {
+    pub fn new(minecraft_version: &str) -> Self {
+        Lockfile {
+            minecraft_version: minecraft_version.trim().to_string(),
+            mods: Vec::new(),
+        }
+    }
+
+    /// 读取锁文件
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        let root = contents.parse::<Value>()?;
+        let format = root.get("format").and_then(|v| v.as_integer()).unwrap_or(0);
+        if format != LOCKFILE_FORMAT {
+            return Err(format!("Unsupported lockfile format: {}", format).into());
+        }
+        let minecraft_version = root
+            .get("minecraft_version")
+            .and_then(|v| v.as_str())
+            .ok_or("Lockfile is missing `minecraft_version`.")?;
+        let mods = root
+            .get("mods")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .map(LockedMod::from_toml)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Lockfile {
+            minecraft_version: minecraft_version.to_string(),
+            mods,
+        })
+    }
+
+    /// 写入锁文件
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut root = Table::new();
+        root.insert("format".to_string(), Value::Integer(LOCKFILE_FORMAT));
+        root.insert(
+            "generator".to_string(),
+            Value::String(format!("MiraMigrator {}", crate::VERSION)),
+        );
+        root.insert(
+            "minecraft_version".to_string(),
+            Value::String(self.minecraft_version.clone()),
+        );
+        let mut mods = self.mods.clone();
+        mods.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+        root.insert(
+            "mods".to_string(),
+            Value::Array(mods.iter().map(LockedMod::to_toml).collect()),
+        );
+        let contents = format!(
+            "# This file is generated by MiraMigrator. Do not edit it by hand.\n{}",
+            toml::to_string(&Value::Table(root))?
+        );
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// 锁定模组的安装结果
+#[derive(Debug, PartialEq)]
+pub enum InstallStatus {
+    /// 目录中已存在哈希一致的文件
+    Unchanged,
+    /// 已重新下载
+    Downloaded,
+}
+
+/// 按锁文件还原 Mods 目录
+pub struct LockInstaller {
+    client: Client,
+    network: NetworkConfig,
+    cache: Cache,
+}
+
+impl LockInstaller {
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        Ok(LockInstaller {
+            client: build_client(&config.network, HeaderMap::new())?,
+            network: config.network.clone(),
+            cache: Cache::new(&config.cache),
+        })
+    }
+
+    /// 安装单个锁定模组, 文件先下载到缓存目录, 哈希校验通过后再移动到 Mods 目录
+    pub fn install(
+        &self,
+        locked: &LockedMod,
+        mods_dir: &Path,
+        cache_dir: &Path,
+    ) -> Result<InstallStatus, error::Error> {
+        if !is_plain_file_name(&locked.file_name) {
+            return Err(error::Error::Other(format!(
+                "Refusing to install `{}`: the lockfile file name must not contain a path.",
+                locked.file_name
+            )));
+        }
+        let target = mods_dir.join(&locked.file_name);
+        if target.is_file() && hash_file(&target)?.sha512 == locked.sha512 {
+            return Ok(InstallStatus::Unchanged);
+        }
+
+        let cache_path = cache_dir.join(&locked.file_name);
+        let expected = ExpectedHashes {
+            sha1: Some(locked.sha1.clone()),
+            sha256: None,
+            sha512: Some(locked.sha512.clone()),
+        };
+        download_file(
+            &self.client,
+            &locked.url,
+            &cache_path,
+            &expected,
+            &self.network,
+            &self.cache,
+        )?;
+        fs::rename(&cache_path, &target)?;
+        Ok(InstallStatus::Downloaded)
+    }
+}
+
+/// 文件名只能是单个普通路径组件, 不能包含分隔符, `..` 或根路径
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        )
+}
+
+/// 删除 Mods 目录中不在锁文件内的 Jar 文件, 返回被删除的文件路径
+/// 仅应在所有锁定模组都安装成功后调用, 否则旧版本被删除后对应的 Mod 将没有可用文件
+pub fn remove_unlocked_jars(lockfile: &Lockfile, mods_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(mods_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().unwrap_or_default() != "jar" {
+            continue;
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !lockfile.mods.iter().any(|m| m.file_name == file_name) {
+            fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InstallStatus, LockInstaller, LockedMod, Lockfile};
+    use crate::config::Config;
+    use crate::utils::hash::hash_file;
+    use crate::utils::testing::TestDir;
+    use std::fs;
+    use std::path::Path;
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    /// 指向 `url`, 哈希与 `contents` 一致的锁定记录
+    fn locked(dir: &Path, file_name: &str, url: &str, contents: &[u8]) -> LockedMod {
+        let path = dir.join("expected.jar");
+        fs::write(&path, contents).unwrap();
+        let hashes = hash_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        LockedMod {
+            mod_id: "sodium".to_string(),
+            loader: "fabric".to_string(),
+            provider: "modrinth".to_string(),
+            project_id: "AANobbMI".to_string(),
+            version_id: "4GyXKCLd".to_string(),
+            version_number: "0.5.8".to_string(),
+            file_name: file_name.to_string(),
+            url: url.to_string(),
+            sha1: hashes.sha1,
+            sha512: hashes.sha512,
+            reason: "Newest release".to_string(),
+        }
+    }
+
+    fn installer() -> LockInstaller {
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        config.network.retries = 0;
+        LockInstaller::new(&config).unwrap()
+    }
+
+    #[test]
+    fn saves_and_loads_lockfiles() {
+        let dir = TestDir::new("lockfile-round-trip");
+        let mut lockfile = Lockfile::new(" 1.20.4 ");
+        let mut lithium = locked(dir.path(), "lithium.jar", "https://example.com/l", b"l");
+        lithium.mod_id = "lithium".to_string();
+        lithium.reason = String::new();
+        let sodium = locked(dir.path(), "sodium.jar", "https://example.com/s", b"s");
+        lockfile.mods = vec![sodium, lithium];
+
+        let path = dir.path().join("miramigrator.lock");
+        lockfile.save(&path).unwrap();
+        let loaded = Lockfile::load(&path).unwrap();
+        assert_eq!(loaded.minecraft_version, "1.20.4");
+        // 保存时按 Mod ID 排序
+        lockfile.mods.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+        assert_eq!(loaded, lockfile);
+
+        fs::write(&path, "format = 2\nminecraft_version = \"1.20.4\"\n").unwrap();
+        assert!(Lockfile::load(&path).is_err());
+    }
+
+    #[test]
+    fn keeps_files_that_match_the_lock() {
+        let dir = TestDir::new("lockfile-unchanged");
+        let mods_dir = dir.subdir("mods");
+        // 地址不可访问, 哈希一致时不应发起下载
+        let locked = locked(dir.path(), "sodium.jar", "http://127.0.0.1:9/x", b"jar");
+        fs::write(mods_dir.join("sodium.jar"), b"jar").unwrap();
+        let status = installer()
+            .install(&locked, &mods_dir, &dir.subdir("cache"))
+            .unwrap();
+        assert_eq!(status, InstallStatus::Unchanged);
+    }
+
+    #[test]
+    fn downloads_changed_files() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/sodium.jar",
+            server.server_addr().to_ip().unwrap()
+        );
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let _ = request.respond(Response::from_data(b"new jar".to_vec()));
+            }
+        });
+
+        let dir = TestDir::new("lockfile-downloaded");
+        let mods_dir = dir.subdir("mods");
+        let cache_dir = dir.subdir("cache");
+        let locked = locked(dir.path(), "sodium.jar", &url, b"new jar");
+        fs::write(mods_dir.join("sodium.jar"), b"old jar").unwrap();
+        let status = installer().install(&locked, &mods_dir, &cache_dir).unwrap();
+        assert_eq!(status, InstallStatus::Downloaded);
+        assert_eq!(fs::read(mods_dir.join("sodium.jar")).unwrap(), b"new jar");
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn rejects_file_names_with_paths() {
+        let dir = TestDir::new("lockfile-paths");
+        let mods_dir = dir.subdir("mods");
+        let installer = installer();
+        for file_name in [
+            "../escape.jar",
+            "/tmp/escape.jar",
+            "sub/escape.jar",
+            "sub\\escape.jar",
+            "..",
+            "",
+        ] {
+            let locked = locked(dir.path(), file_name, "http://127.0.0.1:9/x", b"jar");
+            assert!(
+                installer
+                    .install(&locked, &mods_dir, &dir.subdir("cache"))
+                    .is_err(),
+                "{}",
+                file_name
+            );
+        }
+        assert!(!dir.path().join("escape.jar").exists());
+    }
+}
@@ -14,28 +14,114 @@ const CURSEFORGE_API_KEY: &str = "$2a$10$DnmspuOGwV3zj9600HbYXO9e62i9FYCpXdUez6f
 
 // mod
 mod api;
+mod cache;
+mod cli;
+mod config;
+mod core;
+mod error;
+mod lock;
+mod mapping;
+mod report;
 mod scan;
+mod server;
 mod ui;
 mod utils;
 
 // use
+use cli::args::{Command, parse_args, requested_output};
+use config::{CONFIG_FILE_NAME, Config};
+use std::path::Path;
 use ui::en_us::EnUsInterface;
+use ui::json_lines::JsonLinesInterface;
 use ui::zh_cn::ZhCnInterface;
+use utils::output::{OutputFormat, Verbosity};
 
 fn main() {
     // Choose language
-    let lang = "zh_cn"; 
+    let lang = "zh_cn";
 
     let ui: Box<dyn ui::Interface> = match lang {
         "zh_cn" => Box::new(ZhCnInterface::new()),
         "en_us" => Box::new(EnUsInterface::new()),
-        _ => panic!("Unsupported language"),
+        // 未知语言时使用英文界面
+        _ => Box::new(EnUsInterface::new()),
     };
 
-    // Init
-    ui.init();
-    // Core
-    ui.start();
-    // Exit
-    ui.exit();
+    // 供其他程序调用时输出 JSON 事件, 参数错误也以 JSON 事件输出
+    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let ui: Box<dyn ui::Interface> = match requested_output(&raw_args) {
+        OutputFormat::JsonLines => Box::new(JsonLinesInterface::new()),
+        OutputFormat::Text => ui,
+    };
+
+    let args = match parse_args(raw_args) {
+        Ok(args) => args,
+        Err(arg) => {
+            ui.help(Some(&arg));
+            return;
+        }
+    };
+
+    // Load config
+    let mut config = match Config::load(Path::new(CONFIG_FILE_NAME)) {
+        Ok(config) => config,
+        Err(e) => {
+            ui.config_error(&e.to_string());
+            return;
+        }
+    };
+    if let Some(jobs) = args.jobs {
+        config.jobs = jobs;
+    }
+    if args.interactive {
+        config.interactive = true;
+    }
+    if let Some(verbosity) = args.verbosity {
+        config.verbosity = verbosity;
+    }
+    // 全屏界面中不能向终端输出详细信息
+    if args.command == Command::Tui {
+        config.verbosity = config.verbosity.min(Verbosity::Normal);
+    }
+    utils::output::set_verbosity(config.verbosity);
+    if args.command != Command::Help
+        && let Err(e) = utils::logging::init(&config.log)
+    {
+        ui.log_error(&config.log.file, &e.to_string());
+    }
+    log::info!("MiraMigrator v{} {:?}", VERSION, args);
+
+    match args.command {
+        Command::Start => {
+            // Init
+            if !ui.init() {
+                return;
+            }
+            // Core
+            ui.start(&config);
+            // Exit
+            ui.exit();
+        }
+        Command::Tui => {
+            if !ui.init() {
+                return;
+            }
+            ui.tui(&config);
+        }
+        Command::Install { locked } => {
+            if !ui.init() {
+                return;
+            }
+            ui.install(&config, locked);
+        }
+        Command::Cache(action) => ui.cache(&config, action),
+        Command::Mappings(action) => ui.mappings(&config, action),
+        Command::Serve => {
+            if !ui.init() {
+                return;
+            }
+            ui.serve(&config);
+        }
+        Command::Help => ui.help(None),
+    }
 }
@@ -0,0 +1,200 @@
+use crate::api::http::{build_client, send};
+use crate::config::Config;
+use reqwest::header::HeaderMap;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// 随程序发布的映射数据
+const BUNDLED: &str = include_str!("mappings.toml");
+/// 映射文件格式版本
+const MAPPINGS_FORMAT: i64 = 1;
+
+/// Mod ID 到各来源项目的映射
+/// 依次读取内置数据, `mappings update` 下载的数据与本地覆盖文件, 后读取的条目覆盖之前的条目
+#[derive(Debug, Default)]
+pub struct Mappings {
+    /// Mod ID -> 来源 ID -> 项目 ID 或 slug
+    entries: BTreeMap<String, BTreeMap<String, String>>,
+    /// 已读取的映射文件 (内置数据除外)
+    pub sources: Vec<PathBuf>,
+}
+
+impl Mappings {
+    /// 读取所有映射数据, 不存在的文件将被跳过
+    pub fn load(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let mut mappings = Mappings::default();
+        mappings
+            .merge(BUNDLED)
+            .map_err(|e| format!("bundled mappings: {}", e))?;
+        let files = [
+            config.mappings.file.as_deref(),
+            Some(&config.mappings.overrides),
+        ];
+        for path in files.into_iter().flatten() {
+            if !path.is_file() {
+                continue;
+            }
+            let contents = fs::read_to_string(path)?;
+            mappings
+                .merge(&contents)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            mappings.sources.push(path.to_path_buf());
+        }
+        Ok(mappings)
+    }
+
+    /// 该 Mod 在指定来源上的项目 ID 或 slug
+    pub fn project(&self, mod_id: &str, provider: &str) -> Option<&str> {
+        self.entries
+            .get(mod_id)
+            .and_then(|projects| projects.get(provider))
+            .map(String::as_str)
+    }
+
+    /// 映射的 Mod 数量
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn merge(&mut self, contents: &str) -> Result<(), Box<dyn Error>> {
+        for (mod_id, projects) in parse(contents)? {
+            self.entries.entry(mod_id).or_default().extend(projects);
+        }
+        Ok(())
+    }
+}
+
+/// 解析映射文件, 格式如下, 项目 ID 可以是字符串或整数:
+///
+/// ```toml
+/// format = 1
+///
+/// [mods.jei]
+/// modrinth = "jei"
+/// curseforge = 238222
+/// ```
+fn parse(contents: &str) -> Result<BTreeMap<String, BTreeMap<String, String>>, Box<dyn Error>> {
+    let root = contents.parse::<Value>()?;
+    if let Some(format) = root.get("format").and_then(|f| f.as_integer())
+        && format > MAPPINGS_FORMAT
+    {
+        return Err(format!("unsupported mappings format {}", format).into());
+    }
+    let mut entries = BTreeMap::new();
+    let Some(mods) = root.get("mods") else {
+        return Ok(entries);
+    };
+    let mods = mods.as_table().ok_or("`mods` must be a table")?;
+    for (mod_id, projects) in mods {
+        let projects = projects
+            .as_table()
+            .ok_or_else(|| format!("`mods.{}` must be a table", mod_id))?;
+        let mut ids = BTreeMap::new();
+        for (provider, project) in projects {
+            let project = match project {
+                Value::String(s) => s.clone(),
+                Value::Integer(n) => n.to_string(),
+                _ => {
+                    return Err(format!(
+                        "`mods.{}.{}` must be a string or an integer",
+                        mod_id, provider
+                    )
+                    .into());
+                }
+            };
+            ids.insert(provider.clone(), project);
+        }
+        entries.insert(mod_id.clone(), ids);
+    }
+    Ok(entries)
+}
+
+/// 下载最新的映射数据, 校验后保存到 `mappings.file`, 返回映射的 Mod 数量与保存位置
+pub fn update(config: &Config) -> Result<(usize, &Path), Box<dyn Error>> {
+    let path = config
+        .mappings
+        .file
+        .as_deref()
+        .ok_or("No data directory is available to store the mappings")?;
+    let client = build_client(&config.network, HeaderMap::new())?;
+    let response = send(client.get(&config.mappings.url), &config.network, None)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, config.mappings.url).into());
+    }
+    let contents = response.text()?;
+    let count = parse(&contents)
+        .map_err(|e| format!("{}: {}", config.mappings.url, e))?
+        .len();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok((count, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUNDLED, Mappings};
+    use crate::config::Config;
+    use crate::utils::testing::TestDir;
+    use std::fs;
+
+    #[test]
+    fn later_entries_override_earlier_ones() {
+        let mut mappings = Mappings::default();
+        mappings
+            .merge("format = 1\n[mods.jei]\nmodrinth = \"jei\"\ncurseforge = 238222\n")
+            .unwrap();
+        mappings
+            .merge("[mods.jei]\nmodrinth = \"jei-fork\"\n[mods.emi]\nmodrinth = \"emi\"\n")
+            .unwrap();
+        assert_eq!(mappings.project("jei", "modrinth"), Some("jei-fork"));
+        // 覆盖只替换同一来源的项目
+        assert_eq!(mappings.project("jei", "curseforge"), Some("238222"));
+        assert_eq!(mappings.project("emi", "modrinth"), Some("emi"));
+        assert_eq!(mappings.project("emi", "curseforge"), None);
+        assert_eq!(mappings.count(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_mappings() {
+        let mut mappings = Mappings::default();
+        for contents in [
+            "format = 2\n",
+            "mods = 1\n",
+            "[mods]\njei = \"jei\"\n",
+            "[mods.jei]\nmodrinth = true\n",
+        ] {
+            assert!(mappings.merge(contents).is_err(), "{}", contents);
+        }
+        assert_eq!(mappings.count(), 0);
+    }
+
+    #[test]
+    fn loads_local_overrides() {
+        let dir = TestDir::new("mappings-overrides");
+        let mut config = Config::default();
+        config.mappings.file = Some(dir.path().join("missing.toml"));
+        config.mappings.overrides = dir.path().join("miramigrator-mappings.toml");
+        fs::write(
+            &config.mappings.overrides,
+            "[mods.fabric-api]\nmodrinth = \"P7dR8mSH\"\n",
+        )
+        .unwrap();
+
+        let mut bundled = Mappings::default();
+        bundled.merge(BUNDLED).unwrap();
+        let mappings = Mappings::load(&config).unwrap();
+        assert_eq!(mappings.sources, [config.mappings.overrides.clone()]);
+        assert_eq!(mappings.count(), bundled.count());
+        assert_eq!(mappings.project("fabric-api", "modrinth"), Some("P7dR8mSH"));
+        assert_eq!(
+            mappings.project("fabric-api", "curseforge"),
+            bundled.project("fabric-api", "curseforge")
+        );
+    }
+}
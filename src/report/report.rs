@@ -0,0 +1,458 @@
+use crate::core::migrate::{MigrationOutcome, MigrationResult};
+use crate::error::Error;
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 迁移报告的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// 供其他工具读取
+    Json,
+    /// 便于粘贴到 Issue 或聊天中
+    Markdown,
+    /// 可直接用浏览器打开的单个页面
+    Html,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 3] = [
+        ReportFormat::Json,
+        ReportFormat::Markdown,
+        ReportFormat::Html,
+    ];
+
+    /// 解析配置文件中的名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(ReportFormat::Json),
+            "markdown" | "md" => Some(ReportFormat::Markdown),
+            "html" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+/// 报告文件路径: 在基础文件名后加上格式的扩展名
+pub fn report_path(base: &Path, format: ReportFormat) -> PathBuf {
+    let mut name = base.as_os_str().to_os_string();
+    name.push(".");
+    name.push(format.extension());
+    PathBuf::from(name)
+}
+
+/// 单个 Mod 的迁移状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Migrated,
+    Ignored,
+    Unrecognized,
+    UnknownLoader,
+    Failed,
+}
+
+impl Status {
+    pub fn from_outcome(outcome: &MigrationOutcome) -> Self {
+        match outcome {
+            MigrationOutcome::Migrated { .. } => Status::Migrated,
+            MigrationOutcome::Ignored => Status::Ignored,
+            MigrationOutcome::Unrecognized => Status::Unrecognized,
+            MigrationOutcome::UnknownLoader => Status::UnknownLoader,
+            MigrationOutcome::Failed { .. } => Status::Failed,
+        }
+    }
+
+    /// JSON 中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Migrated => "migrated",
+            Status::Ignored => "ignored",
+            Status::Unrecognized => "unrecognized",
+            Status::UnknownLoader => "unknown_loader",
+            Status::Failed => "failed",
+        }
+    }
+
+    /// Markdown 与 HTML 中显示的名称
+    fn label(&self) -> &'static str {
+        match self {
+            Status::Migrated => "Migrated",
+            Status::Ignored => "Skipped",
+            Status::Unrecognized => "Unrecognized",
+            Status::UnknownLoader => "Unknown loader",
+            Status::Failed => "Failed",
+        }
+    }
+}
+
+/// 某个来源的失败原因
+#[derive(Debug, Clone)]
+pub struct ReportError {
+    pub provider: String,
+    /// 错误类型, 见 [`crate::error::Error::kind`]
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl ReportError {
+    pub fn new(provider: &str, error: &Error) -> Self {
+        ReportError {
+            provider: provider.to_string(),
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "provider": self.provider,
+            "kind": self.kind,
+            "message": self.message,
+        })
+    }
+}
+
+/// 报告中的单个 Mod
+#[derive(Debug, Clone)]
+pub struct ReportEntry {
+    pub file_name: String,
+    pub mod_id: Option<String>,
+    pub status: Status,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    /// 新版本的文件名
+    pub new_file: Option<String>,
+    /// 成功的来源显示名称
+    pub provider: Option<String>,
+    /// 项目页面地址
+    pub link: Option<String>,
+    /// 迁移成功时为选择该版本的原因, 否则为失败原因
+    pub reason: Option<String>,
+    /// 非精确匹配时, 新版本实际标注的 Minecraft 版本
+    pub compatible_with: Option<String>,
+    /// 新版本新增的必需依赖
+    pub added_dependencies: Vec<String>,
+    /// 各来源的失败原因, 按尝试顺序排列
+    pub errors: Vec<ReportError>,
+    pub elapsed: Duration,
+}
+
+impl ReportEntry {
+    pub fn new(result: &MigrationResult) -> Self {
+        let scanned = &result.scanned;
+        let mut entry = ReportEntry {
+            file_name: scanned.file_name.clone(),
+            mod_id: scanned.mod_id.clone(),
+            status: Status::from_outcome(&result.outcome),
+            old_version: scanned.version.clone(),
+            new_version: None,
+            new_file: None,
+            provider: None,
+            link: None,
+            reason: None,
+            compatible_with: None,
+            added_dependencies: Vec::new(),
+            errors: Vec::new(),
+            elapsed: result.elapsed,
+        };
+        let errors = match &result.outcome {
+            MigrationOutcome::Migrated {
+                provider,
+                new_version,
+                compatible_with,
+                link,
+                added_dependencies,
+                locked,
+                errors,
+            } => {
+                entry.new_version = new_version
+                    .clone()
+                    .or_else(|| Some(locked.version_number.clone()));
+                entry.new_file = Some(locked.file_name.clone());
+                entry.provider = Some(provider.clone());
+                entry.link = link.clone();
+                entry.reason = Some(locked.reason.clone());
+                entry.compatible_with = compatible_with.clone();
+                entry.added_dependencies = added_dependencies.clone();
+                errors.as_slice()
+            }
+            MigrationOutcome::Ignored => {
+                entry.reason = Some("Skipped by the config, the original file is kept".to_string());
+                &[]
+            }
+            MigrationOutcome::Unrecognized => {
+                entry.reason = Some("No mod ID or version in the jar".to_string());
+                &[]
+            }
+            MigrationOutcome::UnknownLoader => {
+                entry.reason = Some("Unknown mod loader".to_string());
+                &[]
+            }
+            MigrationOutcome::Failed { errors } => {
+                entry.reason = Some(match errors.last() {
+                    Some(last) => last.error.to_string(),
+                    None => "No provider is available".to_string(),
+                });
+                errors.as_slice()
+            }
+        };
+        entry.errors = errors
+            .iter()
+            .map(|e| ReportError::new(&e.provider, &e.error))
+            .collect();
+        entry
+    }
+
+    /// JSON 报告中的单个 Mod, 也用于 JSON 事件流
+    pub fn to_json(&self) -> Value {
+        json!({
+            "file_name": self.file_name,
+            "mod_id": self.mod_id,
+            "status": self.status.name(),
+            "old_version": self.old_version,
+            "new_version": self.new_version,
+            "new_file": self.new_file,
+            "provider": self.provider,
+            "link": self.link,
+            "reason": self.reason,
+            "compatible_with": self.compatible_with,
+            "added_dependencies": self.added_dependencies,
+            "errors": self.errors.iter().map(ReportError::to_json).collect::<Vec<_>>(),
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+        })
+    }
+
+    /// 显示名称: Mod ID, 无法读取时使用文件名
+    fn display_name(&self) -> &str {
+        self.mod_id.as_deref().unwrap_or(&self.file_name)
+    }
+
+    /// 备注: 选择版本的原因或失败原因, 以及在成功之前失败的来源
+    fn details(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(mc_version) = &self.compatible_with {
+            details.push(format!("Built for Minecraft {}", mc_version));
+        }
+        if self.status == Status::Failed {
+            details.extend(
+                self.errors
+                    .iter()
+                    .map(|e| format!("{}: {}", e.provider, e.message)),
+            );
+        } else {
+            details.extend(self.reason.clone());
+            details.extend(
+                self.errors
+                    .iter()
+                    .map(|e| format!("{} failed: {}", e.provider, e.message)),
+            );
+        }
+        details.join("; ")
+    }
+}
+
+/// 一次迁移的报告
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub minecraft_version: String,
+    /// 生成时间 (本地时间)
+    pub generated_at: String,
+    /// 迁移总耗时
+    pub elapsed: Duration,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    pub fn new(minecraft_version: &str, results: &[MigrationResult], elapsed: Duration) -> Self {
+        Report {
+            minecraft_version: minecraft_version.trim().to_string(),
+            generated_at: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S %:z")
+                .to_string(),
+            elapsed,
+            entries: results.iter().map(ReportEntry::new).collect(),
+        }
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.entries.iter().filter(|e| e.status == status).count()
+    }
+
+    /// 迁移失败或无法迁移的 Mod 数量
+    pub fn failed(&self) -> usize {
+        self.entries.len() - self.count(Status::Migrated) - self.count(Status::Ignored)
+    }
+
+    /// 以指定格式写入报告
+    pub fn save(&self, format: ReportFormat, path: &Path) -> io::Result<()> {
+        fs::write(path, self.render(format))
+    }
+
+    /// 以指定格式生成报告内容
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let mods: Vec<Value> = self.entries.iter().map(ReportEntry::to_json).collect();
+        let root = json!({
+            "generator": format!("MiraMigrator {}", crate::VERSION),
+            "minecraft_version": self.minecraft_version,
+            "generated_at": self.generated_at,
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "summary": {
+                "total": self.entries.len(),
+                "migrated": self.count(Status::Migrated),
+                "failed": self.failed(),
+                "ignored": self.count(Status::Ignored),
+            },
+            "mods": mods,
+        });
+        let mut contents = serde_json::to_string_pretty(&root).unwrap_or_default();
+        contents.push('\n');
+        contents
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(
+            md,
+            "## MiraMigrator report: Minecraft {}\n",
+            self.minecraft_version
+        );
+        let _ = writeln!(
+            md,
+            "{} migrated, {} failed, {} skipped in {} ({})\n",
+            self.count(Status::Migrated),
+            self.failed(),
+            self.count(Status::Ignored),
+            format_elapsed(self.elapsed),
+            self.generated_at
+        );
+        md.push_str("| Mod | Status | Old version | New version | Provider | Added dependencies | Time | Details |\n");
+        md.push_str("| --- | --- | --- | --- | --- | --- | --- | --- |\n");
+        for e in &self.entries {
+            let provider = match (&e.provider, &e.link) {
+                (Some(provider), Some(link)) => format!("[{}]({})", provider, link),
+                (Some(provider), None) => provider.clone(),
+                _ => String::new(),
+            };
+            let cells = [
+                format!("`{}`", e.display_name()),
+                e.status.label().to_string(),
+                e.old_version.clone().unwrap_or_default(),
+                e.new_version.clone().unwrap_or_default(),
+                provider,
+                e.added_dependencies.join(", "),
+                format_elapsed(e.elapsed),
+                e.details(),
+            ];
+            let cells: Vec<String> = cells.iter().map(|cell| markdown_cell(cell)).collect();
+            let _ = writeln!(md, "| {} |", cells.join(" | "));
+        }
+        md
+    }
+
+    fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for e in &self.entries {
+            let provider = match (&e.provider, &e.link) {
+                (Some(provider), Some(link)) => format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(link),
+                    escape_html(provider)
+                ),
+                (Some(provider), None) => escape_html(provider),
+                _ => String::new(),
+            };
+            let _ = writeln!(
+                rows,
+                "<tr class=\"{}\"><td><code>{}</code><div class=\"file\">{}</div></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                e.status.name(),
+                escape_html(e.display_name()),
+                escape_html(&e.file_name),
+                e.status.label(),
+                escape_html(e.old_version.as_deref().unwrap_or_default()),
+                escape_html(e.new_version.as_deref().unwrap_or_default()),
+                provider,
+                escape_html(&e.added_dependencies.join(", ")),
+                format_elapsed(e.elapsed),
+                escape_html(&e.details())
+            );
+        }
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>MiraMigrator report: Minecraft {version}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 14px; }}
+th, td {{ border: 1px solid #ddd; padding: 6px 8px; text-align: left; vertical-align: top; }}
+th {{ background: #f4f4f4; }}
+.file {{ color: #888; font-size: 12px; }}
+tr.migrated td:nth-child(2) {{ color: #1a7f37; }}
+tr.ignored td:nth-child(2) {{ color: #888; }}
+tr.failed td:nth-child(2), tr.unrecognized td:nth-child(2), tr.unknown_loader td:nth-child(2) {{ color: #cf222e; font-weight: bold; }}
+</style>
+</head>
+<body>
+<h1>MiraMigrator report: Minecraft {version}</h1>
+<p>{migrated} migrated, {failed} failed, {ignored} skipped in {elapsed}. Generated by MiraMigrator {generator} at {generated_at}.</p>
+<table>
+<tr><th>Mod</th><th>Status</th><th>Old version</th><th>New version</th><th>Provider</th><th>Added dependencies</th><th>Time</th><th>Details</th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+            version = escape_html(&self.minecraft_version),
+            migrated = self.count(Status::Migrated),
+            failed = self.failed(),
+            ignored = self.count(Status::Ignored),
+            elapsed = format_elapsed(self.elapsed),
+            generator = crate::VERSION,
+            generated_at = escape_html(&self.generated_at),
+            rows = rows
+        )
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.2}s", elapsed.as_secs_f64())
+}
+
+/// 转义表格单元格中的竖线与换行
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -5,26 +5,23 @@ use std::path::Path;
 
 /// 判断模组备份/缓存文件夹是否存在
 /// 不存在则创建
-pub fn create_backup_folder() {
-    if !Path::new("backup").exists() {
-        fs::create_dir("backup").unwrap();
-    }
-    if !Path::new("cache").exists() {
-        fs::create_dir("cache").unwrap();
-    }
+pub fn create_backup_folder() -> Result<()> {
+    fs::create_dir_all("backup")?;
+    fs::create_dir_all("cache")?;
+    Ok(())
 }
 
 /// 获取当前目录中所有Jar文件对象
 /// 返回Jar文件对象Vec
-pub fn get_jar_files() -> Vec<fs::DirEntry> {
+pub fn get_jar_files() -> Result<Vec<fs::DirEntry>> {
     let mut jar_files = Vec::new();
-    for entry in fs::read_dir(".").unwrap() {
-        let entry = entry.unwrap();
+    for entry in fs::read_dir(".")? {
+        let entry = entry?;
         if entry.path().extension().unwrap_or_default() == "jar" {
             jar_files.push(entry);
         }
     }
-    jar_files
+    Ok(jar_files)
 }
 
 /// 从获取到的Vec中筛选出有效的Mod文件
@@ -39,6 +36,19 @@ pub fn filter_valid_mods(jar_files: Vec<fs::DirEntry>) -> Vec<fs::DirEntry> {
     valid_mods
 }
 
+/// 将Mod文件复制到 backup 文件夹
+pub fn backup_file(path: &Path) -> Result<()> {
+    let file_name = path.file_name().unwrap_or_default();
+    fs::copy(path, Path::new("backup").join(file_name))?;
+    Ok(())
+}
+
+/// 将 backup 文件夹中的Mod文件复制回当前目录
+pub fn restore_file(file_name: &str) -> Result<()> {
+    fs::copy(Path::new("backup").join(file_name), file_name)?;
+    Ok(())
+}
+
 /// 将有效的Mod文件移动到当前目录
 pub fn move_files_from_cache_to_current_dir(cache_dir: &Path) -> Result<()> {
     // 获取当前目录路径
@@ -50,8 +60,9 @@ pub fn move_files_from_cache_to_current_dir(cache_dir: &Path) -> Result<()> {
         let entry_path = entry.path();
 
         // 确保是文件而非目录
-        if entry_path.is_file() {
-            let file_name = entry_path.file_name().unwrap();
+        if entry_path.is_file()
+            && let Some(file_name) = entry_path.file_name()
+        {
             let destination = current_dir.join(file_name);
 
             // 移动文件到当前目录
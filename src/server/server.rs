@@ -0,0 +1,995 @@
+use crate::api::provider::ModProvider;
+use crate::config::Config;
+use crate::core::migrate::{
+    Migration, MigrationOutcome, MigrationResult, Plan, ScannedMod, apply_plans, plan_mods,
+};
+use crate::lock::lockfile::{LOCKFILE_NAME, Lockfile};
+use crate::mapping::database::Mappings;
+use crate::report::report::{Report, ReportEntry, ReportError, ReportFormat, Status, report_path};
+use crate::scan::scan;
+use crate::utils::loader::loader_name;
+use crate::utils::version::validate_version;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{self, Read};
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, Scope};
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// 请求体的大小上限
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+/// 下载目录, 迁移完成后移回 Mods 目录
+const CACHE_DIR: &str = "cache";
+
+/// 各 Mod 已下载的字节数与总大小
+type DownloadProgress = Arc<Mutex<Vec<Option<(u64, Option<u64>)>>>>;
+
+/// 当前任务的阶段, 同一时间只运行一个任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Idle,
+    Planning,
+    /// 已选定版本, 等待迁移
+    Planned,
+    Migrating,
+    Finished,
+}
+
+impl Phase {
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Idle => "idle",
+            Phase::Planning => "planning",
+            Phase::Planned => "planned",
+            Phase::Migrating => "migrating",
+            Phase::Finished => "finished",
+        }
+    }
+
+    fn busy(&self) -> bool {
+        matches!(self, Phase::Planning | Phase::Migrating)
+    }
+}
+
+/// 单个 Mod 的状态
+struct ModState {
+    scanned: ScannedMod,
+    /// 版本选择结果
+    resolved: Option<Value>,
+    /// 迁移结果
+    result: Option<Value>,
+}
+
+struct State {
+    phase: Phase,
+    minecraft_version: Option<String>,
+    mods: Vec<ModState>,
+    /// 与 `mods` 一一对应的迁移计划, 迁移时取出
+    plans: Vec<Option<Plan>>,
+    /// 上一次任务失败的原因
+    error: Option<String>,
+    /// 上一次迁移的报告
+    report: Option<Report>,
+    /// 上一次迁移中的原文件名与新文件名, 用于恢复
+    migrated_files: Vec<(String, Option<String>)>,
+}
+
+/// 本地 HTTP API, 通过与命令行相同的迁移流程扫描, 选择版本, 迁移与恢复 Mod
+///
+/// 所有请求需带有 `Authorization: Bearer <令牌>`, 响应均为 JSON (报告除外):
+/// - `GET /api/status`: 当前阶段与各 Mod 的选择结果, 下载进度与迁移结果
+/// - `GET /api/scan`: 扫描 Mods 目录
+/// - `POST /api/plan`: 为所有 Mod 选择版本, 请求体为 `{"minecraft_version": "1.20.4"}`
+/// - `POST /api/migrate`: 按已选定的版本迁移; 请求体带有 `minecraft_version` 时重新选择版本
+/// - `GET /api/report?format=json|markdown|html`: 上一次迁移的报告
+/// - `POST /api/restore`: 删除上一次迁移下载的文件, 从备份中恢复原文件
+///
+/// 选择版本与迁移在后台进行, 通过 `GET /api/status` 查询进度; 运行期间其他任务返回 409
+pub struct ApiServer<'a> {
+    server: Server,
+    token: String,
+    config: &'a Config,
+    providers: &'a [Box<dyn ModProvider>],
+    mappings: &'a Mappings,
+    state: Mutex<State>,
+    /// 下载线程在迁移引擎中更新
+    progress: DownloadProgress,
+}
+
+impl<'a> ApiServer<'a> {
+    /// 监听 `config.serve.address`
+    pub fn bind(
+        token: String,
+        config: &'a Config,
+        providers: &'a [Box<dyn ModProvider>],
+        mappings: &'a Mappings,
+    ) -> Result<Self, String> {
+        let server = Server::http(&config.serve.address).map_err(|e| e.to_string())?;
+        Ok(ApiServer {
+            server,
+            token,
+            config,
+            providers,
+            mappings,
+            state: Mutex::new(State {
+                phase: Phase::Idle,
+                minecraft_version: None,
+                mods: Vec::new(),
+                plans: Vec::new(),
+                error: None,
+                report: None,
+                migrated_files: Vec::new(),
+            }),
+            progress: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn address(&self) -> String {
+        match self.server.server_addr().to_ip() {
+            Some(address) => address.to_string(),
+            None => self.config.serve.address.clone(),
+        }
+    }
+
+    /// 处理请求直到进程退出
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            for request in self.server.incoming_requests() {
+                self.handle(scope, request);
+            }
+        });
+    }
+
+    fn handle<'s>(&'s self, scope: &'s Scope<'s, '_>, mut request: Request) {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let (status, body) = if !self.authorized(&request) {
+            error(401, "Missing or invalid token")
+        } else {
+            match (&method, path) {
+                (Method::Get, "/api/status") => (200, self.status()),
+                (Method::Get, "/api/scan") => self.scan(),
+                (Method::Post, "/api/plan") => match read_body(&mut request) {
+                    Ok(body) => self.plan(scope, &body),
+                    Err(response) => response,
+                },
+                (Method::Post, "/api/migrate") => match read_body(&mut request) {
+                    Ok(body) => self.migrate(scope, &body),
+                    Err(response) => response,
+                },
+                (Method::Get, "/api/report") => {
+                    return self.report(request, query);
+                }
+                (Method::Post, "/api/restore") => self.restore(),
+                (
+                    _,
+                    "/api/status" | "/api/scan" | "/api/plan" | "/api/migrate" | "/api/report"
+                    | "/api/restore",
+                ) => error(405, "Method not allowed"),
+                _ => error(404, "Not found"),
+            }
+        };
+        log::info!("API {} {} -> {}", method, path, status);
+        respond(request, status, "application/json", body.to_string());
+    }
+
+    /// 令牌是否正确, 逐字节比较全部内容以免泄露匹配长度
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(provided) = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        let (a, b) = (provided.trim().as_bytes(), self.token.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn status(&self) -> Value {
+        let state = self.lock();
+        let progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        let mods: Vec<Value> = state
+            .mods
+            .iter()
+            .enumerate()
+            .map(|(index, m)| {
+                let mut value = scanned_json(index, &m.scanned);
+                value["resolved"] = m.resolved.clone().unwrap_or(Value::Null);
+                value["progress"] = match progress.get(index).copied().flatten() {
+                    Some((done, total)) => json!({ "downloaded": done, "total": total }),
+                    None => Value::Null,
+                };
+                value["result"] = m.result.clone().unwrap_or(Value::Null);
+                value
+            })
+            .collect();
+        json!({
+            "phase": state.phase.name(),
+            "minecraft_version": state.minecraft_version,
+            "error": state.error,
+            "finished": state.mods.iter().filter(|m| m.result.is_some()).count(),
+            "total": state.mods.len(),
+            "report": state.report.is_some(),
+            "mods": mods,
+        })
+    }
+
+    fn scan(&self) -> (u16, Value) {
+        if self.lock().phase.busy() {
+            return error(409, "A task is running");
+        }
+        match scan_mods() {
+            Ok(mods) => {
+                let mods: Vec<Value> = mods
+                    .iter()
+                    .enumerate()
+                    .map(|(index, scanned)| scanned_json(index, scanned))
+                    .collect();
+                (200, json!({ "mods": mods }))
+            }
+            Err(e) => error(500, &format!("Failed to read the mods directory: {}", e)),
+        }
+    }
+
+    /// 在后台为所有 Mod 选择版本
+    fn plan<'s>(&'s self, scope: &'s Scope<'s, '_>, body: &Value) -> (u16, Value) {
+        let Some(version) = requested_version(body) else {
+            return error(400, "`minecraft_version` is missing or invalid");
+        };
+        let mods = {
+            let mut state = self.lock();
+            if state.phase.busy() {
+                return error(409, "A task is running");
+            }
+            let mods = match self.reset(&mut state, &version) {
+                Ok(mods) => mods,
+                Err(response) => return response,
+            };
+            state.phase = Phase::Planning;
+            mods
+        };
+        let total = mods.len();
+        scope.spawn(move || {
+            self.plan_all(&mods, &version);
+            self.lock().phase = Phase::Planned;
+        });
+        (202, json!({ "phase": "planning", "total": total }))
+    }
+
+    /// 在后台迁移; 带有 `minecraft_version` 或尚未选择版本时先选择版本
+    fn migrate<'s>(&'s self, scope: &'s Scope<'s, '_>, body: &Value) -> (u16, Value) {
+        let requested = body.get("minecraft_version");
+        let (mods, version, replan) = {
+            let mut state = self.lock();
+            if state.phase.busy() {
+                return error(409, "A task is running");
+            }
+            if requested.is_none()
+                && state.phase == Phase::Planned
+                && let Some(version) = state.minecraft_version.clone()
+            {
+                let mods = state.mods.iter().map(|m| m.scanned.clone()).collect();
+                (mods, version, false)
+            } else {
+                let Some(version) = requested_version(body) else {
+                    return error(400, "`minecraft_version` is missing or invalid");
+                };
+                let mods = match self.reset(&mut state, &version) {
+                    Ok(mods) => mods,
+                    Err(response) => return response,
+                };
+                (mods, version, true)
+            }
+        };
+        // 上一次迁移结束时已删除下载目录, 每次迁移前重新创建
+        if let Err(e) = scan::create_backup_folder() {
+            let message = format!("Failed to create the backup and cache folders: {}", e);
+            self.lock().error = Some(message.clone());
+            return error(500, &message);
+        }
+        // 迁移前备份原文件, 失败时不作任何修改
+        for scanned in &mods {
+            if let Err(e) = scan::backup_file(&scanned.path) {
+                let message = format!("Failed to back up {}: {}", scanned.file_name, e);
+                self.lock().error = Some(message.clone());
+                return error(500, &message);
+            }
+        }
+        self.lock().phase = Phase::Migrating;
+        let total = mods.len();
+        scope.spawn(move || {
+            let start_time = Instant::now();
+            if replan {
+                self.plan_all(&mods, &version);
+            }
+            let plans: Vec<(ScannedMod, Plan)> = {
+                let mut state = self.lock();
+                let plans = mem::take(&mut state.plans);
+                mods.into_iter()
+                    .zip(plans)
+                    .map(|(scanned, plan)| {
+                        (
+                            scanned,
+                            plan.unwrap_or(Plan::Done(MigrationOutcome::Ignored)),
+                        )
+                    })
+                    .collect()
+            };
+            let results = self.apply_all(plans, &version);
+            self.finish(&results, &version, start_time);
+        });
+        (202, json!({ "phase": "migrating", "total": total }))
+    }
+
+    /// 重新扫描 Mods 目录并清空上一次任务的状态, 返回扫描到的 Mod
+    fn reset(&self, state: &mut State, version: &str) -> Result<Vec<ScannedMod>, (u16, Value)> {
+        let mods = scan_mods()
+            .map_err(|e| error(500, &format!("Failed to read the mods directory: {}", e)))?;
+        if mods.is_empty() {
+            return Err(error(409, "No valid mods found in the mods directory"));
+        }
+        state.minecraft_version = Some(version.to_string());
+        state.mods = mods
+            .iter()
+            .map(|scanned| ModState {
+                scanned: scanned.clone(),
+                resolved: None,
+                result: None,
+            })
+            .collect();
+        state.plans = mods.iter().map(|_| None).collect();
+        *self.progress.lock().unwrap_or_else(|e| e.into_inner()) = vec![None; mods.len()];
+        state.error = None;
+        Ok(mods)
+    }
+
+    fn migration<'m>(&'m self, version: &'m str) -> Migration<'m> {
+        Migration {
+            mc_version: version,
+            cache_dir: Path::new(CACHE_DIR),
+            providers: self.providers,
+            mappings: self.mappings,
+            config: self.config,
+            // 无法向请求方提问, 需要选择时按失败处理
+            picker: None,
+        }
+    }
+
+    fn plan_all(&self, mods: &[ScannedMod], version: &str) {
+        plan_mods(mods, &self.migration(version), |index, plan| {
+            let resolved = resolved_json(&plan, self.providers);
+            let mut state = self.lock();
+            state.mods[index].resolved = Some(resolved);
+            state.plans[index] = Some(plan);
+        });
+    }
+
+    fn apply_all(&self, plans: Vec<(ScannedMod, Plan)>, version: &str) -> Vec<MigrationResult> {
+        let mut results = Vec::with_capacity(plans.len());
+        let downloads = Arc::clone(&self.progress);
+        let progress = move |index: usize, done, total| {
+            let mut downloads = downloads.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(entry) = downloads.get_mut(index) {
+                *entry = Some((done, total));
+            }
+        };
+        apply_plans(
+            plans,
+            &self.migration(version),
+            progress,
+            |index, result| {
+                self.lock().mods[index].result = Some(ReportEntry::new(&result).to_json());
+                results.push(result);
+            },
+        );
+        results
+    }
+
+    /// 将下载的文件移回 Mods 目录, 写入锁文件与迁移报告
+    fn finish(&self, results: &[MigrationResult], version: &str, start_time: Instant) {
+        let report = Report::new(version, results, start_time.elapsed());
+        log::info!(
+            "Migration to Minecraft {} finished: {} migrated, {} failed, {} ignored",
+            report.minecraft_version,
+            report.count(Status::Migrated),
+            report.failed(),
+            report.count(Status::Ignored)
+        );
+        let mut errors = Vec::new();
+        if let Err(e) = scan::move_files_from_cache_to_current_dir(Path::new(CACHE_DIR)) {
+            log::error!("Failed to move the downloaded mods: {}", e);
+            errors.push(format!("Failed to move the downloaded mods: {}", e));
+        } else {
+            let mut lockfile = Lockfile::new(version);
+            lockfile.mods = results
+                .iter()
+                .filter_map(|r| match &r.outcome {
+                    MigrationOutcome::Migrated { locked, .. } => Some(locked.as_ref().clone()),
+                    _ => None,
+                })
+                .collect();
+            if let Err(e) = lockfile.save(Path::new(LOCKFILE_NAME)) {
+                errors.push(format!("Failed to write the lockfile: {}", e));
+            }
+        }
+        for format in &self.config.report.formats {
+            let path = report_path(&self.config.report.file, *format);
+            if let Err(e) = report.save(*format, &path) {
+                errors.push(format!(
+                    "Failed to write the migration report {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+        let mut state = self.lock();
+        // 按配置跳过的 Mod 保留原文件, 无需恢复
+        state.migrated_files = results
+            .iter()
+            .filter(|r| !matches!(r.outcome, MigrationOutcome::Ignored))
+            .map(|r| {
+                let new_file = match &r.outcome {
+                    MigrationOutcome::Migrated { locked, .. } => Some(locked.file_name.clone()),
+                    _ => None,
+                };
+                (r.scanned.file_name.clone(), new_file)
+            })
+            .collect();
+        state.report = Some(report);
+        state.error = (!errors.is_empty()).then(|| errors.join("; "));
+        state.phase = Phase::Finished;
+    }
+
+    fn report(&self, request: Request, query: &str) {
+        let format = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("format="))
+            .unwrap_or("json");
+        let (status, content_type, body) = match ReportFormat::parse(format) {
+            None => {
+                let (status, body) = error(400, "`format` must be json, markdown or html");
+                (status, "application/json", body.to_string())
+            }
+            Some(format) => match &self.lock().report {
+                Some(report) => (
+                    200,
+                    match format {
+                        ReportFormat::Json => "application/json",
+                        ReportFormat::Markdown => "text/markdown; charset=utf-8",
+                        ReportFormat::Html => "text/html; charset=utf-8",
+                    },
+                    report.render(format),
+                ),
+                None => {
+                    let (status, body) = error(404, "No migration has finished yet");
+                    (status, "application/json", body.to_string())
+                }
+            },
+        };
+        log::info!("API GET /api/report -> {}", status);
+        respond(request, status, content_type, body);
+    }
+
+    /// 删除上一次迁移下载的文件, 从备份中恢复原文件
+    fn restore(&self) -> (u16, Value) {
+        let mut state = self.lock();
+        if state.phase.busy() {
+            return error(409, "A task is running");
+        }
+        if state.migrated_files.is_empty() {
+            return error(409, "Nothing to restore");
+        }
+        let mut removed = Vec::new();
+        let mut restored = Vec::new();
+        let mut errors = Vec::new();
+        for (original, new_file) in &state.migrated_files {
+            if let Some(new_file) = new_file
+                && new_file != original
+            {
+                match fs::remove_file(new_file) {
+                    Ok(()) => removed.push(new_file.clone()),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => errors.push(ReportError::new(new_file, &e.into()).to_json()),
+                }
+            }
+            match scan::restore_file(original) {
+                Ok(()) => restored.push(original.clone()),
+                Err(e) => errors.push(ReportError::new(original, &e.into()).to_json()),
+            }
+        }
+        log::info!(
+            "Restored {} mods from the backup, removed {} downloaded files",
+            restored.len(),
+            removed.len()
+        );
+        state.migrated_files.clear();
+        state.phase = Phase::Idle;
+        (
+            200,
+            json!({ "restored": restored, "removed": removed, "errors": errors }),
+        )
+    }
+}
+
+/// 随机生成的访问令牌 (32 位十六进制), 读取操作系统的随机数源
+pub fn generate_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 扫描 Mods 目录中的有效 Mod
+fn scan_mods() -> io::Result<Vec<ScannedMod>> {
+    let jar_files = scan::filter_valid_mods(scan::get_jar_files()?);
+    Ok(jar_files.iter().map(ScannedMod::read).collect())
+}
+
+fn scanned_json(index: usize, scanned: &ScannedMod) -> Value {
+    json!({
+        "index": index,
+        "file_name": scanned.file_name,
+        "mod_id": scanned.mod_id,
+        "name": scanned.name,
+        "version": scanned.version,
+        "loader": scanned.loader.as_ref().and_then(loader_name),
+    })
+}
+
+/// 版本选择结果
+fn resolved_json(plan: &Plan, providers: &[Box<dyn ModProvider>]) -> Value {
+    let (status, errors) = match plan {
+        Plan::Ready(planned) => ("ready", planned.errors.as_slice()),
+        Plan::Done(outcome @ MigrationOutcome::Failed { errors }) => {
+            (Status::from_outcome(outcome).name(), errors.as_slice())
+        }
+        Plan::Done(outcome) => (Status::from_outcome(outcome).name(), &[][..]),
+    };
+    let mut value = json!({
+        "status": status,
+        "errors": errors
+            .iter()
+            .map(|e| ReportError::new(&e.provider, &e.error).to_json())
+            .collect::<Vec<_>>(),
+    });
+    if let Plan::Ready(planned) = plan {
+        let version = &planned.resolved.version;
+        value["provider"] = json!(providers[planned.provider].name());
+        value["version_id"] = json!(version.version_id);
+        value["version_number"] = json!(version.version_number);
+        value["file_name"] = json!(version.file_name);
+        value["reason"] = json!(planned.resolved.reason);
+        value["compatible_with"] = json!(planned.resolved.compatible_with);
+    }
+    value
+}
+
+/// 请求体中合法的目标 Minecraft 版本
+fn requested_version(body: &Value) -> Option<String> {
+    body.get("minecraft_version")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|version| validate_version(version))
+        .map(str::to_string)
+}
+
+/// 读取 JSON 请求体, 为空时视为 `{}`; 超过 [`MAX_BODY_SIZE`] 时返回 413
+fn read_body(request: &mut Request) -> Result<Value, (u16, Value)> {
+    let too_large = || error(413, "Request body is larger than 64 KiB");
+    if request
+        .body_length()
+        .is_some_and(|length| length as u64 > MAX_BODY_SIZE)
+    {
+        return Err(too_large());
+    }
+    // 未声明长度 (分块传输) 时多读一个字节以判断是否超出上限
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| error(400, &format!("Failed to read the request body: {}", e)))?;
+    if body.len() as u64 > MAX_BODY_SIZE {
+        return Err(too_large());
+    }
+    if body.trim().is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_str(&body).map_err(|e| error(400, &format!("Invalid JSON: {}", e)))
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
+
+fn respond(request: Request, status: u16, content_type: &str, body: String) {
+    let mut response = Response::from_string(body).with_status_code(status);
+    if let Ok(header) = Header::from_bytes("Content-Type", content_type) {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        log::warn!("Failed to send the API response: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiServer, generate_token};
+    use crate::api::DownloadedMod;
+    use crate::api::download::ExpectedHashes;
+    use crate::api::provider::{
+        Channel, Criteria, ModProvider, RemoteProject, RemoteVersion, Resolved,
+    };
+    use crate::config::Config;
+    use crate::error::Error;
+    use crate::mapping::database::Mappings;
+    use reqwest::StatusCode;
+    use reqwest::blocking::{Client, Response};
+    use serde_json::{Value, json};
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const TOKEN: &str = "test-token";
+
+    /// 服务器读写当前目录, 修改当前目录的测试依次运行
+    static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+    /// 写入只含 `fabric.mod.json` 的 Jar 文件
+    fn write_jar(path: &Path, mod_id: &str, version: &str) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        zip.start_file("fabric.mod.json", SimpleFileOptions::default())
+            .unwrap();
+        let metadata = json!({
+            "schemaVersion": 1,
+            "id": mod_id,
+            "version": version,
+            "name": "Stub Mod",
+        });
+        zip.write_all(metadata.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    /// 本地测试用的下载来源, 所有 Mod 都有一个支持目标版本的 2.0.0 版本
+    /// 关闭 `gate` 时识别项目将等待, 用于测试任务运行期间的请求
+    struct StubProvider {
+        gate: Arc<(Mutex<bool>, Condvar)>,
+    }
+
+    impl StubProvider {
+        fn new(open: bool) -> Self {
+            StubProvider {
+                gate: Arc::new((Mutex::new(open), Condvar::new())),
+            }
+        }
+    }
+
+    impl ModProvider for StubProvider {
+        fn id(&self) -> &'static str {
+            "stub"
+        }
+
+        fn name(&self) -> &'static str {
+            "Stub"
+        }
+
+        fn identify(&self, mod_id: &str, _jar: &Path) -> Result<Option<RemoteProject>, Error> {
+            let (open, opened) = &*self.gate;
+            let _open = opened
+                .wait_while(open.lock().unwrap(), |open| !*open)
+                .unwrap();
+            Ok(Some(RemoteProject {
+                id: mod_id.to_string(),
+                slug: mod_id.to_string(),
+                title: mod_id.to_string(),
+                authors: Vec::new(),
+                downloads: None,
+            }))
+        }
+
+        fn find_project(&self, _id: &str) -> Result<Option<RemoteProject>, Error> {
+            Ok(None)
+        }
+
+        fn search(
+            &self,
+            _query: &str,
+            _mc_version: &str,
+            _loader: &str,
+        ) -> Result<Vec<RemoteProject>, Error> {
+            Ok(Vec::new())
+        }
+
+        fn list_versions(
+            &self,
+            project: &RemoteProject,
+            criteria: &Criteria,
+        ) -> Result<Vec<RemoteVersion>, Error> {
+            Ok(vec![RemoteVersion {
+                project_id: project.id.clone(),
+                version_id: "2".to_string(),
+                version_number: "2.0.0".to_string(),
+                game_versions: vec![criteria.mc_version.to_string()],
+                loaders: vec![criteria.loader.to_string()],
+                published: "2024-01-01T00:00:00Z".to_string(),
+                channel: Channel::Release,
+                file_name: format!("{}-2.0.jar", project.id),
+                url: None,
+                hashes: ExpectedHashes::default(),
+                downloads: None,
+                changelog: None,
+            }])
+        }
+
+        fn download(&self, resolved: &Resolved, output_dir: &Path) -> Result<DownloadedMod, Error> {
+            let version = &resolved.version;
+            let path = output_dir.join(&version.file_name);
+            write_jar(&path, &version.project_id, &version.version_number);
+            Ok(DownloadedMod {
+                provider: self.id().to_string(),
+                project_id: version.project_id.clone(),
+                version_id: version.version_id.clone(),
+                version_number: version.version_number.clone(),
+                file_name: version.file_name.clone(),
+                url: String::new(),
+                reason: resolved.reason.clone(),
+                compatible_with: resolved.compatible_with.clone(),
+                path,
+            })
+        }
+    }
+
+    /// 在后台运行的测试服务器, 监听 `127.0.0.1` 上的随机端口
+    struct TestServer {
+        url: String,
+        client: Client,
+        gate: Arc<(Mutex<bool>, Condvar)>,
+    }
+
+    impl TestServer {
+        fn start(open: bool) -> Self {
+            let provider = StubProvider::new(open);
+            let gate = Arc::clone(&provider.gate);
+            let mut config = Config::default();
+            config.serve.address = "127.0.0.1:0".to_string();
+            config.jobs = 1;
+            // 服务器在测试进程结束前一直运行
+            let config: &'static Config = Box::leak(Box::new(config));
+            let providers: &'static [Box<dyn ModProvider>] =
+                Box::leak(vec![Box::new(provider) as Box<dyn ModProvider>].into_boxed_slice());
+            let mappings: &'static Mappings = Box::leak(Box::default());
+            let server = ApiServer::bind(TOKEN.to_string(), config, providers, mappings).unwrap();
+            let server: &'static ApiServer = Box::leak(Box::new(server));
+            let url = format!("http://{}/api", server.address());
+            thread::spawn(move || server.run());
+            TestServer {
+                url,
+                client: Client::new(),
+                gate,
+            }
+        }
+
+        fn open_gate(&self) {
+            let (open, opened) = &*self.gate;
+            *open.lock().unwrap() = true;
+            opened.notify_all();
+        }
+
+        fn get(&self, path: &str) -> Response {
+            self.client
+                .get(format!("{}{}", self.url, path))
+                .bearer_auth(TOKEN)
+                .send()
+                .unwrap()
+        }
+
+        fn post(&self, path: &str, body: &str) -> Response {
+            self.client
+                .post(format!("{}{}", self.url, path))
+                .bearer_auth(TOKEN)
+                .body(body.to_string())
+                .send()
+                .unwrap()
+        }
+
+        fn status(&self) -> Value {
+            self.get("/status").json().unwrap()
+        }
+
+        /// 等待任务进入 `phase` 阶段, 返回当时的状态
+        fn wait_for(&self, phase: &str) -> Value {
+            let start = Instant::now();
+            loop {
+                let status = self.status();
+                if status["phase"] == phase {
+                    return status;
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "still {} instead of {}",
+                    status["phase"],
+                    phase
+                );
+                thread::sleep(Duration::from_millis(20));
+            }
+        }
+    }
+
+    /// 切换到单独的临时 Mods 目录, 离开作用域时删除
+    struct ModsDir {
+        path: PathBuf,
+        previous: PathBuf,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl ModsDir {
+        fn new(name: &str) -> Self {
+            let lock = CURRENT_DIR.lock().unwrap_or_else(|e| e.into_inner());
+            let path = std::env::temp_dir().join(format!(
+                "MiraMigrator-test-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            let previous = std::env::current_dir().unwrap();
+            std::env::set_current_dir(&path).unwrap();
+            ModsDir {
+                path,
+                previous,
+                _lock: lock,
+            }
+        }
+    }
+
+    impl Drop for ModsDir {
+        fn drop(&mut self) {
+            let _ = std::env::set_current_dir(&self.previous);
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    #[test]
+    fn generates_distinct_hex_tokens() {
+        let (a, b) = (generate_token().unwrap(), generate_token().unwrap());
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn rejects_requests_without_a_valid_token() {
+        let server = TestServer::start(true);
+        let response = server
+            .client
+            .get(format!("{}/status", server.url))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = server
+            .client
+            .get(format!("{}/status", server.url))
+            .bearer_auth("wrong-token")
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(server.get("/status").status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn routes_unknown_paths_and_methods() {
+        let server = TestServer::start(true);
+        assert_eq!(server.get("/unknown").status(), StatusCode::NOT_FOUND);
+        assert_eq!(server.get("/plan").status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            server.post("/status", "").status(),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(server.get("/report").status(), StatusCode::NOT_FOUND);
+        assert_eq!(server.post("/restore", "").status(), StatusCode::CONFLICT);
+        assert_eq!(
+            server
+                .post("/plan", r#"{"minecraft_version": "bogus"}"#)
+                .status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(server.post("/plan", "{").status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn limits_the_request_body() {
+        let server = TestServer::start(true);
+        let body = r#"{"minecraft_version": "bogus"}"#;
+        let padded = format!("{}{}", " ".repeat(64 * 1024 - body.len()), body);
+        assert_eq!(
+            server.post("/plan", &padded).status(),
+            StatusCode::BAD_REQUEST
+        );
+        let too_large = format!(" {}", padded);
+        assert_eq!(
+            server.post("/plan", &too_large).status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[test]
+    fn rejects_tasks_while_one_is_running() {
+        let _dir = ModsDir::new("busy");
+        write_jar(Path::new("stubmod-1.0.jar"), "stubmod", "1.0.0");
+        let server = TestServer::start(false);
+        let plan = r#"{"minecraft_version": "1.20.4"}"#;
+        assert_eq!(server.post("/plan", plan).status(), StatusCode::ACCEPTED);
+        assert_eq!(server.status()["phase"], "planning");
+        assert_eq!(server.post("/plan", plan).status(), StatusCode::CONFLICT);
+        assert_eq!(server.post("/migrate", plan).status(), StatusCode::CONFLICT);
+        assert_eq!(server.get("/scan").status(), StatusCode::CONFLICT);
+        assert_eq!(server.post("/restore", "").status(), StatusCode::CONFLICT);
+        server.open_gate();
+        server.wait_for("planned");
+    }
+
+    #[test]
+    fn plans_migrates_reports_and_restores() {
+        let _dir = ModsDir::new("flow");
+        write_jar(Path::new("stubmod-1.0.jar"), "stubmod", "1.0.0");
+        let server = TestServer::start(true);
+
+        let scan: Value = server.get("/scan").json().unwrap();
+        assert_eq!(scan["mods"][0]["mod_id"], "stubmod");
+
+        let response = server.post("/plan", r#"{"minecraft_version": "1.20.4"}"#);
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let status = server.wait_for("planned");
+        assert_eq!(status["minecraft_version"], "1.20.4");
+        let resolved = &status["mods"][0]["resolved"];
+        assert_eq!(resolved["status"], "ready");
+        assert_eq!(resolved["version_number"], "2.0.0");
+        assert_eq!(server.get("/report").status(), StatusCode::NOT_FOUND);
+
+        assert_eq!(server.post("/migrate", "").status(), StatusCode::ACCEPTED);
+        let status = server.wait_for("finished");
+        assert_eq!(status["error"], Value::Null);
+        assert_eq!(status["mods"][0]["result"]["status"], "migrated");
+        assert!(Path::new("stubmod-2.0.jar").is_file());
+        assert!(!Path::new("stubmod-1.0.jar").exists());
+        assert!(Path::new("backup/stubmod-1.0.jar").is_file());
+        assert!(Path::new("miramigrator.lock").is_file());
+
+        let report: Value = server.get("/report").json().unwrap();
+        assert_eq!(report["minecraft_version"], "1.20.4");
+        let response = server.get("/report?format=markdown");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.text().unwrap().contains("`stubmod`"));
+        let response = server.get("/report?format=html");
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            server.get("/report?format=pdf").status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let restore: Value = server.post("/restore", "").json().unwrap();
+        assert_eq!(restore["restored"], json!(["stubmod-1.0.jar"]));
+        assert_eq!(restore["removed"], json!(["stubmod-2.0.jar"]));
+        assert!(Path::new("stubmod-1.0.jar").is_file());
+        assert!(!Path::new("stubmod-2.0.jar").exists());
+        assert_eq!(server.post("/restore", "").status(), StatusCode::CONFLICT);
+    }
+}
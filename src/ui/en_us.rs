@@ -1,60 +1,441 @@
-use super::Interface;
-use crate::CURSEFORGE_API_KEY;
+use super::progress::ProgressBars;
+use super::tui::{self, TuiContext, TuiText};
+use super::{Interface, format_rules, lock_prompt, read_choice, read_line};
 use crate::LOGO;
 use crate::VERSION;
-use crate::api::curseforge::download_curseforge_mod;
-use crate::api::modrinth::download_modrinth_mod;
+use crate::api::build_providers;
+use crate::api::download;
+use crate::api::provider::{ModProvider, RemoteVersion};
+use crate::cache::store::Cache;
+use crate::cli::args::{CacheAction, MappingsAction};
+use crate::config::{CONFIG_FILE_NAME, Config, ReportConfig, save_mod_rules};
+use crate::core::migrate::{
+    Migration, MigrationOutcome, MigrationResult, ScannedMod, migrate_mods,
+};
+use crate::core::search::{Candidate, Picker, SearchQuery};
+use crate::error::Error;
+use crate::lock::lockfile::{
+    InstallStatus, LOCKFILE_NAME, LockInstaller, Lockfile, remove_unlocked_jars,
+};
+use crate::mapping::database::{self, Mappings};
+use crate::report::report::{Report, report_path};
 use crate::scan::scan;
-use crate::utils::loader::{self, detect_mod, get_mod_id, get_mod_version};
+use crate::server::server::{self, ApiServer};
+use crate::utils::format::format_size;
+use crate::utils::loader;
+use crate::utils::output::{self, Verbosity};
 use crate::utils::version::validate_version;
+use std::env;
+use std::fs::DirEntry;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
 
+/// Maximum number of characters shown from a changelog
+const CHANGELOG_EXCERPT_CHARS: usize = 80;
+
+/// Localized error message
+fn error_message(error: &Error) -> String {
+    match error {
+        Error::NotFound(what) => format!("Not found: {}", what),
+        Error::NoCompatibleVersion(detail) => format!("No compatible version ({})", detail),
+        Error::Network(detail) => format!("Network error: {}", detail),
+        Error::RateLimited(Some(wait)) => format!(
+            "Too many requests, rate limited; retry in {} seconds",
+            wait.as_secs().max(1)
+        ),
+        Error::RateLimited(None) => "Too many requests, rate limited; retry later".to_string(),
+        Error::Io(e) => format!("Failed to read or write a file: {}", e),
+        Error::CorruptJar(detail) => format!("Corrupt jar file: {}", detail),
+        Error::HashMismatch(detail) => {
+            format!("The downloaded file does not match its hash: {}", detail)
+        }
+        Error::DistributionDisabled(file) => format!(
+            "The author does not allow third-party downloads of {}, please download it manually",
+            file
+        ),
+        Error::Other(message) => message.clone(),
+    }
+}
+
+/// Text shown in the full-screen interface
+const TUI_TEXT: TuiText = TuiText {
+    title: "MiraMigrator",
+    columns: [
+        "File",
+        "Loader",
+        "Installed",
+        "Target",
+        "Provider",
+        "Status",
+    ],
+    resolving: "Resolving...",
+    ready: "Ready",
+    skipped: "Skipped",
+    ignored: "Skipped (config)",
+    failed: "Failed",
+    unrecognized: "Unrecognized",
+    unknown_loader: "Unknown loader",
+    waiting: "Waiting",
+    downloading: "Downloading",
+    migrated: "Migrated",
+    details: "Details",
+    reason: "Reason",
+    compatible: "Compatible, not exact: this version was built for Minecraft",
+    pin_title: "Choose a version to pin",
+    pinned: "Pinned and saved to the config:",
+    save_failed: "Failed to save to the config:",
+    no_candidates: "This mod has no versions to choose from",
+    still_resolving: "Some mods are still resolving, please wait",
+    backup_failed: "Failed to back up mod file:",
+    help_review: "↑↓ select  s skip/unskip  p pin version  r re-resolve  Enter migrate  q quit",
+    help_pin: "↑↓ select  Enter pin this version  Esc cancel",
+    help_applying: "Migrating, please wait...",
+    help_done: "Migration complete, press any key to continue",
+    error: error_message,
+};
+
 pub struct EnUsInterface;
 
 impl EnUsInterface {
     pub fn new() -> Self {
         EnUsInterface
     }
-}
 
-impl Interface for EnUsInterface {
-    fn init(&self) {
-        // Initialization
-        println!("Initializing MiraMigrator...");
-        scan::create_backup_folder();
-        println!("MiraMigrator initialization complete.");
+    /// Print the migration result of a single mod
+    fn print_result(&self, result: &MigrationResult) {
+        // 安静模式只输出需要手动处理的 Mod
+        if output::verbosity() == Verbosity::Quiet
+            && matches!(
+                result.outcome,
+                MigrationOutcome::Migrated { .. } | MigrationOutcome::Ignored
+            )
+        {
+            return;
+        }
+        let scanned = &result.scanned;
+        let mod_id = scanned.mod_id.as_deref().unwrap_or_default();
+        let errors = match &result.outcome {
+            MigrationOutcome::Ignored => {
+                println!(
+                    "Skipping mod {} as configured, keeping the original file.",
+                    scanned.file_name
+                );
+                return;
+            }
+            MigrationOutcome::Unrecognized => {
+                println!(
+                    "Unable to recognize mod {}, please migrate manually.",
+                    scanned.file_name
+                );
+                return;
+            }
+            MigrationOutcome::UnknownLoader => {
+                println!("Unknown mod loader type: {:?}", scanned.loader);
+                return;
+            }
+            MigrationOutcome::Migrated { errors, .. } | MigrationOutcome::Failed { errors } => {
+                errors
+            }
+        };
+        let loader = scanned.loader.as_ref().unwrap_or(&loader::ModLoader::None);
+        println!("Downloading {:?} Mod {}...", loader, mod_id);
+        for (i, error) in errors.iter().enumerate() {
+            println!(
+                "Failed to fetch from {}: {}",
+                error.provider,
+                error_message(&error.error)
+            );
+            if let Some(next) = result.outcome.next_provider(i) {
+                println!("Attempting to fetch from {}...", next);
+            }
+        }
+        match &result.outcome {
+            MigrationOutcome::Migrated {
+                new_version,
+                compatible_with,
+                added_dependencies,
+                ..
+            } => {
+                println!(
+                    "{}-{} => {}-{} took {:.2?}s",
+                    mod_id,
+                    scanned.version.as_deref().unwrap_or_default(),
+                    mod_id,
+                    new_version.as_deref().unwrap_or("?"),
+                    result.elapsed.as_secs_f64()
+                );
+                if let Some(mc_version) = compatible_with {
+                    println!(
+                        "Compatible, not exact: this version was built for Minecraft {}.",
+                        mc_version
+                    );
+                }
+                if !added_dependencies.is_empty() {
+                    println!(
+                        "The new version requires these mods, make sure they are installed: {}",
+                        added_dependencies.join(", ")
+                    );
+                }
+            }
+            _ => println!(
+                "{:?} Mod {} download failed, please migrate manually.",
+                loader, mod_id
+            ),
+        }
+    }
+
+    /// Save the user's choice to the config so later migrations do not ask again
+    fn save_choice(&self, mod_id: &str, provider: &dyn ModProvider, project_id: &str) {
+        let key = format!("projects.{}", provider.id());
+        let rules = &[(key.as_str(), project_id)];
+        match save_mod_rules(Path::new(CONFIG_FILE_NAME), mod_id, rules) {
+            Ok(()) => println!(
+                "Saved the choice to [mods.{}] in {}: {}",
+                mod_id,
+                CONFIG_FILE_NAME,
+                format_rules(rules)
+            ),
+            Err(e) => println!("Failed to save the choice: {}", e),
+        }
     }
 
-    fn start(&self) {
+    /// Load the providers and mappings, printing a config error on failure
+    fn load_sources(&self, config: &Config) -> Option<(Vec<Box<dyn ModProvider>>, Mappings)> {
+        let providers = match build_providers(config) {
+            Ok(providers) => providers,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        let mappings = match Mappings::load(config) {
+            Ok(mappings) => mappings,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        Some((providers, mappings))
+    }
+
+    /// Print the welcome message and read the target Minecraft version
+    fn read_version(&self) -> Option<String> {
         println!("{}", LOGO);
         println!("Welcome to MiraMigrator v{}", VERSION);
         println!("Created by: Lumira | QQ: 2301385546 | QQ Group: 000000000");
         println!("Please enter the target Minecraft version (e.g., 1.20.6):");
-        let mut version = String::new();
-        loop {
-            version.clear();
-            std::io::stdin()
-                .read_line(&mut version)
-                .expect("Failed to read user input.");
+        let version = loop {
+            let version = read_line()?;
             if validate_version(version.trim()) {
-                break;
+                break version;
             } else {
                 println!("Invalid version, please re-enter:");
             }
-        }
+        };
         print!("Target Minecraft version: {}", version);
+        Some(version)
+    }
+
+    /// Scan the Mods directory for valid mods, None when there are none
+    fn scan_mods(&self) -> Option<Vec<DirEntry>> {
         // Scanning Mods directory
         println!("Scanning Mods directory...");
-        let jar_files = scan::get_jar_files();
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                println!("Failed to read the Mods directory: {}", e);
+                return None;
+            }
+        };
         if jar_files.is_empty() {
             println!("No Jar files found, please check the Mods directory.");
-            return;
+            return None;
         }
         let jar_files = scan::filter_valid_mods(jar_files);
         if jar_files.is_empty() {
             println!("No valid mods found, please check the Mods directory.");
-            return;
+            return None;
         }
+        Some(jar_files)
+    }
+
+    /// Move the downloads into the Mods directory, print a summary, write the lockfile and the report
+    fn finish(
+        &self,
+        results: &[MigrationResult],
+        version: &str,
+        cache_path: &Path,
+        start_time: Instant,
+        report: &ReportConfig,
+    ) {
+        // Failed mods list
+        let failed_mods: Vec<_> = results
+            .iter()
+            .filter(|r| {
+                !matches!(
+                    r.outcome,
+                    MigrationOutcome::Migrated { .. } | MigrationOutcome::Ignored
+                )
+            })
+            .collect();
+        let ignored = results
+            .iter()
+            .filter(|r| matches!(r.outcome, MigrationOutcome::Ignored))
+            .count();
+        // Lockfile entries
+        let locked_mods = results
+            .iter()
+            .filter_map(|r| match &r.outcome {
+                MigrationOutcome::Migrated { locked, .. } => Some(locked.as_ref().clone()),
+                _ => None,
+            })
+            .collect();
+        log::info!(
+            "Migration to Minecraft {} finished: {} migrated, {} failed, {} ignored",
+            version.trim(),
+            results.len() - failed_mods.len() - ignored,
+            failed_mods.len(),
+            ignored
+        );
+        if let Err(e) = scan::move_files_from_cache_to_current_dir(cache_path) {
+            log::error!("Failed to move the downloaded mods: {}", e);
+            eprintln!("Version migration failed: {}", e);
+        } else {
+            let elapsed_time = start_time.elapsed();
+            println!(
+                "\nVersion migration complete, took: {:.2?}s",
+                elapsed_time.as_secs_f64()
+            );
+            println!(
+                "Successfully migrated {} mods, failed {} mods.",
+                results.len() - failed_mods.len() - ignored,
+                failed_mods.len()
+            );
+            if ignored > 0 {
+                println!("Skipped {} mods as configured.", ignored);
+            }
+            if !failed_mods.is_empty() {
+                println!("Failed mods list (please migrate manually):");
+                for result in &failed_mods {
+                    println!(" - {}", result.scanned.file_name);
+                }
+            }
+            // Writing lockfile
+            let mut lockfile = Lockfile::new(version);
+            lockfile.mods = locked_mods;
+            match lockfile.save(Path::new(LOCKFILE_NAME)) {
+                Ok(()) => println!("Migration result written to lockfile {}.", LOCKFILE_NAME),
+                Err(e) => println!("Failed to write lockfile: {}", e),
+            }
+        }
+        // Write the migration report
+        let migration_report = Report::new(version, results, start_time.elapsed());
+        for format in &report.formats {
+            let path = report_path(&report.file, *format);
+            match migration_report.save(*format, &path) {
+                Ok(()) => println!("Wrote the migration report to {}.", path.display()),
+                Err(e) => println!(
+                    "Failed to write the migration report {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+}
+
+impl Picker for EnUsInterface {
+    fn pick_project(
+        &self,
+        query: &SearchQuery,
+        provider: &dyn ModProvider,
+        candidates: &[Candidate],
+    ) -> Option<usize> {
+        let _prompt = lock_prompt();
+        let _pause = output::pause();
+        println!(
+            "Could not determine the {} project for mod {}, found these candidates:",
+            provider.name(),
+            query.display_name()
+        );
+        for (i, candidate) in candidates.iter().enumerate() {
+            let project = &candidate.project;
+            println!(
+                " {}. {} ({}) similarity {:.0}%, {} downloads",
+                i + 1,
+                project.title,
+                project.slug,
+                candidate.score * 100.0,
+                project.downloads.map_or("?".to_string(), |n| n.to_string())
+            );
+        }
+        println!("Enter a number, or 0 / empty for none of them:");
+        let index = read_choice(candidates.len(), "Invalid input, please try again:")?;
+        let project = &candidates[index].project;
+        self.save_choice(query.mod_id, provider, &project.id);
+        Some(index)
+    }
+
+    fn pick_version(
+        &self,
+        mod_id: &str,
+        provider: &dyn ModProvider,
+        versions: &[RemoteVersion],
+    ) -> Option<usize> {
+        let _prompt = lock_prompt();
+        let _pause = output::pause();
+        println!(
+            "Mod {} has these candidate versions on {}:",
+            mod_id,
+            provider.name()
+        );
+        for (i, version) in versions.iter().enumerate() {
+            println!(
+                " {}. {} [{}] {} {} downloads{}",
+                i + 1,
+                version.version_number,
+                version.channel.name(),
+                version.published_date(),
+                version.downloads.map_or("?".to_string(), |n| n.to_string()),
+                if i == 0 { " (recommended)" } else { "" }
+            );
+            if let Some(changelog) = version.changelog_excerpt(CHANGELOG_EXCERPT_CHARS) {
+                println!("    {}", changelog);
+            }
+        }
+        println!("Enter a number, or press Enter for the recommended version:");
+        let index = read_choice(versions.len(), "Invalid input, please try again:")?;
+        // Only the project is remembered, the version applies to this migration;
+        // a pinned version would never match when migrating to another Minecraft version
+        let version = &versions[index];
+        self.save_choice(mod_id, provider, &version.project_id);
+        Some(index)
+    }
+}
+
+impl Interface for EnUsInterface {
+    fn init(&self) -> bool {
+        // Initialization
+        println!("Initializing MiraMigrator...");
+        if let Err(e) = scan::create_backup_folder() {
+            println!("Failed to create the backup folder: {}", e);
+            return false;
+        }
+        println!("MiraMigrator initialization complete.");
+        true
+    }
+
+    fn start(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let Some(version) = self.read_version() else {
+            return;
+        };
+        let Some(jar_files) = self.scan_mods() else {
+            return;
+        };
         println!("Found {} mod files, listed below:", jar_files.len());
         for jar_file in &jar_files {
             println!(" - {}", jar_file.file_name().to_string_lossy());
@@ -62,12 +443,11 @@ impl Interface for EnUsInterface {
         println!(
             "Do you confirm to proceed with the version migration? The original mods will be backed up to the 'backup' folder in the current directory (y/n):"
         );
-        let mut confirm = String::new();
         loop {
-            confirm.clear();
-            std::io::stdin()
-                .read_line(&mut confirm)
-                .expect("Failed to read user input.");
+            let Some(confirm) = read_line() else {
+                println!("Version migration canceled.");
+                return;
+            };
             if confirm.trim().eq_ignore_ascii_case("y") {
                 break;
             } else if confirm.trim().eq_ignore_ascii_case("n") {
@@ -84,7 +464,13 @@ impl Interface for EnUsInterface {
             let file_name_osstr = jar_file.file_name();
             let file_name = file_name_osstr.to_string_lossy();
             let backup_path = format!("backup/{}", file_name);
-            std::fs::copy(jar_file.path(), backup_path).expect("Failed to back up Mod file.");
+            if let Err(e) = std::fs::copy(jar_file.path(), backup_path) {
+                println!(
+                    "Failed to back up Mod file {}: {}, version migration canceled.",
+                    file_name, e
+                );
+                return;
+            }
         }
         let elapsed_time = start_time.elapsed();
         println!(
@@ -94,183 +480,370 @@ impl Interface for EnUsInterface {
 
         // Executing version migration
         println!("Starting version migration...");
-        // Failed mods list
-        let mut failed_mods = Vec::new();
-        // Successful mods list
-        let mut success_mods = Vec::new();
         // Cache directory
-        let cache_path = std::path::Path::new("cache");
+        let cache_path = Path::new("cache");
         let start_time = Instant::now();
-        for jar_file in &jar_files {
-            let jar_file_path = jar_file.path();
-            let mod_id = get_mod_id(&jar_file_path).ok().flatten();
-            let mod_version = get_mod_version(&jar_file_path).ok().flatten();
-            let mod_loader = detect_mod(&jar_file_path).ok().flatten();
-            if mod_id.is_none() && mod_version.is_none() {
-                std::fs::remove_file(jar_file.path()).expect("Failed to delete original Jar file.");
-                failed_mods.push(jar_file);
-            } else {
-                std::fs::remove_file(jar_file.path()).expect("Failed to delete original Jar file.");
-                let old_mod_id = mod_id.unwrap();
-                let old_mod_version = mod_version.unwrap();
-                let loader = mod_loader.unwrap();
-                let cache_path = std::path::Path::new("cache");
-                let time = Instant::now();
-                let new_mod_version;
-                // Downloading Mod
-                // CourseForge API Key
-                let api_key = CURSEFORGE_API_KEY;
-                match loader {
-                    loader::ModLoader::Fabric => {
-                        println!("Downloading Fabric Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "fabric", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("Failed to fetch from Modrinth: {:?}", error);
-                                println!("Attempting to fetch from CurseForge...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "fabric",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("Failed to fetch from CurseForge: {:?}", error);
-                                        println!(
-                                            "Fabric Mod {} download failed, please migrate manually.",
-                                            old_mod_id
-                                        );
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    loader::ModLoader::Forge => {
-                        println!("Downloading Forge Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "forge", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("Failed to fetch from Modrinth: {:?}", error);
-                                println!("Attempting to fetch from CurseForge...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "forge",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("Failed to fetch from CurseForge: {:?}", error);
-                                        println!(
-                                            "Forge Mod {} download failed, please migrate manually.",
-                                            old_mod_id
-                                        );
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    loader::ModLoader::Quilt => {
-                        println!("Downloading Quilt Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "quilt", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("Failed to fetch from Modrinth: {:?}", error);
-                                println!("Attempting to fetch from CurseForge...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "quilt",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("Failed to fetch from CurseForge: {:?}", error);
-                                        println!(
-                                            "Quilt Mod {} download failed, please migrate manually.",
-                                            old_mod_id
-                                        );
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    loader::ModLoader::None => {
-                        println!("Unknown mod loader type: {:?}", loader);
-                        failed_mods.push(jar_file);
-                        continue;
-                    }
+        let scanned_mods: Vec<_> = jar_files.iter().map(ScannedMod::read).collect();
+        let bars = Arc::new(ProgressBars::new(
+            scanned_mods.iter().map(|m| m.file_name.clone()).collect(),
+            "Overall",
+        ));
+        let progress = {
+            let bars = bars.clone();
+            move |index, done, total| bars.update(index, done, total)
+        };
+        let results = migrate_mods(
+            scanned_mods,
+            &Migration {
+                mc_version: version.trim(),
+                cache_dir: cache_path,
+                providers: &providers,
+                mappings: &mappings,
+                config,
+                picker: Some(self),
+            },
+            progress,
+            |index, result| bars.finish(index, || self.print_result(result)),
+        );
+        bars.clear();
+        self.finish(&results, &version, cache_path, start_time, &config.report);
+    }
+
+    fn tui(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let Some(version) = self.read_version() else {
+            return;
+        };
+        let Some(jar_files) = self.scan_mods() else {
+            return;
+        };
+        let cache_path = Path::new("cache");
+        let start_time = Instant::now();
+        let scanned_mods = jar_files.iter().map(ScannedMod::read).collect();
+        let context = TuiContext {
+            mc_version: version.trim(),
+            cache_dir: cache_path,
+            providers: &providers,
+            mappings: &mappings,
+            config,
+            text: &TUI_TEXT,
+        };
+        match tui::run(&context, scanned_mods) {
+            Ok(Some(results)) => {
+                for result in &results {
+                    self.print_result(result);
                 }
+                self.finish(&results, &version, cache_path, start_time, &config.report);
+            }
+            Ok(None) => println!("Version migration canceled."),
+            Err(e) => println!("Failed to run the full-screen interface: {}", e),
+        }
+    }
 
-                let end_time = time.elapsed();
+    fn install(&self, config: &Config, locked: bool) {
+        if !locked {
+            println!(
+                "Only installing from the lockfile is supported, please use install --locked."
+            );
+            return;
+        }
+        println!("Reading lockfile {}...", LOCKFILE_NAME);
+        let lockfile = match Lockfile::load(Path::new(LOCKFILE_NAME)) {
+            Ok(lockfile) => lockfile,
+            Err(e) => {
+                println!("Failed to read lockfile: {}", e);
+                return;
+            }
+        };
+        println!(
+            "Lockfile target Minecraft version: {}, {} mods in total.",
+            lockfile.minecraft_version,
+            lockfile.mods.len()
+        );
+        // Backing up Mod files
+        println!("Starting to back up Mod files...");
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                println!("Failed to read the Mods directory: {}", e);
+                return;
+            }
+        };
+        for jar_file in jar_files {
+            let file_name = jar_file.file_name().to_string_lossy().to_string();
+            if let Err(e) = std::fs::copy(jar_file.path(), format!("backup/{}", file_name)) {
                 println!(
-                    "{}-{} => {}-{} took {:.2?}s",
-                    old_mod_id,
-                    old_mod_version,
-                    old_mod_id,
-                    new_mod_version.unwrap(),
-                    end_time.as_secs_f64()
+                    "Failed to back up Mod file {}: {}, installation canceled.",
+                    file_name, e
                 );
-                success_mods.push(jar_file);
+                return;
             }
         }
-        if let Err(e) = scan::move_files_from_cache_to_current_dir(cache_path) {
-            eprintln!("Version migration failed: {}", e);
-        } else {
-            let elapsed_time = start_time.elapsed();
-            println!(
-                "\nVersion migration complete, took: {:.2?}s",
-                elapsed_time.as_secs_f64()
+        // Installing from lockfile
+        let installer = match LockInstaller::new(config) {
+            Ok(installer) => installer,
+            Err(e) => {
+                println!("Failed to create the download client: {}", e);
+                return;
+            }
+        };
+        let start_time = Instant::now();
+        let mut failed_mods = Vec::new();
+        let bars = Arc::new(ProgressBars::new(
+            lockfile.mods.iter().map(|m| m.file_name.clone()).collect(),
+            "Overall",
+        ));
+        let quiet = output::verbosity() == Verbosity::Quiet;
+        for (index, locked_mod) in lockfile.mods.iter().enumerate() {
+            let progress = bars.clone();
+            let status = download::with_progress(
+                move |done, total| progress.update(index, done, total),
+                || installer.install(locked_mod, Path::new("."), Path::new("cache")),
             );
+            bars.finish(index, || match status {
+                Ok(_) if quiet => {}
+                Ok(InstallStatus::Unchanged) => {
+                    println!(" = {} (already the locked version)", locked_mod.file_name);
+                }
+                Ok(InstallStatus::Downloaded) => {
+                    println!(" + {} ({})", locked_mod.file_name, locked_mod.provider);
+                }
+                Err(e) => {
+                    println!(
+                        "Failed to install {}: {}",
+                        locked_mod.file_name,
+                        error_message(&e)
+                    );
+                    failed_mods.push(locked_mod);
+                }
+            });
+        }
+        bars.clear();
+        if failed_mods.is_empty() {
+            match remove_unlocked_jars(&lockfile, Path::new(".")) {
+                Ok(removed) => {
+                    for path in removed {
+                        println!(
+                            " - {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        );
+                    }
+                }
+                Err(e) => println!("Failed to remove mods not in the lockfile: {}", e),
+            }
+        } else {
+            println!("Some mods failed to install, so mods not in the lockfile were kept.");
+        }
+        println!(
+            "\nInstallation complete, took: {:.2?}s",
+            start_time.elapsed().as_secs_f64()
+        );
+        if !failed_mods.is_empty() {
             println!(
-                "Successfully migrated {} mods, failed {} mods.",
-                success_mods.len(),
-                failed_mods.len()
+                "The following mods failed to install, originals are backed up in the 'backup' folder:"
             );
-            if !failed_mods.is_empty() {
-                println!("Failed mods list (please migrate manually):");
-                for jar_file in &failed_mods {
-                    println!(" - {}", jar_file.file_name().to_string_lossy());
+            for locked_mod in failed_mods {
+                println!(" - {}", locked_mod.file_name);
+            }
+        }
+    }
+
+    fn cache(&self, config: &Config, action: CacheAction) {
+        let cache = Cache::new(&config.cache);
+        let Some(root) = cache.root() else {
+            println!("The download cache is disabled.");
+            return;
+        };
+        match action {
+            CacheAction::List => match cache.list_files() {
+                Ok(files) => {
+                    println!("Cache directory: {}", root.display());
+                    for file in &files {
+                        println!(
+                            " - {} [{}] {}",
+                            file.file_name,
+                            &file.sha1[..file.sha1.len().min(12)],
+                            format_size(file.size)
+                        );
+                    }
+                    println!(
+                        "{} files, {}; {} cached API responses.",
+                        files.len(),
+                        format_size(files.iter().map(|f| f.size).sum()),
+                        cache.metadata_count().unwrap_or(0)
+                    );
+                }
+                Err(e) => println!("Failed to read the cache: {}", e),
+            },
+            CacheAction::Prune => match cache.prune() {
+                Ok(summary) => println!(
+                    "Removed {} unused files and {} expired API responses, freed {}.",
+                    summary.files,
+                    summary.metadata,
+                    format_size(summary.bytes)
+                ),
+                Err(e) => println!("Failed to prune the cache: {}", e),
+            },
+            CacheAction::Clear => match cache.clear() {
+                Ok(()) => println!("Cleared cache directory {}.", root.display()),
+                Err(e) => println!("Failed to clear the cache: {}", e),
+            },
+        }
+    }
+
+    fn mappings(&self, config: &Config, action: MappingsAction) {
+        match action {
+            MappingsAction::Show => match Mappings::load(config) {
+                Ok(mappings) => {
+                    println!("Bundled mappings loaded.");
+                    for source in &mappings.sources {
+                        println!("Loaded mappings file: {}", source.display());
+                    }
+                    println!(
+                        "{} mods mapped; local overrides file: {}",
+                        mappings.count(),
+                        config.mappings.overrides.display()
+                    );
+                }
+                Err(e) => println!("Failed to read the mappings: {}", e),
+            },
+            MappingsAction::Update => {
+                println!("Downloading mappings from {}...", config.mappings.url);
+                match database::update(config) {
+                    Ok((count, path)) => println!(
+                        "Updated mappings for {} mods, saved to {}.",
+                        count,
+                        path.display()
+                    ),
+                    Err(e) => println!("Failed to update the mappings: {}", e),
                 }
             }
         }
     }
 
+    fn serve(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let (token, generated) = match config.serve.token.clone().or_else(|| {
+            env::var("MIRAMIGRATOR_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty())
+        }) {
+            Some(token) => (token, false),
+            None => match server::generate_token() {
+                Ok(token) => (token, true),
+                Err(e) => {
+                    println!("Failed to generate an access token: {}", e);
+                    return;
+                }
+            },
+        };
+        let api = match ApiServer::bind(token.clone(), config, &providers, &mappings) {
+            Ok(api) => api,
+            Err(e) => {
+                println!("Failed to listen on {}: {}", config.serve.address, e);
+                return;
+            }
+        };
+        println!("HTTP API listening on http://{}/api/", api.address());
+        if generated {
+            println!("Access token for this session: {}", token);
+            println!(
+                "Set [serve] token in {} or MIRAMIGRATOR_TOKEN to keep a fixed token.",
+                CONFIG_FILE_NAME
+            );
+        }
+        println!(
+            "Send the header Authorization: Bearer <token> with every request. Press Ctrl+C to stop."
+        );
+        api.run();
+    }
+
+    fn help(&self, invalid_argument: Option<&str>) {
+        if let Some(arg) = invalid_argument {
+            println!("Unrecognized argument: {}", arg);
+        }
+        println!("Usage: MiraMigrator [command] [options]");
+        println!();
+        println!("Commands:");
+        for (usage, description) in [
+            (
+                "start",
+                "Interactively migrate the mods in the current directory (default)".to_string(),
+            ),
+            (
+                "tui",
+                "Review, adjust and run the migration in a full-screen interface".to_string(),
+            ),
+            (
+                "install --locked",
+                format!(
+                    "Restore the mods in the current directory from {}",
+                    LOCKFILE_NAME
+                ),
+            ),
+            (
+                "cache list|prune|clear",
+                "List, prune or clear the download cache".to_string(),
+            ),
+            (
+                "mappings show|update",
+                "Show or update the mod ID mappings".to_string(),
+            ),
+            (
+                "serve",
+                "Start a local HTTP API to scan, migrate and restore from other programs"
+                    .to_string(),
+            ),
+            ("help", "Show this help".to_string()),
+        ] {
+            println!("  {:<24} {}", usage, description);
+        }
+        println!();
+        println!("Options:");
+        for (usage, description) in [
+            (
+                "-j, --jobs <N>",
+                format!(
+                    "Number of mods migrated at once, overrides {}",
+                    CONFIG_FILE_NAME
+                ),
+            ),
+            (
+                "-i, --interactive",
+                "Choose between candidate versions; choices are saved to the config".to_string(),
+            ),
+            (
+                "-q, --quiet",
+                "Quiet mode, only print mods that need attention and the summary".to_string(),
+            ),
+            (
+                "-v, --verbose",
+                "Verbose mode, also print HTTP requests and retries".to_string(),
+            ),
+            (
+                "--output <FORMAT>",
+                "Output format: text (default) or json-lines (one JSON event per line, for other programs)".to_string(),
+            ),
+            ("-h, --help", "Show this help".to_string()),
+        ] {
+            println!("  {:<24} {}", usage, description);
+        }
+    }
+
+    fn config_error(&self, error: &str) {
+        println!("Failed to read config file {}: {}", CONFIG_FILE_NAME, error);
+    }
+
+    fn log_error(&self, path: &Path, error: &str) {
+        println!("Failed to open log file {}: {}", path.display(), error);
+    }
+
     fn exit(&self) {
         println!("Press any key to exit MiraMigrator...");
-        std::io::stdin()
-            .read_line(&mut String::new())
-            .expect("Failed to read line");
+        let _ = read_line();
     }
 }
@@ -0,0 +1,617 @@
+use super::{Interface, lock_prompt, read_line};
+use crate::VERSION;
+use crate::api::build_providers;
+use crate::api::download;
+use crate::api::provider::{ModProvider, RemoteVersion};
+use crate::cli::args::{CacheAction, MappingsAction};
+use crate::config::{CONFIG_FILE_NAME, Config, save_mod_rules};
+use crate::core::migrate::{
+    Migration, MigrationOutcome, MigrationResult, Plan, ScannedMod, apply_plans, plan_mods,
+};
+use crate::core::search::{Candidate, Picker, SearchQuery};
+use crate::lock::lockfile::{
+    InstallStatus, LOCKFILE_NAME, LockInstaller, Lockfile, remove_unlocked_jars,
+};
+use crate::mapping::database::Mappings;
+use crate::report::report::{Report, ReportEntry, ReportError, Status, report_path};
+use crate::scan::scan;
+use crate::utils::loader::loader_name;
+use crate::utils::version::validate_version;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 同一文件两次下载进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+/// 更新日志摘要的最大字符数
+const CHANGELOG_EXCERPT_CHARS: usize = 200;
+
+/// 上一次提问的 ID
+static PROMPT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 供启动器等程序调用的界面 (`--output json-lines`)
+///
+/// 每个步骤以一行 JSON 输出到标准输出, `event` 字段为事件类型:
+/// `started`, `mod_found`, `backup_finished`, `resolved`, `progress`, `result`,
+/// `installed`, `removed`, `choice_saved`, `summary`, `prompt`, `invalid_command`,
+/// `canceled`, `warning` 与 `error`
+///
+/// 需要回答时输出 `prompt` 事件, 其中 `id` 为提问 ID, `prompt` 为提问类型:
+/// - `minecraft_version`: 回答目标版本字符串
+/// - `confirm`: 回答 `true` 开始迁移, `false` 取消
+/// - `project`: 回答 `candidates` 中的序号 (从 0 开始), `null` 表示都不是
+/// - `version`: 回答 `versions` 中的序号, `null` 表示使用推荐版本 (序号 0)
+///
+/// 标准输入每行一个 JSON 命令:
+/// - `{"command": "answer", "id": <提问 ID>, "value": <回答>}`
+/// - `{"command": "cancel"}`: 取消当前提问, 选择项目或版本时等同于回答 `null`
+///
+/// 命令无效时输出 `invalid_command` 事件, 其中 `id` 为仍在等待回答的提问; 标准输入结束时视为取消
+pub struct JsonLinesInterface;
+
+impl JsonLinesInterface {
+    pub fn new() -> Self {
+        JsonLinesInterface
+    }
+
+    /// 读取下载来源与映射数据, 失败时输出配置错误
+    fn load_sources(&self, config: &Config) -> Option<(Vec<Box<dyn ModProvider>>, Mappings)> {
+        let providers = match build_providers(config) {
+            Ok(providers) => providers,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        let mappings = match Mappings::load(config) {
+            Ok(mappings) => mappings,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        Some((providers, mappings))
+    }
+
+    /// 扫描 Mods 目录中的有效 Mod 并逐个输出, 没有时返回 None
+    fn scan_mods(&self) -> Option<Vec<ScannedMod>> {
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                emit_error(&format!("Failed to read the mods directory: {}", e));
+                return None;
+            }
+        };
+        let jar_files = scan::filter_valid_mods(jar_files);
+        if jar_files.is_empty() {
+            emit_error("No valid mods found in the mods directory");
+            return None;
+        }
+        let scanned_mods: Vec<ScannedMod> = jar_files.iter().map(ScannedMod::read).collect();
+        for (index, scanned) in scanned_mods.iter().enumerate() {
+            emit(
+                "mod_found",
+                json!({
+                    "index": index,
+                    "file_name": scanned.file_name,
+                    "mod_id": scanned.mod_id,
+                    "name": scanned.name,
+                    "version": scanned.version,
+                    "loader": scanned.loader.as_ref().and_then(loader_name),
+                }),
+            );
+        }
+        Some(scanned_mods)
+    }
+
+    /// 将下载的文件移回 Mods 目录, 写入锁文件与迁移报告, 输出统计
+    fn finish(
+        &self,
+        results: &[MigrationResult],
+        version: &str,
+        cache_path: &Path,
+        start_time: Instant,
+        config: &Config,
+    ) {
+        let report = Report::new(version, results, start_time.elapsed());
+        log::info!(
+            "Migration to Minecraft {} finished: {} migrated, {} failed, {} ignored",
+            report.minecraft_version,
+            report.count(Status::Migrated),
+            report.failed(),
+            report.count(Status::Ignored)
+        );
+        let mut lockfile_path = None;
+        if let Err(e) = scan::move_files_from_cache_to_current_dir(cache_path) {
+            log::error!("Failed to move the downloaded mods: {}", e);
+            emit_error(&format!("Failed to move the downloaded mods: {}", e));
+        } else {
+            let mut lockfile = Lockfile::new(version);
+            lockfile.mods = results
+                .iter()
+                .filter_map(|r| match &r.outcome {
+                    MigrationOutcome::Migrated { locked, .. } => Some(locked.as_ref().clone()),
+                    _ => None,
+                })
+                .collect();
+            match lockfile.save(Path::new(LOCKFILE_NAME)) {
+                Ok(()) => lockfile_path = Some(LOCKFILE_NAME),
+                Err(e) => emit_warning(&format!("Failed to write the lockfile: {}", e)),
+            }
+        }
+        let mut reports = Vec::new();
+        for format in &config.report.formats {
+            let path = report_path(&config.report.file, *format);
+            match report.save(*format, &path) {
+                Ok(()) => reports.push(path.display().to_string()),
+                Err(e) => emit_warning(&format!(
+                    "Failed to write the migration report {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+        emit(
+            "summary",
+            json!({
+                "minecraft_version": report.minecraft_version,
+                "total": report.entries.len(),
+                "migrated": report.count(Status::Migrated),
+                "failed": report.failed(),
+                "ignored": report.count(Status::Ignored),
+                "elapsed_ms": report.elapsed.as_millis() as u64,
+                "lockfile": lockfile_path,
+                "reports": reports,
+            }),
+        );
+    }
+
+    /// 将用户的选择保存到配置文件, 之后的迁移不再询问
+    fn save_choice(&self, mod_id: &str, provider: &dyn ModProvider, project_id: &str) {
+        let key = format!("projects.{}", provider.id());
+        let rules = &[(key.as_str(), project_id)];
+        match save_mod_rules(Path::new(CONFIG_FILE_NAME), mod_id, rules) {
+            Ok(()) => emit(
+                "choice_saved",
+                json!({
+                    "mod_id": mod_id,
+                    "file": CONFIG_FILE_NAME,
+                    "rules": rules
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), json!(value)))
+                        .collect::<serde_json::Map<_, _>>(),
+                }),
+            ),
+            Err(e) => emit_warning(&format!(
+                "Failed to save the choice to {}: {}",
+                CONFIG_FILE_NAME, e
+            )),
+        }
+    }
+}
+
+impl Picker for JsonLinesInterface {
+    fn pick_project(
+        &self,
+        query: &SearchQuery,
+        provider: &dyn ModProvider,
+        candidates: &[Candidate],
+    ) -> Option<usize> {
+        let candidates_json: Vec<Value> = candidates
+            .iter()
+            .map(|candidate| {
+                let project = &candidate.project;
+                json!({
+                    "id": project.id,
+                    "slug": project.slug,
+                    "title": project.title,
+                    "authors": project.authors,
+                    "downloads": project.downloads,
+                    "score": candidate.score,
+                })
+            })
+            .collect();
+        let index = ask(
+            "project",
+            json!({
+                "mod_id": query.mod_id,
+                "name": query.display_name(),
+                "provider": provider.id(),
+                "candidates": candidates_json,
+            }),
+            |value| choice(value, candidates.len()),
+        )
+        .flatten()?;
+        let project = &candidates[index].project;
+        self.save_choice(query.mod_id, provider, &project.id);
+        Some(index)
+    }
+
+    fn pick_version(
+        &self,
+        mod_id: &str,
+        provider: &dyn ModProvider,
+        versions: &[RemoteVersion],
+    ) -> Option<usize> {
+        let versions_json: Vec<Value> = versions
+            .iter()
+            .map(|version| {
+                json!({
+                    "version_id": version.version_id,
+                    "version_number": version.version_number,
+                    "channel": version.channel.name(),
+                    "published": version.published_date(),
+                    "downloads": version.downloads,
+                    "changelog": version.changelog_excerpt(CHANGELOG_EXCERPT_CHARS),
+                })
+            })
+            .collect();
+        let index = ask(
+            "version",
+            json!({
+                "mod_id": mod_id,
+                "provider": provider.id(),
+                "versions": versions_json,
+            }),
+            |value| choice(value, versions.len()),
+        )
+        .flatten()?;
+        // 只记住项目, 版本仅用于本次迁移; 固定版本后迁移到其他 Minecraft 版本时将无法匹配
+        let version = &versions[index];
+        self.save_choice(mod_id, provider, &version.project_id);
+        Some(index)
+    }
+}
+
+impl Interface for JsonLinesInterface {
+    fn init(&self) -> bool {
+        if let Err(e) = scan::create_backup_folder() {
+            emit_error(&format!("Failed to create the backup folder: {}", e));
+            return false;
+        }
+        true
+    }
+
+    fn start(&self, config: &Config) {
+        emit("started", json!({ "command": "start", "version": VERSION }));
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let Some(version) = ask("minecraft_version", json!({}), |value| {
+            value
+                .as_str()
+                .map(str::trim)
+                .filter(|version| validate_version(version))
+                .map(str::to_string)
+        }) else {
+            emit("canceled", json!({}));
+            return;
+        };
+        let Some(scanned_mods) = self.scan_mods() else {
+            return;
+        };
+        let confirmed = ask(
+            "confirm",
+            json!({ "mods": scanned_mods.len() }),
+            Value::as_bool,
+        );
+        if confirmed != Some(true) {
+            emit("canceled", json!({}));
+            return;
+        }
+        // 备份 Mod 文件
+        let start_time = Instant::now();
+        for scanned in &scanned_mods {
+            if let Err(e) = scan::backup_file(&scanned.path) {
+                emit_error(&format!("Failed to back up {}: {}", scanned.file_name, e));
+                emit("canceled", json!({}));
+                return;
+            }
+        }
+        emit(
+            "backup_finished",
+            json!({ "elapsed_ms": start_time.elapsed().as_millis() as u64 }),
+        );
+
+        // 先为所有 Mod 选择版本, 再统一下载
+        let cache_path = Path::new("cache");
+        let start_time = Instant::now();
+        let migration = Migration {
+            mc_version: &version,
+            cache_dir: cache_path,
+            providers: &providers,
+            mappings: &mappings,
+            config,
+            picker: Some(self),
+        };
+        let mut plans = Vec::with_capacity(scanned_mods.len());
+        plan_mods(&scanned_mods, &migration, |index, plan| {
+            emit("resolved", resolved_event(index, &plan, &providers));
+            plans.push(plan);
+        });
+        let mut results = Vec::with_capacity(plans.len());
+        apply_plans(
+            scanned_mods.into_iter().zip(plans).collect(),
+            &migration,
+            progress_events(),
+            |index, result| {
+                let mut event = ReportEntry::new(&result).to_json();
+                event["index"] = json!(index);
+                emit("result", event);
+                results.push(result);
+            },
+        );
+        self.finish(&results, &version, cache_path, start_time, config);
+    }
+
+    fn tui(&self, _config: &Config) {
+        emit_error("The full-screen interface is not available with --output json-lines");
+    }
+
+    fn install(&self, config: &Config, locked: bool) {
+        emit(
+            "started",
+            json!({ "command": "install", "version": VERSION }),
+        );
+        if !locked {
+            emit_error("Only installing from the lockfile is supported, use install --locked");
+            return;
+        }
+        let lockfile = match Lockfile::load(Path::new(LOCKFILE_NAME)) {
+            Ok(lockfile) => lockfile,
+            Err(e) => {
+                emit_error(&format!("Failed to read {}: {}", LOCKFILE_NAME, e));
+                return;
+            }
+        };
+        // 备份 Mod 文件
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                emit_error(&format!("Failed to read the mods directory: {}", e));
+                return;
+            }
+        };
+        for jar_file in jar_files {
+            if let Err(e) = scan::backup_file(&jar_file.path()) {
+                emit_error(&format!(
+                    "Failed to back up {}: {}",
+                    jar_file.file_name().to_string_lossy(),
+                    e
+                ));
+                emit("canceled", json!({}));
+                return;
+            }
+        }
+        let installer = match LockInstaller::new(config) {
+            Ok(installer) => installer,
+            Err(e) => {
+                emit_error(&format!("Failed to create the HTTP client: {}", e));
+                return;
+            }
+        };
+        let start_time = Instant::now();
+        let mut failed = 0;
+        let progress = progress_events();
+        for (index, locked_mod) in lockfile.mods.iter().enumerate() {
+            let progress = progress.clone();
+            let status = download::with_progress(
+                move |done, total| progress(index, done, total),
+                || installer.install(locked_mod, Path::new("."), Path::new("cache")),
+            );
+            let (status, error) = match status {
+                Ok(InstallStatus::Unchanged) => ("unchanged", None),
+                Ok(InstallStatus::Downloaded) => ("downloaded", None),
+                Err(e) => {
+                    failed += 1;
+                    ("failed", Some(ReportError::new(&locked_mod.provider, &e)))
+                }
+            };
+            emit(
+                "installed",
+                json!({
+                    "index": index,
+                    "mod_id": locked_mod.mod_id,
+                    "file_name": locked_mod.file_name,
+                    "provider": locked_mod.provider,
+                    "version_number": locked_mod.version_number,
+                    "status": status,
+                    "error": error.as_ref().map(ReportError::to_json),
+                }),
+            );
+        }
+        if failed == 0 {
+            match remove_unlocked_jars(&lockfile, Path::new(".")) {
+                Ok(removed) => {
+                    for path in removed {
+                        emit(
+                            "removed",
+                            json!({
+                                "file_name": path.file_name().unwrap_or_default().to_string_lossy(),
+                            }),
+                        );
+                    }
+                }
+                Err(e) => {
+                    emit_warning(&format!("Failed to remove mods not in the lockfile: {}", e))
+                }
+            }
+        } else {
+            emit_warning("Some mods failed to install, so mods not in the lockfile were kept.");
+        }
+        emit(
+            "summary",
+            json!({
+                "minecraft_version": lockfile.minecraft_version,
+                "total": lockfile.mods.len(),
+                "installed": lockfile.mods.len() - failed,
+                "failed": failed,
+                "elapsed_ms": start_time.elapsed().as_millis() as u64,
+            }),
+        );
+    }
+
+    fn cache(&self, _config: &Config, _action: CacheAction) {
+        emit_error("The cache command is not available with --output json-lines");
+    }
+
+    fn mappings(&self, _config: &Config, _action: MappingsAction) {
+        emit_error("The mappings command is not available with --output json-lines");
+    }
+
+    fn serve(&self, _config: &Config) {
+        emit_error("The serve command is not available with --output json-lines");
+    }
+
+    fn help(&self, invalid_argument: Option<&str>) {
+        match invalid_argument {
+            Some(arg) => emit_error(&format!("Unrecognized argument: {}", arg)),
+            None => emit_error("Help is not available with --output json-lines"),
+        }
+    }
+
+    fn config_error(&self, error: &str) {
+        emit_error(&format!("Failed to read {}: {}", CONFIG_FILE_NAME, error));
+    }
+
+    fn log_error(&self, path: &Path, error: &str) {
+        emit_warning(&format!(
+            "Failed to open the log file {}: {}",
+            path.display(),
+            error
+        ));
+    }
+
+    fn exit(&self) {}
+}
+
+/// 输出一行事件, `fields` 为事件的其他字段
+fn emit(event: &str, mut fields: Value) {
+    if let Some(object) = fields.as_object_mut() {
+        object.insert("event".to_string(), Value::from(event));
+    }
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", fields);
+    let _ = stdout.flush();
+}
+
+fn emit_error(message: &str) {
+    emit("error", json!({ "message": message }));
+}
+
+fn emit_warning(message: &str) {
+    emit("warning", json!({ "message": message }));
+}
+
+/// 输出 `prompt` 事件并等待对应的回答, `parse` 返回 None 时回答不合法, 输出 `invalid_command` 后继续等待
+/// 取消或标准输入结束时返回 None
+fn ask<T>(prompt: &str, mut fields: Value, parse: impl Fn(&Value) -> Option<T>) -> Option<T> {
+    let _prompt = lock_prompt();
+    let id = PROMPT_ID.fetch_add(1, Ordering::Relaxed) + 1;
+    fields["id"] = json!(id);
+    fields["prompt"] = json!(prompt);
+    emit("prompt", fields);
+    loop {
+        let line = read_line()?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match serde_json::from_str::<Value>(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                emit_invalid(id, &format!("Invalid JSON: {}", e));
+                continue;
+            }
+        };
+        match command.get("command").and_then(Value::as_str) {
+            Some("cancel") => return None,
+            Some("answer") if command.get("id").and_then(Value::as_u64) == Some(id) => {
+                match parse(command.get("value").unwrap_or(&Value::Null)) {
+                    Some(value) => return Some(value),
+                    None => emit_invalid(id, "Invalid answer"),
+                }
+            }
+            Some("answer") => emit_invalid(id, "No pending prompt with this ID"),
+            _ => emit_invalid(id, "Unknown command, expected \"answer\" or \"cancel\""),
+        }
+    }
+}
+
+/// 命令无效, `id` 为仍在等待回答的提问
+fn emit_invalid(id: u64, message: &str) {
+    emit("invalid_command", json!({ "id": id, "message": message }));
+}
+
+/// 解析选择的序号, `null` 表示不选择
+fn choice(value: &Value, count: usize) -> Option<Option<usize>> {
+    match value {
+        Value::Null => Some(None),
+        value => value
+            .as_u64()
+            .map(|index| index as usize)
+            .filter(|index| *index < count)
+            .map(Some),
+    }
+}
+
+/// 版本选择结果的事件
+fn resolved_event(index: usize, plan: &Plan, providers: &[Box<dyn ModProvider>]) -> Value {
+    let (status, errors) = match plan {
+        Plan::Ready(planned) => {
+            let version = &planned.resolved.version;
+            let errors: Vec<Value> = planned
+                .errors
+                .iter()
+                .map(|e| ReportError::new(&e.provider, &e.error).to_json())
+                .collect();
+            return json!({
+                "index": index,
+                "status": "ready",
+                "provider": providers[planned.provider].name(),
+                "version_id": version.version_id,
+                "version_number": version.version_number,
+                "file_name": version.file_name,
+                "reason": planned.resolved.reason,
+                "compatible_with": planned.resolved.compatible_with,
+                "errors": errors,
+            });
+        }
+        Plan::Done(outcome @ MigrationOutcome::Failed { errors }) => {
+            (Status::from_outcome(outcome), errors.as_slice())
+        }
+        Plan::Done(outcome) => (Status::from_outcome(outcome), &[][..]),
+    };
+    json!({
+        "index": index,
+        "status": status.name(),
+        "errors": errors
+            .iter()
+            .map(|e| ReportError::new(&e.provider, &e.error).to_json())
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// 下载进度的回调, 同一文件按间隔输出 `progress` 事件, 下载完成时总会输出
+fn progress_events() -> impl Fn(usize, u64, Option<u64>) + Clone + Send + Sync + 'static {
+    let last = Arc::new(Mutex::new(BTreeMap::<usize, Instant>::new()));
+    move |index, done, total| {
+        let complete = total.is_some_and(|total| done >= total);
+        let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+        if !complete
+            && last
+                .get(&index)
+                .is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        last.insert(index, Instant::now());
+        emit(
+            "progress",
+            json!({ "index": index, "downloaded": done, "total": total }),
+        );
+    }
+}
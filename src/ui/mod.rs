@@ -2,9 +2,116 @@
 pub mod zh_cn;
 // en_US
 pub mod en_us;
+// 全屏界面
+pub mod tui;
+// 下载进度条
+pub mod progress;
+// JSON 事件流
+pub mod json_lines;
+
+use crate::cli::args::{CacheAction, MappingsAction};
+use crate::config::Config;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// 迁移线程可能同时需要用户选择, 同一时间只允许一个提问
+static PROMPT: Mutex<()> = Mutex::new(());
+
+/// 获取提问锁, 持有期间其他线程的提问将等待
+fn lock_prompt() -> MutexGuard<'static, ()> {
+    PROMPT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 读取一行输入, 输入结束或读取失败时返回 None
+fn read_line() -> Option<String> {
+    let mut input = String::new();
+    match std::io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input),
+    }
+}
+
+/// 以 `key = "value", ...` 的形式显示保存到配置文件的规则
+fn format_rules(rules: &[(&str, &str)]) -> String {
+    rules
+        .iter()
+        .map(|(key, value)| format!("{} = {:?}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 读取 1 ~ `count` 的序号, 返回从 0 开始的序号; 输入 0, 直接回车或输入结束时返回 None
+/// 输入不合法时输出 `invalid` 并重新读取
+fn read_choice(count: usize, invalid: &str) -> Option<usize> {
+    read_choice_from(&mut std::io::stdin().lock(), count, invalid)
+}
+
+fn read_choice_from(reader: &mut impl BufRead, count: usize, invalid: &str) -> Option<usize> {
+    let mut input = String::new();
+    loop {
+        input.clear();
+        match reader.read_line(&mut input) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+        match input.trim() {
+            "" | "0" => return None,
+            choice => match choice.parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => return Some(n - 1),
+                _ => println!("{}", invalid),
+            },
+        }
+    }
+}
 
 pub trait Interface {
-    fn init(&self);
-    fn start(&self);
+    /// 创建备份与缓存目录, 失败时返回 false
+    fn init(&self) -> bool;
+    fn start(&self, config: &Config);
+    fn tui(&self, config: &Config);
+    fn install(&self, config: &Config, locked: bool);
+    fn cache(&self, config: &Config, action: CacheAction);
+    fn mappings(&self, config: &Config, action: MappingsAction);
+    /// 启动本地 HTTP API, 直到进程退出
+    fn serve(&self, config: &Config);
+    fn help(&self, invalid_argument: Option<&str>);
+    fn config_error(&self, error: &str);
+    fn log_error(&self, path: &Path, error: &str);
     fn exit(&self);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::read_choice_from;
+
+    fn choose(input: &str, count: usize) -> Option<usize> {
+        read_choice_from(&mut input.as_bytes(), count, "invalid")
+    }
+
+    #[test]
+    fn read_choice_accepts_indices_in_range() {
+        assert_eq!(choose("1\n", 3), Some(0));
+        assert_eq!(choose(" 3 \n", 3), Some(2));
+        assert_eq!(choose("0\n", 3), None);
+        assert_eq!(choose("\n", 3), None);
+    }
+
+    #[test]
+    fn read_choice_rejects_zero_in_other_forms() {
+        assert_eq!(choose("00\n2\n", 3), Some(1));
+        assert_eq!(choose("+0\n", 3), None);
+    }
+
+    #[test]
+    fn read_choice_rejects_out_of_range() {
+        assert_eq!(choose("4\n1\n", 3), Some(0));
+        assert_eq!(choose("abc\n-1\n", 3), None);
+    }
+
+    #[test]
+    fn read_choice_stops_at_end_of_input() {
+        assert_eq!(choose("", 3), None);
+        assert_eq!(choose("4", 3), None);
+    }
+}
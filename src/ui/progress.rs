@@ -0,0 +1,170 @@
+use crate::utils::format::format_size;
+use crate::utils::output;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 进度条刷新间隔
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// 进度条宽度 (字符)
+const BAR_WIDTH: usize = 20;
+/// 文件名的最大显示宽度 (字符)
+const NAME_WIDTH: usize = 32;
+
+/// 下载进度条: 每个正在下载的文件一行, 最后一行为总进度
+/// 标准输出不是终端或处于安静模式时不显示, 只输出各文件的结果
+pub struct ProgressBars {
+    /// 各文件的显示名称, 与迁移序号对应
+    names: Vec<String>,
+    /// 总进度一行的标题
+    label: &'static str,
+    enabled: bool,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// 正在下载的文件, 以序号为键
+    active: BTreeMap<usize, FileProgress>,
+    /// 已完成的文件数量
+    finished: usize,
+    /// 已完成的文件的下载字节数
+    finished_bytes: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+}
+
+struct FileProgress {
+    done: u64,
+    total: Option<u64>,
+    started: Instant,
+}
+
+impl ProgressBars {
+    pub fn new(names: Vec<String>, label: &'static str) -> Self {
+        ProgressBars {
+            names,
+            label,
+            enabled: output::progress_enabled(),
+            state: Mutex::new(State {
+                active: BTreeMap::new(),
+                finished: 0,
+                finished_bytes: 0,
+                started: Instant::now(),
+                last_draw: None,
+            }),
+        }
+    }
+
+    /// 更新第 `index` 个文件的下载进度, 按刷新间隔重绘
+    pub fn update(&self, index: usize, done: u64, total: Option<u64>) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.lock();
+        let file = state.active.entry(index).or_insert_with(|| FileProgress {
+            done: 0,
+            total,
+            started: Instant::now(),
+        });
+        file.done = done;
+        file.total = total;
+        let complete = total.is_some_and(|total| done >= total);
+        if complete
+            || state
+                .last_draw
+                .is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw(&mut state);
+        }
+    }
+
+    /// 第 `index` 个文件处理完毕: 移除其进度条, 执行 `report` (输出结果) 后重绘
+    pub fn finish(&self, index: usize, report: impl FnOnce()) {
+        let mut state = self.lock();
+        if let Some(file) = state.active.remove(&index) {
+            state.finished_bytes += file.done;
+        }
+        state.finished += 1;
+        output::suspend(report);
+        if self.enabled && state.finished < self.names.len() {
+            self.draw(&mut state);
+        }
+    }
+
+    /// 清除进度条, 之后的输出不再被覆盖
+    pub fn clear(&self) {
+        if self.enabled {
+            output::clear_bars();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn draw(&self, state: &mut State) {
+        let mut lines: Vec<String> = state
+            .active
+            .iter()
+            .map(|(index, file)| {
+                let name = self.names.get(*index).map(String::as_str).unwrap_or("?");
+                format!(
+                    "  {:<width$} {} {}  {}",
+                    truncate(name, NAME_WIDTH),
+                    bar(file.total.map(|total| ratio(file.done, total))),
+                    match file.total {
+                        Some(total) =>
+                            format!("{} / {}", format_size(file.done), format_size(total)),
+                        None => format_size(file.done),
+                    },
+                    throughput(file.done, file.started.elapsed()),
+                    width = NAME_WIDTH
+                )
+            })
+            .collect();
+        let total = self.names.len();
+        let downloaded = state.finished_bytes + state.active.values().map(|f| f.done).sum::<u64>();
+        lines.push(format!(
+            "{} {} {}/{}  {}  {}",
+            self.label,
+            bar(Some(ratio(state.finished as u64, total as u64))),
+            state.finished,
+            total,
+            format_size(downloaded),
+            throughput(downloaded, state.started.elapsed())
+        ));
+        output::draw_bars(&lines);
+        state.last_draw = Some(Instant::now());
+    }
+}
+
+fn ratio(done: u64, total: u64) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        (done as f64 / total as f64).min(1.0)
+    }
+}
+
+/// 进度条, 总大小未知时显示为空
+fn bar(ratio: Option<f64>) -> String {
+    let filled = ratio.map_or(0, |r| (r * BAR_WIDTH as f64).round() as usize);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+fn throughput(bytes: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    if secs < 0.1 {
+        return String::new();
+    }
+    format!("{}/s", format_size((bytes as f64 / secs) as u64))
+}
+
+/// 截断过长的名称, 以 `...` 结尾
+fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let kept: String = name.chars().take(width - 3).collect();
+    format!("{}...", kept)
+}
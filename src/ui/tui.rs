@@ -0,0 +1,612 @@
+use crate::api::provider::{ModProvider, RemoteVersion};
+use crate::config::{CONFIG_FILE_NAME, Config, save_mod_rules};
+use crate::core::migrate::{
+    Migration, MigrationOutcome, MigrationResult, Plan, ScannedMod, apply_plans, plan_mod,
+    plan_mods,
+};
+use crate::error::Error;
+use crate::mapping::database::Mappings;
+use crate::scan::scan;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Block, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap,
+};
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, Scope};
+use std::time::Duration;
+
+/// 界面刷新间隔
+const TICK: Duration = Duration::from_millis(100);
+
+/// 全屏界面中使用的文字, 由各语言的界面提供
+pub struct TuiText {
+    pub title: &'static str,
+    /// 列标题: 文件, 加载器, 当前版本, 目标版本, 来源, 状态
+    pub columns: [&'static str; 6],
+    pub resolving: &'static str,
+    pub ready: &'static str,
+    pub skipped: &'static str,
+    pub ignored: &'static str,
+    pub failed: &'static str,
+    pub unrecognized: &'static str,
+    pub unknown_loader: &'static str,
+    pub waiting: &'static str,
+    pub downloading: &'static str,
+    pub migrated: &'static str,
+    pub details: &'static str,
+    pub reason: &'static str,
+    /// 后接实际标注的 Minecraft 版本
+    pub compatible: &'static str,
+    pub pin_title: &'static str,
+    /// 后接版本号
+    pub pinned: &'static str,
+    pub save_failed: &'static str,
+    pub no_candidates: &'static str,
+    pub still_resolving: &'static str,
+    pub backup_failed: &'static str,
+    pub help_review: &'static str,
+    pub help_pin: &'static str,
+    pub help_applying: &'static str,
+    pub help_done: &'static str,
+    /// 本地化的错误提示
+    pub error: fn(&Error) -> String,
+}
+
+/// 运行全屏界面所需的参数
+pub struct TuiContext<'a> {
+    pub mc_version: &'a str,
+    pub cache_dir: &'a Path,
+    pub providers: &'a [Box<dyn ModProvider>],
+    pub mappings: &'a Mappings,
+    pub config: &'a Config,
+    pub text: &'a TuiText,
+}
+
+/// 单个 Mod 当前的状态
+enum ModState {
+    Resolving,
+    Planned(Plan),
+    /// 已开始迁移, 等待处理
+    Waiting,
+    Downloading {
+        done: u64,
+        total: Option<u64>,
+    },
+    /// 迁移结果在 [`App::results`] 中的序号
+    Finished(usize),
+}
+
+struct ModRow {
+    scanned: ScannedMod,
+    state: ModState,
+    /// 用户选择跳过, 迁移时保留原文件
+    skipped: bool,
+}
+
+/// 当前的操作阶段
+enum Mode {
+    /// 查看与调整迁移计划
+    Review,
+    /// 为选中的 Mod 选择固定的版本
+    Pin(ListState),
+    /// 正在下载
+    Applying,
+    /// 迁移完成
+    Done,
+}
+
+/// 后台任务发给界面的消息
+enum Message {
+    Planned(usize, Plan),
+    Progress(usize, u64, Option<u64>),
+    Applied(usize, MigrationResult),
+    Finished,
+}
+
+struct App<'a> {
+    context: &'a TuiContext<'a>,
+    /// 在界面中固定版本后更新的配置
+    config: Config,
+    rows: Vec<ModRow>,
+    table: TableState,
+    mode: Mode,
+    /// 状态栏中显示的消息
+    status: Option<String>,
+    results: Vec<MigrationResult>,
+}
+
+/// 运行全屏界面: 解析所有 Mod 的迁移计划, 由用户调整后执行迁移
+/// 返回各 Mod 的迁移结果, 用户未执行迁移就退出时返回 None
+pub fn run(
+    context: &TuiContext,
+    mods: Vec<ScannedMod>,
+) -> io::Result<Option<Vec<MigrationResult>>> {
+    let mut terminal = ratatui::init();
+    let result = thread::scope(|scope| {
+        let mut app = App {
+            context,
+            config: context.config.clone(),
+            rows: mods
+                .into_iter()
+                .map(|scanned| ModRow {
+                    scanned,
+                    state: ModState::Resolving,
+                    skipped: false,
+                })
+                .collect(),
+            table: TableState::default().with_selected(Some(0)),
+            mode: Mode::Review,
+            status: None,
+            results: Vec::new(),
+        };
+        let (tx, rx) = mpsc::channel();
+        app.plan_all(scope, &tx);
+        app.event_loop(&mut terminal, scope, &tx, &rx)
+    });
+    ratatui::restore();
+    result
+}
+
+/// 后台任务使用的迁移参数, `config` 包含界面中固定的版本
+fn migration<'m>(context: &'m TuiContext, config: &'m Config) -> Migration<'m> {
+    Migration {
+        mc_version: context.mc_version,
+        cache_dir: context.cache_dir,
+        providers: context.providers,
+        mappings: context.mappings,
+        config,
+        // 全屏界面中无法在终端中提问
+        picker: None,
+    }
+}
+
+impl<'a> App<'a> {
+    /// 在后台解析所有 Mod 的迁移计划
+    fn plan_all<'s>(&self, scope: &'s Scope<'s, '_>, tx: &Sender<Message>)
+    where
+        'a: 's,
+    {
+        let mods: Vec<ScannedMod> = self.rows.iter().map(|row| row.scanned.clone()).collect();
+        let config = self.config.clone();
+        let context = self.context;
+        let tx = tx.clone();
+        scope.spawn(move || {
+            let migration = migration(context, &config);
+            plan_mods(&mods, &migration, |index, plan| {
+                let _ = tx.send(Message::Planned(index, plan));
+            });
+        });
+    }
+
+    /// 在后台重新解析选中的 Mod
+    fn replan<'s>(&mut self, scope: &'s Scope<'s, '_>, tx: &Sender<Message>, index: usize)
+    where
+        'a: 's,
+    {
+        let row = &mut self.rows[index];
+        row.state = ModState::Resolving;
+        let scanned = row.scanned.clone();
+        let config = self.config.clone();
+        let context = self.context;
+        let tx = tx.clone();
+        scope.spawn(move || {
+            let migration = migration(context, &config);
+            let _ = tx.send(Message::Planned(index, plan_mod(&scanned, &migration)));
+        });
+    }
+
+    fn event_loop<'s>(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        scope: &'s Scope<'s, '_>,
+        tx: &Sender<Message>,
+        rx: &Receiver<Message>,
+    ) -> io::Result<Option<Vec<MigrationResult>>>
+    where
+        'a: 's,
+    {
+        loop {
+            while let Ok(message) = rx.try_recv() {
+                self.receive(message);
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match &mut self.mode {
+                Mode::Review => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                    KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+                    KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+                    KeyCode::Char('s') => {
+                        if let Some(row) = self.selected_row() {
+                            row.skipped = !row.skipped;
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        if let Some(index) = self.selected() {
+                            self.replan(scope, tx, index);
+                        }
+                    }
+                    KeyCode::Char('p') => self.open_pin(),
+                    KeyCode::Enter | KeyCode::Char('m') => self.apply(scope, tx),
+                    _ => {}
+                },
+                Mode::Pin(list) => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Review,
+                    KeyCode::Up | KeyCode::Char('k') => list.select_previous(),
+                    KeyCode::Down | KeyCode::Char('j') => list.select_next(),
+                    KeyCode::Enter => {
+                        let choice = list.selected();
+                        self.mode = Mode::Review;
+                        if let Some(choice) = choice {
+                            self.pin(scope, tx, choice);
+                        }
+                    }
+                    _ => {}
+                },
+                Mode::Applying => {}
+                Mode::Done => return Ok(Some(mem::take(&mut self.results))),
+            }
+        }
+    }
+
+    fn receive(&mut self, message: Message) {
+        match message {
+            Message::Planned(index, plan) => self.rows[index].state = ModState::Planned(plan),
+            Message::Progress(index, done, total) => {
+                self.rows[index].state = ModState::Downloading { done, total };
+            }
+            Message::Applied(index, result) => {
+                self.rows[index].state = ModState::Finished(self.results.len());
+                self.results.push(result);
+            }
+            Message::Finished => self.mode = Mode::Done,
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.table.selected().filter(|i| *i < self.rows.len())
+    }
+
+    fn selected_row(&mut self) -> Option<&mut ModRow> {
+        let index = self.selected()?;
+        self.rows.get_mut(index)
+    }
+
+    /// 选中的 Mod 可固定的版本
+    fn candidates(&self) -> &[RemoteVersion] {
+        match self.selected().map(|i| &self.rows[i].state) {
+            Some(ModState::Planned(Plan::Ready(planned))) => &planned.candidates,
+            _ => &[],
+        }
+    }
+
+    fn open_pin(&mut self) {
+        if self.candidates().is_empty() {
+            self.status = Some(self.context.text.no_candidates.to_string());
+            return;
+        }
+        self.mode = Mode::Pin(ListState::default().with_selected(Some(0)));
+    }
+
+    /// 固定选中的版本, 保存到配置文件并重新解析
+    fn pin<'s>(&mut self, scope: &'s Scope<'s, '_>, tx: &Sender<Message>, choice: usize)
+    where
+        'a: 's,
+    {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let Some(version) = self
+            .candidates()
+            .get(choice)
+            .map(|v| v.version_number.clone())
+        else {
+            return;
+        };
+        let Some(mod_id) = self.rows[index].scanned.mod_id.clone() else {
+            return;
+        };
+        let text = self.context.text;
+        self.status = Some(
+            match save_mod_rules(Path::new(CONFIG_FILE_NAME), &mod_id, &[("pin", &version)]) {
+                Ok(()) => format!("{} {}", text.pinned, version),
+                Err(e) => format!("{} {}", text.save_failed, e),
+            },
+        );
+        self.config.mods.entry(mod_id).or_default().pin = Some(version);
+        self.replan(scope, tx, index);
+    }
+
+    /// 备份原文件并在后台执行迁移计划, 跳过的 Mod 保留原文件
+    fn apply<'s>(&mut self, scope: &'s Scope<'s, '_>, tx: &Sender<Message>)
+    where
+        'a: 's,
+    {
+        let text = self.context.text;
+        if self
+            .rows
+            .iter()
+            .any(|row| matches!(row.state, ModState::Resolving))
+        {
+            self.status = Some(text.still_resolving.to_string());
+            return;
+        }
+        for row in self.rows.iter().filter(|row| !row.skipped) {
+            if let Err(e) = scan::backup_file(&row.scanned.path) {
+                self.status = Some(format!("{} {}", text.backup_failed, e));
+                return;
+            }
+        }
+        let mut plans = Vec::new();
+        // 计划与行一一对应, 序号相同
+        for row in &mut self.rows {
+            let plan = match mem::replace(&mut row.state, ModState::Waiting) {
+                ModState::Planned(plan) if !row.skipped => plan,
+                _ => Plan::Done(MigrationOutcome::Ignored),
+            };
+            plans.push((row.scanned.clone(), plan));
+        }
+        self.mode = Mode::Applying;
+        self.status = None;
+        let config = self.config.clone();
+        let context = self.context;
+        let tx = tx.clone();
+        scope.spawn(move || {
+            let migration = migration(context, &config);
+            let progress = {
+                let tx = tx.clone();
+                move |index, done, total| {
+                    let _ = tx.send(Message::Progress(index, done, total));
+                }
+            };
+            apply_plans(plans, &migration, progress, |index, result| {
+                let _ = tx.send(Message::Applied(index, result));
+            });
+            let _ = tx.send(Message::Finished);
+        });
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let text = self.context.text;
+        let [header, body, details, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        // 标题与整体进度
+        let finished = self
+            .rows
+            .iter()
+            .filter(|row| {
+                !matches!(
+                    row.state,
+                    ModState::Resolving | ModState::Waiting | ModState::Downloading { .. }
+                )
+            })
+            .count();
+        let title = format!(
+            "{} -> Minecraft {}  {}/{}",
+            text.title,
+            self.context.mc_version,
+            finished,
+            self.rows.len()
+        );
+        frame.render_widget(
+            Gauge::default()
+                .label(title)
+                .gauge_style(Style::new().fg(Color::Blue))
+                .ratio(finished as f64 / self.rows.len().max(1) as f64),
+            header,
+        );
+
+        let rows: Vec<Row> = self.rows.iter().map(|row| self.row(row)).collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(3),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Fill(2),
+            ],
+        )
+        .header(Row::new(text.columns).bold())
+        .block(Block::bordered())
+        .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, body, &mut self.table);
+
+        let lines = self
+            .selected()
+            .map(|i| self.details(&self.rows[i]))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().title(text.details))
+                .wrap(Wrap { trim: true }),
+            details,
+        );
+
+        let help = match (&self.mode, &self.status) {
+            (Mode::Review, Some(status)) => status.as_str(),
+            (Mode::Review, None) => text.help_review,
+            (Mode::Pin(_), _) => text.help_pin,
+            (Mode::Applying, _) => text.help_applying,
+            (Mode::Done, _) => text.help_done,
+        };
+        frame.render_widget(Line::from(help).dim(), footer);
+
+        if let Mode::Pin(list) = &mut self.mode {
+            let items: Vec<ListItem> = match self.table.selected().map(|i| &self.rows[i].state) {
+                Some(ModState::Planned(Plan::Ready(planned))) => planned
+                    .candidates
+                    .iter()
+                    .map(|v| {
+                        ListItem::new(format!(
+                            "{}  [{}]  {}  {}",
+                            v.version_number,
+                            v.channel.name(),
+                            v.published_date(),
+                            v.downloads.map_or("?".to_string(), |n| n.to_string())
+                        ))
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let area = popup(frame.area(), 60, items.len() as u16 + 2);
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(Block::bordered().title(text.pin_title))
+                    .highlight_style(Style::new().reversed()),
+                area,
+                list,
+            );
+        }
+    }
+
+    fn row(&self, row: &ModRow) -> Row<'static> {
+        let text = self.context.text;
+        let scanned = &row.scanned;
+        let (target, provider, status) = match &row.state {
+            _ if row.skipped => (String::new(), String::new(), text.skipped.to_string()),
+            ModState::Resolving => (String::new(), String::new(), text.resolving.to_string()),
+            ModState::Waiting => (String::new(), String::new(), text.waiting.to_string()),
+            ModState::Planned(Plan::Ready(planned)) => (
+                planned.resolved.version.version_number.clone(),
+                self.context.providers[planned.provider].name().to_string(),
+                text.ready.to_string(),
+            ),
+            ModState::Planned(Plan::Done(outcome)) => {
+                (String::new(), String::new(), self.outcome(outcome))
+            }
+            ModState::Finished(result) => {
+                let outcome = &self.results[*result].outcome;
+                let target = match outcome {
+                    MigrationOutcome::Migrated { new_version, .. } => {
+                        new_version.clone().unwrap_or_default()
+                    }
+                    _ => String::new(),
+                };
+                let provider = match outcome {
+                    MigrationOutcome::Migrated { provider, .. } => provider.clone(),
+                    _ => String::new(),
+                };
+                (target, provider, self.outcome(outcome))
+            }
+            ModState::Downloading { done, total } => (
+                String::new(),
+                String::new(),
+                match total {
+                    Some(total) if *total > 0 => {
+                        format!("{} {}%", text.downloading, done * 100 / total)
+                    }
+                    _ => text.downloading.to_string(),
+                },
+            ),
+        };
+        let style = match &row.state {
+            _ if row.skipped => Style::new().dim(),
+            ModState::Planned(Plan::Done(_)) => Style::new().fg(Color::Red),
+            ModState::Finished(result) => match self.results[*result].outcome {
+                MigrationOutcome::Migrated { .. } | MigrationOutcome::Ignored => {
+                    Style::new().fg(Color::Green)
+                }
+                _ => Style::new().fg(Color::Red),
+            },
+            _ => Style::new(),
+        };
+        Row::new([
+            Cell::from(scanned.file_name.clone()),
+            Cell::from(
+                scanned
+                    .loader
+                    .as_ref()
+                    .map_or("?".to_string(), |l| format!("{:?}", l)),
+            ),
+            Cell::from(scanned.version.clone().unwrap_or_default()),
+            Cell::from(target),
+            Cell::from(provider),
+            Cell::from(status),
+        ])
+        .style(style)
+    }
+
+    fn outcome(&self, outcome: &MigrationOutcome) -> String {
+        let text = self.context.text;
+        match outcome {
+            MigrationOutcome::Migrated { .. } => text.migrated,
+            MigrationOutcome::Ignored => text.ignored,
+            MigrationOutcome::Unrecognized => text.unrecognized,
+            MigrationOutcome::UnknownLoader => text.unknown_loader,
+            MigrationOutcome::Failed { .. } => text.failed,
+        }
+        .to_string()
+    }
+
+    /// 选中 Mod 的选择原因与各来源的失败原因
+    fn details(&self, row: &ModRow) -> Vec<Line<'static>> {
+        let text = self.context.text;
+        let mut lines = Vec::new();
+        let errors = match &row.state {
+            ModState::Planned(Plan::Ready(planned)) => {
+                lines.push(Line::from(format!(
+                    "{}: {}",
+                    text.reason, planned.resolved.reason
+                )));
+                if let Some(mc_version) = &planned.resolved.compatible_with {
+                    lines.push(Line::from(format!("{} {}", text.compatible, mc_version)).yellow());
+                }
+                planned.errors.as_slice()
+            }
+            ModState::Planned(Plan::Done(MigrationOutcome::Failed { errors })) => errors.as_slice(),
+            ModState::Finished(result) => match &self.results[*result].outcome {
+                MigrationOutcome::Migrated { errors, .. } | MigrationOutcome::Failed { errors } => {
+                    errors.as_slice()
+                }
+                _ => &[],
+            },
+            _ => &[],
+        };
+        for error in errors {
+            lines.push(
+                Line::from(format!(
+                    "{}: {}",
+                    error.provider,
+                    (self.context.text.error)(&error.error)
+                ))
+                .red(),
+            );
+        }
+        lines
+    }
+}
+
+/// 居中的弹出区域
+fn popup(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
@@ -1,69 +1,420 @@
-use super::Interface;
-use crate::CURSEFORGE_API_KEY;
+use super::progress::ProgressBars;
+use super::tui::{self, TuiContext, TuiText};
+use super::{Interface, format_rules, lock_prompt, read_choice, read_line};
 use crate::LOGO;
 use crate::VERSION;
-use crate::api::curseforge::download_curseforge_mod;
-use crate::api::modrinth::download_modrinth_mod;
+use crate::api::build_providers;
+use crate::api::download;
+use crate::api::provider::{ModProvider, RemoteVersion};
+use crate::cache::store::Cache;
+use crate::cli::args::{CacheAction, MappingsAction};
+use crate::config::{CONFIG_FILE_NAME, Config, ReportConfig, save_mod_rules};
+use crate::core::migrate::{
+    Migration, MigrationOutcome, MigrationResult, ScannedMod, migrate_mods,
+};
+use crate::core::search::{Candidate, Picker, SearchQuery};
+use crate::error::Error;
+use crate::lock::lockfile::{
+    InstallStatus, LOCKFILE_NAME, LockInstaller, Lockfile, remove_unlocked_jars,
+};
+use crate::mapping::database::{self, Mappings};
+use crate::report::report::{Report, report_path};
 use crate::scan::scan;
-use crate::utils::loader::{self, detect_mod, get_mod_id, get_mod_version};
+use crate::server::server::{self, ApiServer};
+use crate::utils::format::format_size;
+use crate::utils::loader;
+use crate::utils::output::{self, Verbosity};
 use crate::utils::version::validate_version;
+use std::env;
+use std::fs::DirEntry;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
+
+/// 更新日志摘要的最大字符数
+const CHANGELOG_EXCERPT_CHARS: usize = 80;
+
+/// 本地化的错误提示
+fn error_message(error: &Error) -> String {
+    match error {
+        Error::NotFound(what) => format!("未找到 {}", what),
+        Error::NoCompatibleVersion(detail) => format!("没有兼容的版本 ({})", detail),
+        Error::Network(detail) => format!("网络错误: {}", detail),
+        Error::RateLimited(Some(wait)) => {
+            format!(
+                "请求过于频繁, 已被限速, 请 {} 秒后重试",
+                wait.as_secs().max(1)
+            )
+        }
+        Error::RateLimited(None) => "请求过于频繁, 已被限速, 请稍后重试".to_string(),
+        Error::Io(e) => format!("文件读写失败: {}", e),
+        Error::CorruptJar(detail) => format!("Jar 文件已损坏: {}", detail),
+        Error::HashMismatch(detail) => format!("下载的文件与哈希值不一致: {}", detail),
+        Error::DistributionDisabled(file) => {
+            format!("作者禁止第三方下载 {}, 请手动下载", file)
+        }
+        Error::Other(message) => message.clone(),
+    }
+}
+
+/// 全屏界面的文字
+const TUI_TEXT: TuiText = TuiText {
+    title: "MiraMigrator",
+    columns: ["文件", "加载器", "当前版本", "目标版本", "来源", "状态"],
+    resolving: "解析中...",
+    ready: "就绪",
+    skipped: "跳过",
+    ignored: "按配置跳过",
+    failed: "失败",
+    unrecognized: "无法识别",
+    unknown_loader: "未知加载器",
+    waiting: "等待中",
+    downloading: "下载中",
+    migrated: "已迁移",
+    details: "详情",
+    reason: "选择原因",
+    compatible: "兼容但非精确匹配: 该版本为 Minecraft",
+    pin_title: "选择要固定的版本",
+    pinned: "已固定并保存到配置文件:",
+    save_failed: "保存到配置文件失败:",
+    no_candidates: "该 Mod 没有可选的版本",
+    still_resolving: "仍有 Mod 正在解析, 请稍候",
+    backup_failed: "备份 Mod 文件失败:",
+    help_review: "↑↓ 选择  s 跳过/恢复  p 固定版本  r 重新解析  Enter 开始迁移  q 退出",
+    help_pin: "↑↓ 选择  Enter 固定该版本  Esc 取消",
+    help_applying: "正在迁移, 请稍候...",
+    help_done: "迁移完成, 按任意键继续",
+    error: error_message,
+};
 pub struct ZhCnInterface;
 
 impl ZhCnInterface {
     pub fn new() -> Self {
         ZhCnInterface
     }
-}
 
-impl Interface for ZhCnInterface {
-    fn init(&self) {
-        // 初始化
-        println!("正在初始化 MiraMigrator 中...");
-        scan::create_backup_folder();
-        println!("MiraMigrator 初始化完毕.");
+    /// 输出单个 Mod 的迁移结果
+    fn print_result(&self, result: &MigrationResult) {
+        // 安静模式只输出需要手动处理的 Mod
+        if output::verbosity() == Verbosity::Quiet
+            && matches!(
+                result.outcome,
+                MigrationOutcome::Migrated { .. } | MigrationOutcome::Ignored
+            )
+        {
+            return;
+        }
+        let scanned = &result.scanned;
+        let mod_id = scanned.mod_id.as_deref().unwrap_or_default();
+        let errors = match &result.outcome {
+            MigrationOutcome::Ignored => {
+                println!("按配置跳过 Mod {}, 保留原文件.", scanned.file_name);
+                return;
+            }
+            MigrationOutcome::Unrecognized => {
+                println!("无法识别 Mod {}, 请手动迁移.", scanned.file_name);
+                return;
+            }
+            MigrationOutcome::UnknownLoader => {
+                println!("未知的 Mod 加载器类型: {:?}", scanned.loader);
+                return;
+            }
+            MigrationOutcome::Migrated { errors, .. } | MigrationOutcome::Failed { errors } => {
+                errors
+            }
+        };
+        let loader = scanned.loader.as_ref().unwrap_or(&loader::ModLoader::None);
+        println!("正在下载 {:?} Mod {}...", loader, mod_id);
+        for (i, error) in errors.iter().enumerate() {
+            println!(
+                "从 {} 获取失败: {}",
+                error.provider,
+                error_message(&error.error)
+            );
+            if let Some(next) = result.outcome.next_provider(i) {
+                println!("正在尝试从 {} 获取...", next);
+            }
+        }
+        match &result.outcome {
+            MigrationOutcome::Migrated {
+                new_version,
+                compatible_with,
+                added_dependencies,
+                ..
+            } => {
+                println!(
+                    "{}-{} => {}-{} 耗时 {:.2?}s",
+                    mod_id,
+                    scanned.version.as_deref().unwrap_or_default(),
+                    mod_id,
+                    new_version.as_deref().unwrap_or("?"),
+                    result.elapsed.as_secs_f64()
+                );
+                if let Some(mc_version) = compatible_with {
+                    println!("兼容但非精确匹配: 该版本为 Minecraft {} 发布.", mc_version);
+                }
+                if !added_dependencies.is_empty() {
+                    println!(
+                        "新版本需要以下前置 Mod, 请确认已安装: {}",
+                        added_dependencies.join(", ")
+                    );
+                }
+            }
+            _ => println!("{:?} Mod {} 下载失败, 请手动迁移.", loader, mod_id),
+        }
+    }
+
+    /// 将用户的选择保存到配置文件, 之后的迁移不再询问
+    fn save_choice(&self, mod_id: &str, provider: &dyn ModProvider, project_id: &str) {
+        let key = format!("projects.{}", provider.id());
+        let rules = &[(key.as_str(), project_id)];
+        match save_mod_rules(Path::new(CONFIG_FILE_NAME), mod_id, rules) {
+            Ok(()) => println!(
+                "已将选择保存到 {} 的 [mods.{}] 中: {}",
+                CONFIG_FILE_NAME,
+                mod_id,
+                format_rules(rules)
+            ),
+            Err(e) => println!("保存选择失败: {}", e),
+        }
+    }
+
+    /// 读取下载来源与映射数据, 失败时输出配置错误
+    fn load_sources(&self, config: &Config) -> Option<(Vec<Box<dyn ModProvider>>, Mappings)> {
+        let providers = match build_providers(config) {
+            Ok(providers) => providers,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        let mappings = match Mappings::load(config) {
+            Ok(mappings) => mappings,
+            Err(e) => {
+                self.config_error(&e.to_string());
+                return None;
+            }
+        };
+        Some((providers, mappings))
     }
-    fn start(&self) {
+
+    /// 输出欢迎信息并读取目标 Minecraft 版本
+    fn read_version(&self) -> Option<String> {
         println!("{}", LOGO);
         println!("欢迎使用 MiraMigrator v{}", VERSION);
         println!("制作: 安禾辞 | QQ: 2301385546 | 交流群: 000000000");
         println!("请输入目标 Minecraft 版本 (格式如 1.20.6):");
-        let mut version = String::new();
-        loop {
-            version.clear();
-            std::io::stdin()
-                .read_line(&mut version)
-                .expect("获取用户输入版本失败.");
+        let version = loop {
+            let version = read_line()?;
             if validate_version(version.trim()) {
-                break;
+                break version;
             } else {
                 println!("版本不合法, 请重新输入:");
             }
-        }
+        };
         print!("目标 Minecraft 版本为: {}", version);
+        Some(version)
+    }
+
+    /// 扫描 Mods 目录中的有效 Mod, 没有时返回 None
+    fn scan_mods(&self) -> Option<Vec<DirEntry>> {
         // 扫描Mods目录
         println!("正在扫描 Mods 目录...");
-        let jar_files = scan::get_jar_files();
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                println!("读取 Mods 目录失败: {}", e);
+                return None;
+            }
+        };
         if jar_files.is_empty() {
             println!("未发现任何 Jar 文件, 请检查 Mods 目录.");
-            return;
+            return None;
         }
         let jar_files = scan::filter_valid_mods(jar_files);
         if jar_files.is_empty() {
             println!("未发现任何有效 Mod, 请检查 Mods 目录.");
-            return;
+            return None;
+        }
+        Some(jar_files)
+    }
+
+    /// 将下载的文件移回 Mods 目录, 输出迁移统计, 写入锁文件与迁移报告
+    fn finish(
+        &self,
+        results: &[MigrationResult],
+        version: &str,
+        cache_path: &Path,
+        start_time: Instant,
+        report: &ReportConfig,
+    ) {
+        // 失败的 Mod 列表
+        let failed_mods: Vec<_> = results
+            .iter()
+            .filter(|r| {
+                !matches!(
+                    r.outcome,
+                    MigrationOutcome::Migrated { .. } | MigrationOutcome::Ignored
+                )
+            })
+            .collect();
+        let ignored = results
+            .iter()
+            .filter(|r| matches!(r.outcome, MigrationOutcome::Ignored))
+            .count();
+        // 锁文件记录
+        let locked_mods = results
+            .iter()
+            .filter_map(|r| match &r.outcome {
+                MigrationOutcome::Migrated { locked, .. } => Some(locked.as_ref().clone()),
+                _ => None,
+            })
+            .collect();
+        log::info!(
+            "Migration to Minecraft {} finished: {} migrated, {} failed, {} ignored",
+            version.trim(),
+            results.len() - failed_mods.len() - ignored,
+            failed_mods.len(),
+            ignored
+        );
+        if let Err(e) = scan::move_files_from_cache_to_current_dir(cache_path) {
+            log::error!("Failed to move the downloaded mods: {}", e);
+            eprintln!("版本迁移失败: {}", e);
+        } else {
+            let elapsed_time = start_time.elapsed();
+            println!(
+                "\n版本迁移完成, 共耗时: {:.2?}s",
+                elapsed_time.as_secs_f64()
+            );
+            println!(
+                "成功迁移 {} 个 Mod, 失败 {} 个 Mod.",
+                results.len() - failed_mods.len() - ignored,
+                failed_mods.len()
+            );
+            if ignored > 0 {
+                println!("按配置跳过 {} 个 Mod.", ignored);
+            }
+            if !failed_mods.is_empty() {
+                println!("失败的 Mod 列表如下 (请手动迁移):");
+                for result in &failed_mods {
+                    println!(" - {}", result.scanned.file_name);
+                }
+            }
+            // 写入锁文件
+            let mut lockfile = Lockfile::new(version);
+            lockfile.mods = locked_mods;
+            match lockfile.save(Path::new(LOCKFILE_NAME)) {
+                Ok(()) => println!("已将迁移结果写入锁文件 {}.", LOCKFILE_NAME),
+                Err(e) => println!("写入锁文件失败: {}", e),
+            }
+        }
+        // 写入迁移报告
+        let migration_report = Report::new(version, results, start_time.elapsed());
+        for format in &report.formats {
+            let path = report_path(&report.file, *format);
+            match migration_report.save(*format, &path) {
+                Ok(()) => println!("已将迁移报告写入 {}.", path.display()),
+                Err(e) => println!("写入迁移报告 {} 失败: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+impl Picker for ZhCnInterface {
+    fn pick_project(
+        &self,
+        query: &SearchQuery,
+        provider: &dyn ModProvider,
+        candidates: &[Candidate],
+    ) -> Option<usize> {
+        let _prompt = lock_prompt();
+        let _pause = output::pause();
+        println!(
+            "无法确定 Mod {} 在 {} 上的项目, 搜索到以下候选:",
+            query.display_name(),
+            provider.name()
+        );
+        for (i, candidate) in candidates.iter().enumerate() {
+            let project = &candidate.project;
+            println!(
+                " {}. {} ({}) 相似度 {:.0}%, 下载 {}",
+                i + 1,
+                project.title,
+                project.slug,
+                candidate.score * 100.0,
+                project.downloads.map_or("?".to_string(), |n| n.to_string())
+            );
+        }
+        println!("请输入序号, 输入 0 或直接回车表示都不是:");
+        let index = read_choice(candidates.len(), "输入不合法, 请重新输入:")?;
+        let project = &candidates[index].project;
+        self.save_choice(query.mod_id, provider, &project.id);
+        Some(index)
+    }
+
+    fn pick_version(
+        &self,
+        mod_id: &str,
+        provider: &dyn ModProvider,
+        versions: &[RemoteVersion],
+    ) -> Option<usize> {
+        let _prompt = lock_prompt();
+        let _pause = output::pause();
+        println!("Mod {} 在 {} 上有以下可选版本:", mod_id, provider.name());
+        for (i, version) in versions.iter().enumerate() {
+            println!(
+                " {}. {} [{}] {} 下载 {}{}",
+                i + 1,
+                version.version_number,
+                version.channel.name(),
+                version.published_date(),
+                version.downloads.map_or("?".to_string(), |n| n.to_string()),
+                if i == 0 { " (推荐)" } else { "" }
+            );
+            if let Some(changelog) = version.changelog_excerpt(CHANGELOG_EXCERPT_CHARS) {
+                println!("    {}", changelog);
+            }
+        }
+        println!("请输入序号, 直接回车使用推荐版本:");
+        let index = read_choice(versions.len(), "输入不合法, 请重新输入:")?;
+        // 只记住项目, 版本仅用于本次迁移; 固定版本后迁移到其他 Minecraft 版本时将无法匹配
+        let version = &versions[index];
+        self.save_choice(mod_id, provider, &version.project_id);
+        Some(index)
+    }
+}
+
+impl Interface for ZhCnInterface {
+    fn init(&self) -> bool {
+        // 初始化
+        println!("正在初始化 MiraMigrator 中...");
+        if let Err(e) = scan::create_backup_folder() {
+            println!("创建备份目录失败: {}", e);
+            return false;
         }
+        println!("MiraMigrator 初始化完毕.");
+        true
+    }
+    fn start(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let Some(version) = self.read_version() else {
+            return;
+        };
+        let Some(jar_files) = self.scan_mods() else {
+            return;
+        };
         println!("发现 {} 个 Mod 文件, 列表如下:", jar_files.len(),);
         for jar_file in &jar_files {
             println!(" - {}", jar_file.file_name().to_string_lossy());
         }
         println!("是否确认执行版本迁移? 原 Mod 将会备份至当前目录下 backup 文件夹中 (y/n):");
-        let mut confirm = String::new();
         loop {
-            confirm.clear();
-            std::io::stdin()
-                .read_line(&mut confirm)
-                .expect("获取用户输入确认失败.");
+            let Some(confirm) = read_line() else {
+                println!("已取消版本迁移.");
+                return;
+            };
             if confirm.trim().eq_ignore_ascii_case("y") {
                 break;
             } else if confirm.trim().eq_ignore_ascii_case("n") {
@@ -80,183 +431,352 @@ impl Interface for ZhCnInterface {
             let file_name_osstr = jar_file.file_name();
             let file_name = file_name_osstr.to_string_lossy();
             let backup_path = format!("backup/{}", file_name);
-            std::fs::copy(jar_file.path(), backup_path).expect("备份 Mod 文件失败.");
+            if let Err(e) = std::fs::copy(jar_file.path(), backup_path) {
+                println!("备份 Mod 文件 {} 失败: {}, 已取消版本迁移.", file_name, e);
+                return;
+            }
         }
         let elapsed_time = start_time.elapsed();
         println!("文件备份完成, 共耗时: {:.2?}s", elapsed_time.as_secs_f64());
 
         // 执行版本迁移
         println!("开始执行版本迁移...");
-        // 失败的 Mod列表
-        let mut failed_mods = Vec::new();
-        // 成功的 Mod列表
-        let mut success_mods = Vec::new();
         // 缓存目录
-        let cache_path = std::path::Path::new("cache");
+        let cache_path = Path::new("cache");
         let start_time = Instant::now();
-        for jar_file in &jar_files {
-            let jar_file_path = jar_file.path();
-            let mod_id = get_mod_id(&jar_file_path).ok().flatten();
-            let mod_version = get_mod_version(&jar_file_path).ok().flatten();
-            let mod_loader = detect_mod(&jar_file_path).ok().flatten();
-            if mod_id.is_none() | mod_version.is_none() {
-                println!("id {}", mod_id.is_none());
-                println!("version {}", mod_version.is_none());
-                println!("无法识别 Mod {}, 请手动迁移.", jar_file.file_name().to_string_lossy());
-                std::fs::remove_file(jar_file.path()).expect("删除原 Jar 文件失败.");
-                failed_mods.push(jar_file);
-            } else  {
-                std::fs::remove_file(jar_file.path()).expect("删除原 Jar 文件失败.");
-                let old_mod_id = mod_id.unwrap();
-                let old_mod_version = mod_version.unwrap();
-                let loader = mod_loader.unwrap();
-                let cache_path = std::path::Path::new("cache");
-                let time = Instant::now();
-                let new_mod_version;
-                // 下载Mod
-                // CourseForge API Key
-                let api_key = CURSEFORGE_API_KEY;
-                match loader {
-                    loader::ModLoader::Fabric => {
-                        println!("正在下载 Fabric Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "fabric", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("从 Modrinth 拉取失败: {:?}", error);
-                                println!("开始尝试从 CurseForge 拉取...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "fabric",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("从 CurseForge 拉取失败: {:?}", error);
-                                        println!("Fabric Mod {} 下载失败, 请手动迁移.", old_mod_id);
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
+        let scanned_mods: Vec<_> = jar_files.iter().map(ScannedMod::read).collect();
+        let bars = Arc::new(ProgressBars::new(
+            scanned_mods.iter().map(|m| m.file_name.clone()).collect(),
+            "总进度",
+        ));
+        let progress = {
+            let bars = bars.clone();
+            move |index, done, total| bars.update(index, done, total)
+        };
+        let results = migrate_mods(
+            scanned_mods,
+            &Migration {
+                mc_version: version.trim(),
+                cache_dir: cache_path,
+                providers: &providers,
+                mappings: &mappings,
+                config,
+                picker: Some(self),
+            },
+            progress,
+            |index, result| bars.finish(index, || self.print_result(result)),
+        );
+        bars.clear();
+        self.finish(&results, &version, cache_path, start_time, &config.report);
+    }
+
+    fn tui(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let Some(version) = self.read_version() else {
+            return;
+        };
+        let Some(jar_files) = self.scan_mods() else {
+            return;
+        };
+        let cache_path = Path::new("cache");
+        let start_time = Instant::now();
+        let scanned_mods = jar_files.iter().map(ScannedMod::read).collect();
+        let context = TuiContext {
+            mc_version: version.trim(),
+            cache_dir: cache_path,
+            providers: &providers,
+            mappings: &mappings,
+            config,
+            text: &TUI_TEXT,
+        };
+        match tui::run(&context, scanned_mods) {
+            Ok(Some(results)) => {
+                for result in &results {
+                    self.print_result(result);
+                }
+                self.finish(&results, &version, cache_path, start_time, &config.report);
+            }
+            Ok(None) => println!("已取消版本迁移."),
+            Err(e) => println!("全屏界面运行失败: {}", e),
+        }
+    }
+
+    fn install(&self, config: &Config, locked: bool) {
+        if !locked {
+            println!("目前仅支持按锁文件安装, 请使用 install --locked.");
+            return;
+        }
+        println!("正在读取锁文件 {}...", LOCKFILE_NAME);
+        let lockfile = match Lockfile::load(Path::new(LOCKFILE_NAME)) {
+            Ok(lockfile) => lockfile,
+            Err(e) => {
+                println!("读取锁文件失败: {}", e);
+                return;
+            }
+        };
+        println!(
+            "锁文件目标 Minecraft 版本: {}, 共 {} 个 Mod.",
+            lockfile.minecraft_version,
+            lockfile.mods.len()
+        );
+        // 备份 Mod 文件
+        println!("开始备份 Mod 文件...");
+        let jar_files = match scan::get_jar_files() {
+            Ok(jar_files) => jar_files,
+            Err(e) => {
+                println!("读取 Mods 目录失败: {}", e);
+                return;
+            }
+        };
+        for jar_file in jar_files {
+            let file_name = jar_file.file_name().to_string_lossy().to_string();
+            if let Err(e) = std::fs::copy(jar_file.path(), format!("backup/{}", file_name)) {
+                println!("备份 Mod 文件 {} 失败: {}, 已取消安装.", file_name, e);
+                return;
+            }
+        }
+        // 按锁文件安装
+        let installer = match LockInstaller::new(config) {
+            Ok(installer) => installer,
+            Err(e) => {
+                println!("创建下载客户端失败: {}", e);
+                return;
+            }
+        };
+        let start_time = Instant::now();
+        let mut failed_mods = Vec::new();
+        let bars = Arc::new(ProgressBars::new(
+            lockfile.mods.iter().map(|m| m.file_name.clone()).collect(),
+            "总进度",
+        ));
+        let quiet = output::verbosity() == Verbosity::Quiet;
+        for (index, locked_mod) in lockfile.mods.iter().enumerate() {
+            let progress = bars.clone();
+            let status = download::with_progress(
+                move |done, total| progress.update(index, done, total),
+                || installer.install(locked_mod, Path::new("."), Path::new("cache")),
+            );
+            bars.finish(index, || match status {
+                Ok(_) if quiet => {}
+                Ok(InstallStatus::Unchanged) => {
+                    println!(" = {} (已是锁定版本)", locked_mod.file_name);
+                }
+                Ok(InstallStatus::Downloaded) => {
+                    println!(" + {} ({})", locked_mod.file_name, locked_mod.provider);
+                }
+                Err(e) => {
+                    println!("安装 {} 失败: {}", locked_mod.file_name, error_message(&e));
+                    failed_mods.push(locked_mod);
+                }
+            });
+        }
+        bars.clear();
+        if failed_mods.is_empty() {
+            match remove_unlocked_jars(&lockfile, Path::new(".")) {
+                Ok(removed) => {
+                    for path in removed {
+                        println!(
+                            " - {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        );
                     }
-                    loader::ModLoader::Forge => {
-                        println!("正在下载 Forge Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "forge", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("从 Modrinth 拉取失败: {:?}", error);
-                                println!("开始尝试从 CurseForge 拉取...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "forge",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("从 CurseForge 拉取失败: {:?}", error);
-                                        println!("Fabric Mod {} 下载失败, 请手动迁移.", old_mod_id);
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
+                }
+                Err(e) => println!("清理锁文件之外的 Mod 失败: {}", e),
+            }
+        } else {
+            println!("部分 Mod 安装失败, 已保留锁文件之外的 Mod.");
+        }
+        println!(
+            "\n安装完成, 共耗时: {:.2?}s",
+            start_time.elapsed().as_secs_f64()
+        );
+        if !failed_mods.is_empty() {
+            println!("以下 Mod 安装失败, 原文件已备份至 backup 文件夹:");
+            for locked_mod in failed_mods {
+                println!(" - {}", locked_mod.file_name);
+            }
+        }
+    }
+
+    fn cache(&self, config: &Config, action: CacheAction) {
+        let cache = Cache::new(&config.cache);
+        let Some(root) = cache.root() else {
+            println!("下载缓存已禁用.");
+            return;
+        };
+        match action {
+            CacheAction::List => match cache.list_files() {
+                Ok(files) => {
+                    println!("缓存目录: {}", root.display());
+                    for file in &files {
+                        println!(
+                            " - {} [{}] {}",
+                            file.file_name,
+                            &file.sha1[..file.sha1.len().min(12)],
+                            format_size(file.size)
+                        );
                     }
-                    loader::ModLoader::Quilt => {
-                        println!("正在下载 Quilt Mod {}...", old_mod_id);
-                        let result =
-                            download_modrinth_mod(&old_mod_id, &version, "quilt", cache_path);
-                        match result {
-                            Ok(path) => {
-                                new_mod_version = get_mod_version(&path).unwrap();
-                            }
-                            Err(error) => {
-                                println!("从 Modrinth 拉取失败: {:?}", error);
-                                println!("开始尝试从 CurseForge 拉取...");
-                                let result = download_curseforge_mod(
-                                    &old_mod_id,
-                                    &version,
-                                    "quilt",
-                                    cache_path,
-                                    api_key,
-                                );
-                                match result {
-                                    Ok(path) => {
-                                        new_mod_version = get_mod_version(&path).unwrap();
-                                    }
-                                    Err(error) => {
-                                        println!("从 CurseForge 拉取失败: {:?}", error);
-                                        println!("Fabric Mod {} 下载失败, 请手动迁移.", old_mod_id);
-                                        failed_mods.push(jar_file);
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
+                    println!(
+                        "共 {} 个文件, {}; {} 条 API 响应缓存.",
+                        files.len(),
+                        format_size(files.iter().map(|f| f.size).sum()),
+                        cache.metadata_count().unwrap_or(0)
+                    );
+                }
+                Err(e) => println!("读取缓存失败: {}", e),
+            },
+            CacheAction::Prune => match cache.prune() {
+                Ok(summary) => println!(
+                    "已清理 {} 个长期未使用的文件与 {} 条过期的 API 响应, 释放 {}.",
+                    summary.files,
+                    summary.metadata,
+                    format_size(summary.bytes)
+                ),
+                Err(e) => println!("清理缓存失败: {}", e),
+            },
+            CacheAction::Clear => match cache.clear() {
+                Ok(()) => println!("已清空缓存目录 {}.", root.display()),
+                Err(e) => println!("清空缓存失败: {}", e),
+            },
+        }
+    }
+
+    fn mappings(&self, config: &Config, action: MappingsAction) {
+        match action {
+            MappingsAction::Show => match Mappings::load(config) {
+                Ok(mappings) => {
+                    println!("内置映射数据已加载.");
+                    for source in &mappings.sources {
+                        println!("已加载映射文件: {}", source.display());
                     }
-                    loader::ModLoader::None => {
-                        println!("未知的模组加载器类型: {:?}", loader);
-                        failed_mods.push(jar_file);
-                        continue;
+                    println!(
+                        "共 {} 个 Mod 的映射, 本地覆盖文件: {}",
+                        mappings.count(),
+                        config.mappings.overrides.display()
+                    );
+                }
+                Err(e) => println!("读取映射数据失败: {}", e),
+            },
+            MappingsAction::Update => {
+                println!("正在从 {} 下载映射数据...", config.mappings.url);
+                match database::update(config) {
+                    Ok((count, path)) => {
+                        println!("已更新 {} 个 Mod 的映射, 保存到 {}.", count, path.display())
                     }
+                    Err(e) => println!("更新映射数据失败: {}", e),
                 }
-
-                let end_time = time.elapsed();
-                println!(
-                    "{}-{} => {}-{} 耗时 {:.2?}s",
-                    old_mod_id,
-                    old_mod_version,
-                    old_mod_id,
-                    new_mod_version.unwrap(),
-                    end_time.as_secs_f64()
-                );
-                success_mods.push(jar_file);
             }
         }
-        if let Err(e) = scan::move_files_from_cache_to_current_dir(cache_path) {
-            eprintln!("版本迁移失败: {}", e);
-        } else {
-            let elapsed_time = start_time.elapsed();
-            println!(
-                "\n版本迁移完成, 共耗时: {:.2?}s",
-                elapsed_time.as_secs_f64()
-            );
-            println!(
-                "成功迁移 {} 个 Mod, 失败 {} 个 Mod.",
-                success_mods.len(),
-                failed_mods.len()
-            );
-            if !failed_mods.is_empty() {
-                println!("失败的 Mod 列表如下 (请手动迁移):");
-                for jar_file in &failed_mods {
-                    println!(" - {}", jar_file.file_name().to_string_lossy());
+    }
+
+    fn serve(&self, config: &Config) {
+        let Some((providers, mappings)) = self.load_sources(config) else {
+            return;
+        };
+        let (token, generated) = match config.serve.token.clone().or_else(|| {
+            env::var("MIRAMIGRATOR_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty())
+        }) {
+            Some(token) => (token, false),
+            None => match server::generate_token() {
+                Ok(token) => (token, true),
+                Err(e) => {
+                    println!("无法生成访问令牌: {}", e);
+                    return;
                 }
+            },
+        };
+        let api = match ApiServer::bind(token.clone(), config, &providers, &mappings) {
+            Ok(api) => api,
+            Err(e) => {
+                println!("无法监听 {}: {}", config.serve.address, e);
+                return;
             }
+        };
+        println!("HTTP API 已启动: http://{}/api/", api.address());
+        if generated {
+            println!("本次访问令牌: {}", token);
+            println!(
+                "可在 {} 的 [serve] token 或环境变量 MIRAMIGRATOR_TOKEN 中固定令牌.",
+                CONFIG_FILE_NAME
+            );
+        }
+        println!("请求需带有请求头 Authorization: Bearer <令牌>, 按 Ctrl+C 停止.");
+        api.run();
+    }
+
+    fn help(&self, invalid_argument: Option<&str>) {
+        if let Some(arg) = invalid_argument {
+            println!("无法识别的参数: {}", arg);
+        }
+        println!("用法: MiraMigrator [命令] [选项]");
+        println!();
+        println!("命令:");
+        for (usage, description) in [
+            ("start", "交互式迁移当前目录中的 Mod (默认)".to_string()),
+            ("tui", "在全屏界面中查看, 调整并执行迁移".to_string()),
+            (
+                "install --locked",
+                format!("按 {} 还原当前目录中的 Mod", LOCKFILE_NAME),
+            ),
+            (
+                "cache list|prune|clear",
+                "查看, 清理或清空下载缓存".to_string(),
+            ),
+            (
+                "mappings show|update",
+                "查看或更新 Mod ID 映射数据".to_string(),
+            ),
+            (
+                "serve",
+                "启动本地 HTTP API, 供网页面板等程序扫描, 迁移与恢复".to_string(),
+            ),
+            ("help", "显示本帮助".to_string()),
+        ] {
+            println!("  {:<24} {}", usage, description);
         }
+        println!();
+        println!("选项:");
+        for (usage, description) in [
+            (
+                "-j, --jobs <N>",
+                format!("同时迁移的 Mod 数量, 覆盖 {} 中的设置", CONFIG_FILE_NAME),
+            ),
+            (
+                "-i, --interactive",
+                "有多个可选版本时手动选择, 选择将保存到配置文件".to_string(),
+            ),
+            (
+                "-q, --quiet",
+                "安静模式, 只输出需要手动处理的 Mod 与最终统计".to_string(),
+            ),
+            (
+                "-v, --verbose",
+                "详细模式, 额外输出 HTTP 请求与重试详情".to_string(),
+            ),
+            (
+                "--output <FORMAT>",
+                "输出格式: text (默认) 或 json-lines (每行一个 JSON 事件, 供其他程序调用)"
+                    .to_string(),
+            ),
+            ("-h, --help", "显示本帮助".to_string()),
+        ] {
+            println!("  {:<24} {}", usage, description);
+        }
+    }
+
+    fn config_error(&self, error: &str) {
+        println!("读取配置文件 {} 失败: {}", CONFIG_FILE_NAME, error);
+    }
+
+    fn log_error(&self, path: &Path, error: &str) {
+        println!("无法打开日志文件 {}: {}", path.display(), error);
     }
     fn exit(&self) {
         println!("按任意键退出MiraMigrator...");
-        std::io::stdin()
-            .read_line(&mut String::new())
-            .expect("Failed to read line");
+        let _ = read_line();
     }
 }
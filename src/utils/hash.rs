@@ -0,0 +1,140 @@
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 文件的哈希值 (十六进制小写)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHashes {
+    pub sha1: String,
+    pub sha256: String,
+    pub sha512: String,
+}
+
+/// 同时计算 SHA-1, SHA-256 与 SHA-512 的增量哈希器
+#[derive(Default)]
+pub struct Hasher {
+    sha1: Sha1,
+    sha256: Sha256,
+    sha512: Sha512,
+}
+
+impl Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sha1.update(data);
+        self.sha256.update(data);
+        self.sha512.update(data);
+    }
+
+    pub fn finalize(self) -> FileHashes {
+        FileHashes {
+            sha1: to_hex(&self.sha1.finalize()),
+            sha256: to_hex(&self.sha256.finalize()),
+            sha512: to_hex(&self.sha512.finalize()),
+        }
+    }
+}
+
+/// 计算文件的 SHA-1, SHA-256 与 SHA-512 哈希值
+pub fn hash_file(path: &Path) -> io::Result<FileHashes> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize())
+}
+
+/// 将字节转换为十六进制小写字符串
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 计算 CurseForge 文件指纹: 去除空白字节 (9, 10, 13, 32) 后, 以种子 1 计算 MurmurHash2
+pub fn curseforge_fingerprint(path: &Path) -> io::Result<u32> {
+    let data: Vec<u8> = std::fs::read(path)?
+        .into_iter()
+        .filter(|b| !matches!(b, 9 | 10 | 13 | 32))
+        .collect();
+    Ok(murmur2(&data, 1))
+}
+
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let mut h = seed ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M) ^ k;
+    }
+    let tail = chunks.remainder();
+    if tail.len() >= 3 {
+        h ^= (tail[2] as u32) << 16;
+    }
+    if tail.len() >= 2 {
+        h ^= (tail[1] as u32) << 8;
+    }
+    if !tail.is_empty() {
+        h ^= tail[0] as u32;
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^ (h >> 15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hasher, curseforge_fingerprint, murmur2};
+    use crate::utils::testing::TestDir;
+    use std::fs;
+
+    #[test]
+    fn computes_murmur2() {
+        assert_eq!(murmur2(b"", 0), 0);
+        assert_eq!(murmur2(b"", 1), 0x5bd1_5e36);
+        // 覆盖 0 ~ 3 字节的尾部
+        assert_eq!(murmur2(b"a", 1), 0x2550_b18c);
+        assert_eq!(murmur2(b"abc", 1), 0x60a4_fcc1);
+        assert_eq!(murmur2(b"abcd", 1), 0xc93f_7a16);
+        assert_eq!(
+            murmur2(b"The quick brown fox jumps over the lazy dog", 0),
+            0x2127_29d0
+        );
+    }
+
+    #[test]
+    fn fingerprints_ignore_whitespace() {
+        let dir = TestDir::new("hash-fingerprint");
+        let path = dir.path().join("mod.jar");
+        fs::write(&path, b"a b\tc\r\n").unwrap();
+        assert_eq!(curseforge_fingerprint(&path).unwrap(), murmur2(b"abc", 1));
+    }
+
+    #[test]
+    fn hashes_incrementally() {
+        let mut hasher = Hasher::new();
+        hasher.update(b"a");
+        hasher.update(b"bc");
+        let hashes = hasher.finalize();
+        assert_eq!(hashes.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hashes.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(hashes.sha512.starts_with("ddaf35a193617aba"));
+    }
+}
@@ -1,12 +1,12 @@
+use serde_json::Value;
 use std::{
     fs,
     io::{self, Read},
     path::Path,
 };
 use zip::ZipArchive;
-use serde_json::Value;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ModLoader {
     Forge,
     Fabric,
@@ -25,7 +25,6 @@ pub fn detect_mod(jar_path: &Path) -> io::Result<Option<ModLoader>> {
     for i in 0..zip.len() {
         let entry = zip.by_index(i)?;
         let entry_name = entry.name().to_lowercase();
-        
 
         match entry_name.as_str() {
             "fabric.mod.json" => {
@@ -51,7 +50,6 @@ pub fn detect_mod(jar_path: &Path) -> io::Result<Option<ModLoader>> {
     Ok(loader)
 }
 
-
 /// 检查类文件中的 @Mod 注解来识别 Forge 模组
 fn has_mod_annotation(zip: &mut ZipArchive<fs::File>) -> io::Result<bool> {
     let annotation_marker = b"Lnet/minecraftforge/fml/common/Mod;";
@@ -86,49 +84,44 @@ pub fn get_mod_version(jar_path: &Path) -> io::Result<Option<String>> {
             "fabric.mod.json" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(version) = json.get("version").and_then(|v| v.as_str()) {
-                        return Ok(Some(version.to_string()));
-                    }
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(version) = json.get("version").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(version.to_string()));
                 }
             }
             "meta-inf/mods.toml" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(toml) = contents.parse::<toml::Value>() {
-                    if let Some(mods) = toml.get("mods").and_then(|m| m.as_array()) {
-                        if let Some(first_mod) = mods.get(0) {
-                            if let Some(version) = first_mod.get("version").and_then(|v| v.as_str()) {
-                                return Ok(Some(version.to_string()));
-                            }
-                        }
-                    }
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(mods) = toml.get("mods").and_then(|m| m.as_array())
+                    && let Some(first_mod) = mods.first()
+                    && let Some(version) = first_mod.get("version").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(version.to_string()));
                 }
             }
             "mcmod.info" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(arr) = json.as_array() {
-                        if let Some(first) = arr.get(0) {
-                            if let Some(version) = first.get("version").and_then(|v| v.as_str()) {
-                                return Ok(Some(version.to_string()));
-                            }
-                        }
-                    }
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(arr) = json.as_array()
+                    && let Some(first) = arr.first()
+                    && let Some(version) = first.get("version").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(version.to_string()));
                 }
             }
             "quilt.mod.json" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(version) = json
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(version) = json
                         .get("quilt_loader")
                         .and_then(|ql| ql.get("version"))
                         .and_then(|v| v.as_str())
-                    {
-                        return Ok(Some(version.to_string()));
-                    }
+                {
+                    return Ok(Some(version.to_string()));
                 }
             }
             _ => {}
@@ -150,58 +143,370 @@ pub fn get_mod_id(jar_path: &Path) -> io::Result<Option<String>> {
             "fabric.mod.json" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(name) = json.get("id").and_then(|v| v.as_str()) {
-                        return Ok(Some(name.to_string()));
-                    }
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(name) = json.get("id").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
                 }
             }
             "meta-inf/mods.toml" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(toml) = contents.parse::<toml::Value>() {
-                    if let Some(mods) = toml.get("mods").and_then(|m| m.as_array()) {
-                        if let Some(first_mod) = mods.get(0) {
-                            if let Some(name) = first_mod.get("modId").and_then(|v| v.as_str()) {
-                                return Ok(Some(name.to_string()));
-                            }
-                        }
-                    }
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(mods) = toml.get("mods").and_then(|m| m.as_array())
+                    && let Some(first_mod) = mods.first()
+                    && let Some(name) = first_mod.get("modId").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
                 }
             }
             "mcmod.info" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(arr) = json.as_array() {
-                        if let Some(first) = arr.get(0) {
-                            if let Some(name) = first.get("modid").and_then(|v| v.as_str()) {
-                                return Ok(Some(name.to_string()));
-                            }
-                        }
-                    }
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(arr) = json.as_array()
+                    && let Some(first) = arr.first()
+                    && let Some(name) = first.get("modid").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
                 }
             }
             "quilt.mod.json" => {
                 let mut contents = String::new();
                 entry.read_to_string(&mut contents)?;
-                if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                    if let Some(name) = json.get("id").and_then(|v| v.as_str()) {
-                        return Ok(Some(name.to_string()));
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(name) = json.get("id").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// 获取 mod 声明支持的 Minecraft 版本范围
+/// Fabric 的多个范围以 ` || ` 连接, Forge 返回 Maven 区间, mcmod.info 返回单个版本
+pub fn get_minecraft_range(jar_path: &Path) -> io::Result<Option<String>> {
+    let file = fs::File::open(jar_path)?;
+    let mut zip = ZipArchive::new(file)?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let entry_name = entry.name().to_lowercase();
+
+        match entry_name.as_str() {
+            "fabric.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(range) = json.get("depends").and_then(|d| d.get("minecraft"))
+                {
+                    return Ok(join_ranges(range));
+                }
+            }
+            "meta-inf/mods.toml" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(dependencies) = toml.get("dependencies").and_then(|d| d.as_table())
+                {
+                    let range = dependencies
+                        .values()
+                        .filter_map(|deps| deps.as_array())
+                        .flatten()
+                        .find(|dep| dep.get("modId").and_then(|v| v.as_str()) == Some("minecraft"))
+                        .and_then(|dep| dep.get("versionRange"))
+                        .and_then(|v| v.as_str());
+                    if let Some(range) = range {
+                        return Ok(Some(range.to_string()));
                     }
                 }
             }
+            "mcmod.info" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(arr) = json.as_array()
+                    && let Some(first) = arr.first()
+                    && let Some(version) = first.get("mcversion").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(version.to_string()));
+                }
+            }
+            "quilt.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(depends) = json
+                        .get("quilt_loader")
+                        .and_then(|ql| ql.get("depends"))
+                        .and_then(|d| d.as_array())
+                    && let Some(range) = depends
+                        .iter()
+                        .find(|dep| dep.get("id").and_then(|v| v.as_str()) == Some("minecraft"))
+                        .and_then(|dep| dep.get("versions"))
+                {
+                    return Ok(join_ranges(range));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// 获取 mod 的显示名称
+pub fn get_mod_name(jar_path: &Path) -> io::Result<Option<String>> {
+    let file = fs::File::open(jar_path)?;
+    let mut zip = ZipArchive::new(file)?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let entry_name = entry.name().to_lowercase();
+
+        match entry_name.as_str() {
+            "fabric.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(name) = json.get("name").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+            "meta-inf/mods.toml" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(mods) = toml.get("mods").and_then(|m| m.as_array())
+                    && let Some(first_mod) = mods.first()
+                    && let Some(name) = first_mod.get("displayName").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+            "mcmod.info" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(arr) = json.as_array()
+                    && let Some(first) = arr.first()
+                    && let Some(name) = first.get("name").and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
+                }
+            }
+            "quilt.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(name) = json
+                        .get("quilt_loader")
+                        .and_then(|ql| ql.get("metadata"))
+                        .and_then(|m| m.get("name"))
+                        .and_then(|v| v.as_str())
+                {
+                    return Ok(Some(name.to_string()));
+                }
+            }
             _ => {}
         }
     }
     Ok(None)
 }
 
+/// 获取 mod 的作者列表
+pub fn get_mod_authors(jar_path: &Path) -> io::Result<Vec<String>> {
+    let file = fs::File::open(jar_path)?;
+    let mut zip = ZipArchive::new(file)?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let entry_name = entry.name().to_lowercase();
+
+        match entry_name.as_str() {
+            // 作者可以是字符串或 `{ "name": ... }` 对象
+            "fabric.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(authors) = json.get("authors").and_then(|a| a.as_array())
+                {
+                    return Ok(authors
+                        .iter()
+                        .filter_map(|a| a.as_str().or_else(|| a.get("name")?.as_str()))
+                        .map(|a| a.to_string())
+                        .collect());
+                }
+            }
+            // Forge 的作者为以逗号分隔的字符串
+            "meta-inf/mods.toml" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(mods) = toml.get("mods").and_then(|m| m.as_array())
+                    && let Some(authors) = mods
+                        .first()
+                        .and_then(|m| m.get("authors"))
+                        .or_else(|| toml.get("authors"))
+                        .and_then(|a| a.as_str())
+                {
+                    return Ok(authors
+                        .split(',')
+                        .map(|a| a.trim().to_string())
+                        .filter(|a| !a.is_empty())
+                        .collect());
+                }
+            }
+            "mcmod.info" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(arr) = json.as_array()
+                    && let Some(first) = arr.first()
+                    && let Some(authors) = first
+                        .get("authorList")
+                        .or_else(|| first.get("authors"))
+                        .and_then(|a| a.as_array())
+                {
+                    return Ok(authors
+                        .iter()
+                        .filter_map(|a| a.as_str())
+                        .map(|a| a.to_string())
+                        .collect());
+                }
+            }
+            // Quilt 的贡献者为名称到角色的映射
+            "quilt.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(contributors) = json
+                        .get("quilt_loader")
+                        .and_then(|ql| ql.get("metadata"))
+                        .and_then(|m| m.get("contributors"))
+                        .and_then(|c| c.as_object())
+                {
+                    return Ok(contributors.keys().cloned().collect());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// 不属于 Mod 的依赖: 游戏本体, Java 与加载器
+const PLATFORM_DEPENDENCIES: [&str; 7] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "forge",
+    "neoforge",
+    "quilt_loader",
+    "fml",
+];
+
+/// 获取 mod 声明的必需依赖 (Mod ID), 不含游戏本体, Java 与加载器
+pub fn get_mod_dependencies(jar_path: &Path) -> io::Result<Vec<String>> {
+    let file = fs::File::open(jar_path)?;
+    let mut zip = ZipArchive::new(file)?;
+
+    let mut dependencies: Vec<String> = Vec::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let entry_name = entry.name().to_lowercase();
+
+        match entry_name.as_str() {
+            // 依赖为 Mod ID 到版本范围的映射
+            "fabric.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(depends) = json.get("depends").and_then(|d| d.as_object())
+                {
+                    dependencies = depends.keys().cloned().collect();
+                    break;
+                }
+            }
+            // 旧版使用 `mandatory = true`, NeoForge 使用 `type = "required"`
+            "meta-inf/mods.toml" | "meta-inf/neoforge.mods.toml" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(toml) = contents.parse::<toml::Value>()
+                    && let Some(tables) = toml.get("dependencies").and_then(|d| d.as_table())
+                {
+                    dependencies = tables
+                        .values()
+                        .filter_map(|deps| deps.as_array())
+                        .flatten()
+                        .filter(|dep| {
+                            dep.get("mandatory").and_then(|v| v.as_bool()) == Some(true)
+                                || dep.get("type").and_then(|v| v.as_str()) == Some("required")
+                        })
+                        .filter_map(|dep| dep.get("modId").and_then(|v| v.as_str()))
+                        .map(|id| id.to_string())
+                        .collect();
+                    break;
+                }
+            }
+            // 依赖可以是 ID 字符串或 `{ "id": ... }` 对象, 可选依赖带有 `optional`
+            "quilt.mod.json" => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                if let Ok(json) = serde_json::from_str::<Value>(&contents)
+                    && let Some(depends) = json
+                        .get("quilt_loader")
+                        .and_then(|ql| ql.get("depends"))
+                        .and_then(|d| d.as_array())
+                {
+                    dependencies = depends
+                        .iter()
+                        .filter(|dep| dep.get("optional").and_then(|v| v.as_bool()) != Some(true))
+                        .filter_map(|dep| dep.as_str().or_else(|| dep.get("id")?.as_str()))
+                        .map(|id| id.to_string())
+                        .collect();
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    dependencies.retain(|id| !PLATFORM_DEPENDENCIES.contains(&id.as_str()));
+    dependencies.sort();
+    dependencies.dedup();
+    Ok(dependencies)
+}
+
+/// 将字符串或字符串数组形式的版本范围合并为以 ` || ` 分隔的字符串
+fn join_ranges(value: &Value) -> Option<String> {
+    match value {
+        Value::String(range) => Some(range.clone()),
+        Value::Array(ranges) => Some(
+            ranges
+                .iter()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(" || "),
+        ),
+        _ => None,
+    }
+}
+
+/// 加载器在各平台 API 中使用的名称
+pub fn loader_name(loader: &ModLoader) -> Option<&'static str> {
+    match loader {
+        ModLoader::Fabric => Some("fabric"),
+        ModLoader::Forge => Some("forge"),
+        ModLoader::Quilt => Some("quilt"),
+        ModLoader::None => None,
+    }
+}
+
 /// 判断Jar文件是否为有效的Mod文件
 pub fn is_valid_mod(jar_file: &fs::DirEntry) -> bool {
     let path = jar_file.path();
-    match detect_mod(&path) {
-        Ok(Some(_)) => true,
-        _ => false,
-    }
-}
\ No newline at end of file
+    matches!(detect_mod(&path), Ok(Some(_)))
+}
@@ -0,0 +1,151 @@
+use super::output::{self, Verbosity};
+use crate::config::LogConfig;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 写入日志文件的日志器, 详细模式下同时输出到标准错误
+/// 每条记录包含时间, 级别, 来源模块与内容, 文件超过大小上限时轮换为 `.1`, `.2`...
+struct FileLogger {
+    file: Mutex<Option<LogFile>>,
+    /// 写入日志文件的最低级别
+    level: LevelFilter,
+}
+
+struct LogFile {
+    path: PathBuf,
+    /// 轮换期间为 None
+    file: Option<File>,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+/// 初始化全局日志器, 只应调用一次
+/// 日志文件无法打开时仍在详细模式下输出到标准错误, 并返回错误
+pub fn init(config: &LogConfig) -> io::Result<()> {
+    let (file, result) = if config.enabled {
+        match LogFile::open(&config.file, config.max_size, config.max_files) {
+            Ok(file) => (Some(file), Ok(())),
+            Err(e) => (None, Err(e)),
+        }
+    } else {
+        (None, Ok(()))
+    };
+    let level = if file.is_some() {
+        config.level
+    } else {
+        LevelFilter::Off
+    };
+    let logger = FileLogger {
+        file: Mutex::new(file),
+        level,
+    };
+    // 详细模式下至少输出调试信息
+    let max_level = if output::verbosity() == Verbosity::Verbose {
+        level.max(LevelFilter::Debug)
+    } else {
+        level
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+    result
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // 依赖库的调试信息过多, 只在 trace 级别记录
+        let ours = metadata.target().starts_with(env!("CARGO_CRATE_NAME"));
+        metadata.level() <= log::max_level()
+            && (ours || metadata.level() <= Level::Info || log::max_level() == LevelFilter::Trace)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if record.level() <= self.level {
+            let line = format!(
+                "{} {:<5} [{}] {}\n",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(log_file) = file.as_mut()
+                && log_file.write(line.as_bytes()).is_err()
+            {
+                // 写入失败后不再尝试, 避免每条日志都报错
+                *file = None;
+            }
+        }
+        if record.level() <= Level::Debug && output::verbosity() == Verbosity::Verbose {
+            output::suspend(|| eprintln!("[{}] {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log_file) = self.file.lock().unwrap_or_else(|e| e.into_inner()).as_mut()
+            && let Some(file) = log_file.file.as_mut()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl LogFile {
+    fn open(path: &Path, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file: Some(file),
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        match self.file.as_mut() {
+            Some(file) => file.write_all(line)?,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        }
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// 依次将 `.log` 重命名为 `.log.1`, `.log.1` 重命名为 `.log.2`..., 删除超出数量的旧文件
+    fn rotate(&mut self) -> io::Result<()> {
+        let numbered = |n: usize| {
+            let mut name = self.path.as_os_str().to_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+        // Windows 上无法重命名已打开的文件, 先关闭
+        self.file = None;
+        if self.max_files > 1 {
+            let _ = fs::remove_file(numbered(self.max_files - 1));
+            for n in (1..self.max_files - 1).rev() {
+                let _ = fs::rename(numbered(n), numbered(n + 1));
+            }
+            fs::rename(&self.path, numbered(1))?;
+        }
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?,
+        );
+        self.size = 0;
+        Ok(())
+    }
+}
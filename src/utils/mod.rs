@@ -1,2 +1,9 @@
+pub mod format;
+pub mod hash;
 pub mod loader;
+pub mod logging;
+pub mod ordering;
+pub mod output;
+#[cfg(test)]
+pub mod testing;
 pub mod version;
@@ -0,0 +1,395 @@
+use regex::Regex;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+/// 比较两个 Mod 版本号
+/// Fabric/Quilt 按 SemVer 比较, Forge 按 Maven ComparableVersion 规则比较
+/// 版本号先去除名称前缀与 `mc1.20.4-` 这类游戏版本前缀, 无法可靠比较时返回 None
+pub fn compare_mod_versions(a: &str, b: &str, loader: &str) -> Option<Ordering> {
+    let (a, b) = (normalize(a)?, normalize(b)?);
+    match loader {
+        "fabric" | "quilt" => compare_semver(&a, &b).or_else(|| Some(compare_maven(&a, &b))),
+        _ => Some(compare_maven(&a, &b)),
+    }
+}
+
+/// 提取版本号主体: 取最后一个空格后的部分 (CurseForge 的显示名称如 `Sodium 0.5.8`),
+/// 去除 `v` 前缀与 `mc1.20.4-` 前缀; 结果不以数字开头时返回 None
+fn normalize(version: &str) -> Option<String> {
+    static MC_PREFIX: OnceLock<Regex> = OnceLock::new();
+    let re =
+        MC_PREFIX.get_or_init(|| Regex::new(r"^mc\d+\.\d+(\.\d+)?[-_+]").expect("valid regex"));
+    let version = version.split_whitespace().last()?.to_lowercase();
+    let version = re.replace(&version, "").to_string();
+    let version = version
+        .strip_prefix('v')
+        .map(str::to_string)
+        .unwrap_or(version);
+    version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then_some(version)
+}
+
+/// SemVer 比较: 主体为任意数量的数字分段, 预发布版本低于正式版本, 构建元数据 (`+...`) 不参与比较
+/// 不符合格式时返回 None
+pub fn compare_semver(a: &str, b: &str) -> Option<Ordering> {
+    let (a_core, a_pre) = parse_semver(a)?;
+    let (b_core, b_pre) = parse_semver(b)?;
+    for i in 0..a_core.len().max(b_core.len()) {
+        let ordering = a_core.get(i).unwrap_or(&0).cmp(b_core.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return Some(ordering);
+        }
+    }
+    Some(match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_prerelease(a, b),
+    })
+}
+
+fn parse_semver(version: &str) -> Option<(Vec<u64>, Option<&str>)> {
+    let version = version.split('+').next()?;
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    let core = core
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((core, pre))
+}
+
+/// 预发布标识逐段比较: 数字按数值, 数字低于字母, 前缀相同时较短者更低
+fn compare_prerelease(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => {
+                let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// Maven ComparableVersion 中的一段
+#[derive(Debug, PartialEq)]
+enum Item {
+    Int(u64),
+    Qualifier(String),
+}
+
+/// Maven ComparableVersion 比较 (简化版): 按 `.`, `-` 与数字/字母边界分段
+/// 限定符顺序为 alpha < beta < milestone < rc < snapshot < 正式版 < sp < 其他
+pub fn compare_maven(a: &str, b: &str) -> Ordering {
+    let (a, b) = (maven_items(a), maven_items(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => compare_items(a, b),
+            (Some(a), None) => compare_to_null(a),
+            (None, Some(b)) => compare_to_null(b).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn maven_items(version: &str) -> Vec<Item> {
+    let version = version.to_lowercase();
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut push = |token: &mut String, next_is_digit: bool| {
+        if token.is_empty() {
+            return;
+        }
+        let item = match token.parse::<u64>() {
+            Ok(n) => Item::Int(n),
+            Err(_) => Item::Qualifier(canonical_qualifier(token, next_is_digit)),
+        };
+        items.push(item);
+        token.clear();
+    };
+    let chars: Vec<char> = version.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if matches!(c, '.' | '-' | '_' | '+') {
+            push(&mut current, false);
+            continue;
+        }
+        if let Some(&last) = current.chars().last().as_ref()
+            && last.is_ascii_digit() != c.is_ascii_digit()
+        {
+            push(&mut current, c.is_ascii_digit());
+        }
+        current.push(c);
+        if i + 1 == chars.len() {
+            push(&mut current, false);
+        }
+    }
+    // 去除末尾的 0 与空限定符, 使 1.0 与 1 相等
+    while items
+        .last()
+        .is_some_and(|item| compare_to_null(item) == Ordering::Equal)
+    {
+        items.pop();
+    }
+    items
+}
+
+/// 统一限定符别名, `a1`/`b1`/`m1` 中紧跟数字的单字母视为 alpha/beta/milestone
+fn canonical_qualifier(token: &str, next_is_digit: bool) -> String {
+    match token {
+        "a" if next_is_digit => "alpha",
+        "b" if next_is_digit => "beta",
+        "m" if next_is_digit => "milestone",
+        "cr" => "rc",
+        "ga" | "final" | "release" => "",
+        other => other,
+    }
+    .to_string()
+}
+
+fn qualifier_rank(qualifier: &str) -> usize {
+    match qualifier {
+        "alpha" => 0,
+        "beta" => 1,
+        "milestone" => 2,
+        "rc" => 3,
+        "snapshot" => 4,
+        "" => 5,
+        "sp" => 6,
+        _ => 7,
+    }
+}
+
+fn compare_items(a: &Item, b: &Item) -> Ordering {
+    match (a, b) {
+        (Item::Int(a), Item::Int(b)) => a.cmp(b),
+        (Item::Int(_), Item::Qualifier(_)) => Ordering::Greater,
+        (Item::Qualifier(_), Item::Int(_)) => Ordering::Less,
+        (Item::Qualifier(a), Item::Qualifier(b)) => qualifier_rank(a)
+            .cmp(&qualifier_rank(b))
+            .then_with(|| a.cmp(b)),
+    }
+}
+
+/// 与缺失的分段比较: 缺失等同于 0 或正式版
+fn compare_to_null(item: &Item) -> Ordering {
+    match item {
+        Item::Int(n) => n.cmp(&0),
+        Item::Qualifier(q) => qualifier_rank(q).cmp(&qualifier_rank("")),
+    }
+}
+
+/// 比较两个 RFC 3339 时间, 无法解析时按字符串比较
+pub fn compare_timestamps(a: &str, b: &str) -> Ordering {
+    match (parse_timestamp(a), parse_timestamp(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// 解析 RFC 3339 时间为 (UTC 秒数, 纳秒)
+fn parse_timestamp(timestamp: &str) -> Option<(i64, u32)> {
+    static RFC3339: OnceLock<Regex> = OnceLock::new();
+    let re = RFC3339.get_or_init(|| {
+        Regex::new(
+            r"^(\d{4})-(\d{2})-(\d{2})[Tt ](\d{2}):(\d{2}):(\d{2})(?:\.(\d{1,9})\d*)?([Zz]|[+-]\d{2}:?\d{2})?$",
+        )
+        .expect("valid regex")
+    });
+    let caps = re.captures(timestamp.trim())?;
+    let number = |i: usize| caps[i].parse::<i64>().ok();
+    let days = days_from_civil(number(1)?, number(2)?, number(3)?);
+    let mut seconds = days * 86_400 + number(4)? * 3_600 + number(5)? * 60 + number(6)?;
+    let nanos = caps
+        .get(7)
+        .and_then(|f| format!("{:0<9}", f.as_str()).parse::<u32>().ok())
+        .unwrap_or(0);
+    if let Some(offset) = caps.get(8).map(|o| o.as_str())
+        && !offset.eq_ignore_ascii_case("z")
+    {
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let digits = offset[1..].replace(':', "");
+        let hours = digits[..2].parse::<i64>().ok()?;
+        let minutes = digits[2..].parse::<i64>().ok()?;
+        seconds -= sign * (hours * 3_600 + minutes * 60);
+    }
+    Some((seconds, nanos))
+}
+
+/// 公历日期距 1970-01-01 的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_mod_versions, compare_timestamps, days_from_civil};
+    use std::cmp::Ordering;
+
+    /// 检查 `versions` 按给定加载器的规则严格递增
+    fn assert_ascending(versions: &[&str], loader: &str) {
+        for pair in versions.windows(2) {
+            assert_eq!(
+                compare_mod_versions(pair[0], pair[1], loader),
+                Some(Ordering::Less),
+                "{} < {} ({})",
+                pair[0],
+                pair[1],
+                loader
+            );
+            assert_eq!(
+                compare_mod_versions(pair[1], pair[0], loader),
+                Some(Ordering::Greater),
+                "{} > {} ({})",
+                pair[1],
+                pair[0],
+                loader
+            );
+        }
+    }
+
+    #[test]
+    fn orders_semver_prereleases() {
+        assert_ascending(
+            &[
+                "1.0.0-alpha",
+                "1.0.0-beta.2",
+                "1.0.0-beta.11",
+                "1.0.0",
+                "1.0.1",
+            ],
+            "fabric",
+        );
+        // 构建元数据不参与比较
+        assert_eq!(
+            compare_mod_versions("1.0.0+build.1", "1.0.0+build.2", "quilt"),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn orders_maven_qualifiers() {
+        assert_ascending(
+            &[
+                "1.0-alpha1",
+                "1.0-beta1",
+                "1.0-rc1",
+                "1.0-snapshot",
+                "1.0",
+                "1.0-sp1",
+            ],
+            "forge",
+        );
+        assert_ascending(&["1.0-a1", "1.0-alpha2", "1.0-b1", "1.0-cr1"], "neoforge");
+        assert_ascending(&["1.9", "1.10", "1.10.1"], "forge");
+    }
+
+    #[test]
+    fn treats_trailing_zeros_as_equal() {
+        for loader in ["fabric", "forge"] {
+            assert_eq!(
+                compare_mod_versions("1.0", "1.0.0", loader),
+                Some(Ordering::Equal),
+                "{}",
+                loader
+            );
+        }
+        assert_eq!(
+            compare_mod_versions("1.0-final", "1.0", "forge"),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn normalizes_prefixes() {
+        for (a, b, expected) in [
+            ("mc1.20.4-2.1", "2.0", Ordering::Greater),
+            ("mc1.20.4-2.1", "2.1", Ordering::Equal),
+            ("mc1.20-2.1", "mc1.19.2-2.1", Ordering::Equal),
+            ("Sodium 0.5.8", "0.5.10", Ordering::Less),
+            ("v1.2", "1.2", Ordering::Equal),
+        ] {
+            assert_eq!(
+                compare_mod_versions(a, b, "fabric"),
+                Some(expected),
+                "{} vs {}",
+                a,
+                b
+            );
+        }
+        assert_eq!(compare_mod_versions("latest", "1.0", "fabric"), None);
+    }
+
+    #[test]
+    fn compares_timestamps_across_offsets() {
+        for (a, b, expected) in [
+            (
+                "2024-01-01T10:00:00+02:00",
+                "2024-01-01T08:00:00Z",
+                Ordering::Equal,
+            ),
+            (
+                "2024-01-01T00:30:00-01:00",
+                "2024-01-01T01:00:00Z",
+                Ordering::Greater,
+            ),
+            (
+                "2024-01-01T08:00:00+0130",
+                "2024-01-01T06:30:00z",
+                Ordering::Equal,
+            ),
+            (
+                "2024-01-01T08:00:00.5Z",
+                "2024-01-01T08:00:00.499999Z",
+                Ordering::Greater,
+            ),
+            (
+                "2024-01-01T08:00:00.1234567891Z",
+                "2024-01-01T08:00:00.123456789Z",
+                Ordering::Equal,
+            ),
+            (
+                "2023-12-31T23:59:59.9Z",
+                "2024-01-01 00:00:00Z",
+                Ordering::Less,
+            ),
+            // 无法解析时按字符串比较
+            ("unknown", "2024-01-01T00:00:00Z", Ordering::Greater),
+        ] {
+            assert_eq!(compare_timestamps(a, b), expected, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn counts_days_from_the_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+        assert_eq!(days_from_civil(2024, 3, 1), 19_783);
+    }
+}
@@ -0,0 +1,115 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// 输出详细程度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// 只输出错误与最终统计
+    Quiet,
+    #[default]
+    Normal,
+    /// 额外输出 HTTP 请求与重试详情
+    Verbose,
+}
+
+impl Verbosity {
+    /// 解析配置文件中的名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "quiet" => Some(Verbosity::Quiet),
+            "normal" => Some(Verbosity::Normal),
+            "verbose" => Some(Verbosity::Verbose),
+            _ => None,
+        }
+    }
+}
+
+/// 标准输出的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 供人阅读的文字与进度条
+    #[default]
+    Text,
+    /// 每行一个 JSON 事件, 提问通过标准输入回答, 供启动器等程序调用
+    JsonLines,
+}
+
+impl OutputFormat {
+    /// 解析命令行中的名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json-lines" => Some(OutputFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// 全局输出详细程度, 由命令行与配置文件决定
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// 终端底部当前显示的进度条行数, 输出其他内容前需先清除
+static BAR_LINES: Mutex<usize> = Mutex::new(0);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+/// 是否显示进度条: 非安静模式且标准输出为终端, 否则输出纯文本
+pub fn progress_enabled() -> bool {
+    verbosity() != Verbosity::Quiet && io::stdout().is_terminal()
+}
+
+/// 清除进度条后执行 `f` (通常为输出文字), 进度条在下次刷新时重新绘制
+pub fn suspend<T>(f: impl FnOnce() -> T) -> T {
+    let _pause = pause();
+    f()
+}
+
+/// 清除进度条, 返回值存活期间不重绘 (例如等待用户输入时)
+pub fn pause() -> MutexGuard<'static, usize> {
+    let mut lines = lock_bars();
+    clear_lines(&mut lines);
+    lines
+}
+
+/// 用 `bars` 替换终端底部的进度条
+pub fn draw_bars(bars: &[String]) {
+    let mut lines = lock_bars();
+    clear_lines(&mut lines);
+    let mut stdout = io::stdout().lock();
+    for bar in bars {
+        let _ = writeln!(stdout, "{}", bar);
+    }
+    let _ = stdout.flush();
+    *lines = bars.len();
+}
+
+/// 清除终端底部的进度条
+pub fn clear_bars() {
+    clear_lines(&mut lock_bars());
+}
+
+fn lock_bars() -> MutexGuard<'static, usize> {
+    BAR_LINES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn clear_lines(lines: &mut usize) {
+    if *lines == 0 {
+        return;
+    }
+    // 光标上移并清除到屏幕末尾
+    let mut stdout = io::stdout().lock();
+    let _ = write!(stdout, "\x1b[{}F\x1b[J", lines);
+    let _ = stdout.flush();
+    *lines = 0;
+}
@@ -0,0 +1,52 @@
+use serde_json::json;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// 测试用的临时目录, 创建时清空, 离开作用域时删除
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// `name` 需在所有测试中唯一, 避免并行测试共用目录
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("MiraMigrator-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// 在目录中创建子目录
+    pub fn subdir(&self, name: &str) -> PathBuf {
+        let path = self.0.join(name);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 只声明了 Mod ID, 版本与 Minecraft 版本范围的 Fabric Jar 文件
+pub fn fabric_jar(mod_id: &str, version: &str, minecraft: &str) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("fabric.mod.json", SimpleFileOptions::default())
+        .unwrap();
+    let metadata = json!({
+        "schemaVersion": 1,
+        "id": mod_id,
+        "version": version,
+        "depends": { "minecraft": minecraft },
+    });
+    zip.write_all(metadata.to_string().as_bytes()).unwrap();
+    zip.finish().unwrap().into_inner()
+}
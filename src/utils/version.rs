@@ -1,9 +1,215 @@
-/// 验证版本格式是否合法
+use std::cmp::Ordering;
+
+/// Minecraft 正式版本及其网络协议号, 协议号相同的版本互相兼容
+/// 仅列出与其他版本共用协议号的版本
+const PROTOCOL_VERSIONS: [(&str, u32); 40] = [
+    ("1.7.6", 5),
+    ("1.7.7", 5),
+    ("1.7.8", 5),
+    ("1.7.9", 5),
+    ("1.7.10", 5),
+    ("1.8", 47),
+    ("1.8.1", 47),
+    ("1.8.2", 47),
+    ("1.8.3", 47),
+    ("1.8.4", 47),
+    ("1.8.5", 47),
+    ("1.8.6", 47),
+    ("1.8.7", 47),
+    ("1.8.8", 47),
+    ("1.8.9", 47),
+    ("1.9.3", 110),
+    ("1.9.4", 110),
+    ("1.10", 210),
+    ("1.10.1", 210),
+    ("1.10.2", 210),
+    ("1.11.1", 316),
+    ("1.11.2", 316),
+    ("1.16.4", 754),
+    ("1.16.5", 754),
+    ("1.18", 757),
+    ("1.18.1", 757),
+    ("1.19.1", 760),
+    ("1.19.2", 760),
+    ("1.20", 763),
+    ("1.20.1", 763),
+    ("1.20.3", 765),
+    ("1.20.4", 765),
+    ("1.20.5", 766),
+    ("1.20.6", 766),
+    ("1.21", 767),
+    ("1.21.1", 767),
+    ("1.21.2", 768),
+    ("1.21.3", 768),
+    ("1.21.7", 772),
+    ("1.21.8", 772),
+];
+
+/// 验证版本格式是否合法, 接受 `1.21` 与 `1.21.1` 两种形式
 pub fn validate_version(version: &str) -> bool {
     if version.is_empty() {
-        return false;
+        false
     } else {
-        let verion_re = regex::Regex::new(r"^\d+\.\d+\.\d+$").unwrap();
-        return verion_re.is_match(version);
+        let verion_re = regex::Regex::new(r"^\d+\.\d+(\.\d+)?$").expect("valid regex");
+        verion_re.is_match(version)
+    }
+}
+
+fn protocol(version: &str) -> Option<u32> {
+    PROTOCOL_VERSIONS
+        .iter()
+        .find(|(v, _)| *v == version.trim())
+        .map(|(_, protocol)| *protocol)
+}
+
+/// 与该版本协议兼容的其他版本, 不含版本本身
+pub fn compatible_versions(version: &str) -> Vec<&'static str> {
+    let Some(target) = protocol(version) else {
+        return Vec::new();
+    };
+    PROTOCOL_VERSIONS
+        .iter()
+        .filter(|(v, protocol)| *protocol == target && *v != version.trim())
+        .map(|(v, _)| *v)
+        .collect()
+}
+
+/// 按数字分段比较版本号, 缺失的分段视为 0, 忽略 `-` 或 `+` 之后的后缀
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (numeric_parts(a), numeric_parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn numeric_parts(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| {
+            part.chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// 判断 Minecraft 版本是否满足 Mod 声明的版本范围
+/// 支持 Maven 区间 (Forge, 如 `[1.20,1.21)`) 与 Fabric/Quilt 版本谓词
+/// (如 `>=1.20 <1.21`, `~1.20.4`, `1.20.x`), 多个范围以 `||` 分隔
+pub fn version_in_range(version: &str, range: &str) -> bool {
+    range.split("||").any(|range| {
+        let range = range.trim();
+        if range.starts_with('[') || range.starts_with('(') {
+            maven_range_matches(version, range)
+        } else {
+            range
+                .split_whitespace()
+                .all(|predicate| predicate_matches(version, predicate))
+        }
+    })
+}
+
+/// Maven 区间, 可包含多个以逗号连接的区间, 如 `[1.19,1.20),[1.20.1,)`
+fn maven_range_matches(version: &str, range: &str) -> bool {
+    let mut rest = range;
+    while let Some(start) = rest.find(['[', '(']) {
+        let Some(end) = rest[start..].find([']', ')']).map(|i| start + i) else {
+            return false;
+        };
+        let inclusive_low = rest[start..].starts_with('[');
+        let inclusive_high = rest[end..].starts_with(']');
+        let interval = &rest[start + 1..end];
+        let matched = match interval.split_once(',') {
+            Some((low, high)) => {
+                let low = low.trim();
+                let high = high.trim();
+                (low.is_empty()
+                    || match compare_versions(version, low) {
+                        Ordering::Greater => true,
+                        Ordering::Equal => inclusive_low,
+                        Ordering::Less => false,
+                    })
+                    && (high.is_empty()
+                        || match compare_versions(version, high) {
+                            Ordering::Less => true,
+                            Ordering::Equal => inclusive_high,
+                            Ordering::Greater => false,
+                        })
+            }
+            // `[1.20.1]` 表示精确版本
+            None => compare_versions(version, interval) == Ordering::Equal,
+        };
+        if matched {
+            return true;
+        }
+        rest = &rest[end + 1..];
+    }
+    false
+}
+
+/// Fabric/Quilt 的单个版本谓词
+fn predicate_matches(version: &str, predicate: &str) -> bool {
+    if predicate == "*" {
+        return true;
+    }
+    let (operator, target) = match predicate.find(|c: char| c.is_ascii_digit()) {
+        Some(i) => predicate.split_at(i),
+        None => return false,
+    };
+    // `1.20.x` 形式的通配符等价于 `~1.20`
+    let (operator, target) = match target.find(['x', 'X', '*']) {
+        Some(i) if operator.is_empty() => ("~", target[..i].trim_end_matches('.')),
+        _ => (operator, target),
+    };
+    let ordering = compare_versions(version, target);
+    match operator {
+        ">=" => ordering != Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        "<" => ordering == Ordering::Less,
+        "" | "=" => ordering == Ordering::Equal,
+        // `~1.20.4`: 前两段相同且不低于目标; `^1.20`: 主版本相同且不低于目标
+        "~" | "^" => {
+            let keep = if operator == "^" {
+                1
+            } else {
+                numeric_parts(target).len().clamp(1, 2)
+            };
+            ordering != Ordering::Less
+                && numeric_parts(version)
+                    .iter()
+                    .take(keep)
+                    .eq(numeric_parts(target).iter().take(keep))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compatible_versions;
+
+    #[test]
+    fn lists_protocol_compatible_versions() {
+        assert_eq!(compatible_versions("1.20.1"), ["1.20"]);
+        assert_eq!(compatible_versions(" 1.21 "), ["1.21.1"]);
+        assert_eq!(
+            compatible_versions("1.8"),
+            [
+                "1.8.1", "1.8.2", "1.8.3", "1.8.4", "1.8.5", "1.8.6", "1.8.7", "1.8.8", "1.8.9"
+            ]
+        );
+        // 没有共用协议号的版本
+        assert!(compatible_versions("1.20.2").is_empty());
     }
 }